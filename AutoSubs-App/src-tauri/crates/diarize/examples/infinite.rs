@@ -9,12 +9,13 @@ use diarize::raw::{EmbeddingExtractor, EmbeddingManager};
 
 fn process_segment(
     segment: diarize::raw::Segment,
+    samples: &[i16],
     embedding_extractor: &mut EmbeddingExtractor,
     embedding_manager: &mut EmbeddingManager,
     search_threshold: f32,
 ) -> Result<(), eyre::Report> {
     let embedding_result: Vec<f32> = embedding_extractor
-        .compute(&segment.samples)
+        .compute(&samples[segment.range.clone()])
         .unwrap();
 
     let speaker = embedding_manager
@@ -54,6 +55,7 @@ fn main() -> Result<(), eyre::Report> {
         if let Ok(segment) = segment {
             if let Err(error) = process_segment(
                 segment,
+                &samples,
                 &mut embedding_extractor,
                 &mut embedding_manager,
                 search_threshold,
@@ -23,7 +23,7 @@ fn main() {
     for segment in segments {
         match segment {
             Ok(segment) => {
-                if let Ok(embedding) = extractor.compute(&segment.samples) {
+                if let Ok(embedding) = extractor.compute(&samples[segment.range.clone()]) {
                     let speaker = if manager.get_all_speakers().len() == max_speakers {
                         manager
                             .get_best_speaker_match(embedding)
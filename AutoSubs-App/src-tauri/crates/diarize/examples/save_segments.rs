@@ -49,7 +49,7 @@ fn main() {
                     "{}/start_{:.2}_end_{:.2}.wav",
                     output_folder, segment.start, segment.end
                 );
-                write_wav(&segment_file_name, &segment.samples, sample_rate).unwrap();
+                write_wav(&segment_file_name, &samples[segment.range.clone()], sample_rate).unwrap();
                 println!("Created {}", segment_file_name);
             }
             Err(error) => eprintln!("Failed to process segment: {:?}", error),
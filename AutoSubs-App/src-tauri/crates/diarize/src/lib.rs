@@ -7,7 +7,9 @@ mod segment;
 mod wav;
 
 use eyre::{eyre, Result};
+use std::ops::Range;
 use std::path::PathBuf;
+use std::sync::Arc;
 
 #[derive(Clone, Debug)]
 pub struct DiarizeOptions {
@@ -17,14 +19,27 @@ pub struct DiarizeOptions {
     pub max_speakers: usize,
 }
 
+/// A whole recording, shared by every segment cut from it so that long audio
+/// is held in memory once.
+pub type SharedAudio = Arc<Vec<i16>>;
+
 #[derive(Debug, Clone)]
 pub struct SpeechSegment {
     pub start: f64,
     pub end: f64,
-    pub samples: Vec<i16>,
+    /// The recording this segment was cut from.
+    pub audio: SharedAudio,
+    /// The segment's samples within `audio`.
+    pub range: Range<usize>,
     pub speaker_id: Option<String>,
 }
 
+impl SpeechSegment {
+    pub fn samples(&self) -> &[i16] {
+        &self.audio[self.range.clone()]
+    }
+}
+
 pub type ProgressFn<'a> = dyn Fn(i32) + Send + Sync + 'a;
 
 #[doc(hidden)]
@@ -37,13 +52,13 @@ pub mod raw {
 }
 
 pub fn diarize(
-    samples: &[i16],
+    audio: &SharedAudio,
     sample_rate: u32,
     options: &DiarizeOptions,
     progress_callback: Option<&ProgressFn<'_>>,
     is_cancelled: Option<&(dyn Fn() -> bool + Send + Sync)>,
 ) -> Result<Vec<SpeechSegment>> {
-    let mut speech_segments = segment_speech(samples, sample_rate, options)?;
+    let mut speech_segments = segment_speech(audio, sample_rate, options)?;
     label_speakers(
         speech_segments.as_mut_slice(),
         options,
@@ -54,11 +69,11 @@ pub fn diarize(
 }
 
 pub fn segment_speech(
-    samples: &[i16],
+    audio: &SharedAudio,
     sample_rate: u32,
     options: &DiarizeOptions,
 ) -> Result<Vec<SpeechSegment>> {
-    let diarize_segments = segment::get_segments(samples, sample_rate, &options.segment_model_path)?;
+    let diarize_segments = segment::get_segments(audio, sample_rate, &options.segment_model_path)?;
     let mut speech_segments = Vec::new();
 
     for segment in diarize_segments {
@@ -66,7 +81,8 @@ pub fn segment_speech(
         speech_segments.push(SpeechSegment {
             start: segment.start,
             end: segment.end,
-            samples: segment.samples,
+            audio: audio.clone(),
+            range: segment.range,
             speaker_id: None,
         });
     }
@@ -96,7 +112,7 @@ pub fn label_speakers(
             }
         }
 
-        let embedding_result = extractor.compute(&segment.samples);
+        let embedding_result = extractor.compute(segment.samples());
         let speaker = match embedding_result {
             Ok(embedding_vec) => {
                 if embedding_manager.get_all_speakers().len() == options.max_speakers {
@@ -137,13 +153,29 @@ mod tests {
         let segment = SpeechSegment {
             start: 0.0,
             end: 1.0,
-            samples: vec![0; 16_000],
+            audio: Arc::new(vec![0; 16_000]),
+            range: 0..16_000,
             speaker_id: None,
         };
 
         assert_eq!(segment.speaker_id, None);
     }
 
+    #[test]
+    fn segments_read_their_range_of_the_shared_audio() {
+        let audio: SharedAudio = Arc::new((0..100).collect());
+        let segment = SpeechSegment {
+            start: 0.0,
+            end: 0.0,
+            audio: audio.clone(),
+            range: 10..20,
+            speaker_id: None,
+        };
+
+        assert_eq!(segment.samples(), &audio[10..20]);
+        assert!(Arc::ptr_eq(&segment.clone().audio, &audio));
+    }
+
     #[test]
     fn empty_speaker_labeling_does_not_touch_models_or_cancellation() {
         let options = DiarizeOptions {
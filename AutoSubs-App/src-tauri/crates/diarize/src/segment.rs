@@ -3,6 +3,7 @@ use eyre::{Context, ContextCompat, Result};
 use ndarray::{ArrayBase, Axis, IxDyn, ViewRepr};
 use ort::value::TensorRef;
 use std::iter;
+use std::ops::Range;
 use std::{cmp::Ordering, collections::VecDeque, path::Path};
 
 #[derive(Debug, Clone)]
@@ -10,7 +11,8 @@ use std::{cmp::Ordering, collections::VecDeque, path::Path};
 pub struct Segment {
     pub start: f64,
     pub end: f64,
-    pub samples: Vec<i16>,
+    /// Sample indices into the audio passed to [`get_segments`].
+    pub range: Range<usize>,
 }
 
 fn find_max_index(row: ArrayBase<ViewRepr<&f32>, IxDyn>) -> Result<usize> {
@@ -55,13 +57,6 @@ pub fn get_segments<P: AsRef<Path>>(
     let mut last_emitted_offset: usize = 0;
     let mut speech_run: usize = 0;
 
-    // Pad end with silence for full last segment
-    let padded_samples = {
-        let mut padded = Vec::from(samples);
-        padded.extend(vec![0; window_size]);
-        padded
-    };
-
     let mut start_iter = (0..samples.len()).step_by(step_size.max(1));
 
     let mut segments_queue = VecDeque::new();
@@ -71,10 +66,10 @@ pub fn get_segments<P: AsRef<Path>>(
         }
 
         if let Some(start) = start_iter.next() {
-            let end = start + window_size;
-            let window = &padded_samples[start..end];
-
-            let window_f32 = window.iter().map(|&x| x as f32).collect::<Vec<_>>();
+            // Pad the last window with silence rather than copying the audio.
+            let window_f32 = (start..start + window_size)
+                .map(|i| samples.get(i).map_or(0.0, |&x| x as f32))
+                .collect::<Vec<_>>();
 
             // Handle potential errors during the session and input processing
             let tensor = match TensorRef::from_array_view((
@@ -153,12 +148,11 @@ pub fn get_segments<P: AsRef<Path>>(
                                 {
                                     let start_sec = start_idx as f64 / sample_rate as f64;
                                     let end_sec = end_idx as f64 / sample_rate as f64;
-                                    let segment_samples = &samples[start_idx..end_idx];
 
                                     segments_queue.push_back(Segment {
                                         start: start_sec,
                                         end: end_sec,
-                                        samples: segment_samples.to_vec(),
+                                        range: start_idx..end_idx,
                                     });
                                 }
 
@@ -184,11 +178,10 @@ pub fn get_segments<P: AsRef<Path>>(
                 if segment_duration_ms >= min_segment_duration_ms {
                     let start_sec = start_idx as f64 / sample_rate as f64;
                     let end_sec = end_idx as f64 / sample_rate as f64;
-                    let segment_samples = &samples[start_idx..end_idx];
                     segments_queue.push_back(Segment {
                         start: start_sec,
                         end: end_sec,
-                        samples: segment_samples.to_vec(),
+                        range: start_idx..end_idx,
                     });
                 }
             }
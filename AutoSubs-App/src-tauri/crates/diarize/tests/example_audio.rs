@@ -41,7 +41,7 @@ fn example_audio_segments_and_speakers_match_baseline() -> Result<()> {
     let actual = diarize::raw::get_segments(&samples, sample_rate, "segmentation-community-1.onnx")?
         .map(|segment| {
             let segment = segment?;
-            let embedding = extractor.compute(&segment.samples)?;
+            let embedding = extractor.compute(&samples[segment.range.clone()])?;
             let speaker = if manager.get_all_speakers().len() == max_speakers {
                 manager.get_best_speaker_match(embedding)?.to_string()
             } else {
@@ -0,0 +1,106 @@
+use eyre::{Result, eyre};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use transcription_engine::{Callbacks, Engine, EngineConfig, Segment, SegmentStage, StreamOptions, TranscribeOptions};
+
+struct CliArgs {
+    wav_path: PathBuf,
+    model: String,
+    lang: Option<String>,
+    idle_secs: u64,
+}
+
+fn print_usage(program: &str) {
+    eprintln!("Usage: {program} <wav-path> [options]");
+    eprintln!("\nFollows a 16 kHz mono 16-bit WAV while it is being recorded, e.g.:");
+    eprintln!("  ffmpeg -f pulse -i default -ar 16000 -ac 1 live.wav");
+    eprintln!("\nOptions:");
+    eprintln!("  --model <name>                 Model to use (default: tiny)");
+    eprintln!("  --lang <code>                  Source language code (default: auto)");
+    eprintln!("  --idle <secs>                  Stop once the file hasn't grown for this long (default: 5)");
+}
+
+fn parse_args() -> Result<CliArgs> {
+    let mut args = std::env::args().skip(1);
+    let program = std::env::args().next().unwrap_or_else(|| "cargo run --example stream --".into());
+
+    let mut wav_path: Option<PathBuf> = None;
+    let mut model = "tiny".to_string();
+    let mut lang = Some("auto".to_string());
+    let mut idle_secs = 5;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--model" => {
+                model = args.next().ok_or_else(|| eyre!("missing value for --model"))?;
+            }
+            "--lang" => {
+                lang = Some(args.next().ok_or_else(|| eyre!("missing value for --lang"))?);
+            }
+            "--idle" => {
+                let raw = args.next().ok_or_else(|| eyre!("missing value for --idle"))?;
+                idle_secs = raw.parse()?;
+            }
+            "-h" | "--help" => {
+                print_usage(&program);
+                std::process::exit(0);
+            }
+            value if value.starts_with('-') => {
+                return Err(eyre!("unknown option: {value}"));
+            }
+            value => {
+                if wav_path.is_some() {
+                    return Err(eyre!("unexpected extra positional argument: {value}"));
+                }
+                wav_path = Some(PathBuf::from(value));
+            }
+        }
+    }
+
+    let wav_path = match wav_path {
+        Some(path) => path,
+        None => {
+            print_usage(&program);
+            return Err(eyre!("missing required <wav-path> argument"));
+        }
+    };
+
+    Ok(CliArgs { wav_path, model, lang, idle_secs })
+}
+
+fn on_new_segment(index: usize, segment: &Segment, stage: SegmentStage) {
+    match stage {
+        SegmentStage::Partial => println!("  … #{index} {}", segment.text),
+        _ => println!("[{:>7.2}s] #{index} {}", segment.start, segment.text),
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    whisper_rs::install_logging_hooks();
+
+    let args = parse_args()?;
+
+    let options = TranscribeOptions {
+        model: args.model,
+        lang: args.lang,
+        ..Default::default()
+    };
+
+    let callbacks = Callbacks {
+        speakers_identified: None,
+        progress: None,
+        new_segment_callback: Some(Arc::new(on_new_segment)),
+        is_cancelled: None,
+    };
+
+    let mut engine = Engine::new(EngineConfig::default());
+    let mut session = engine.start_stream(options, StreamOptions::default(), Some(callbacks)).await?;
+    session.tail_wav(&args.wav_path, Duration::from_secs(args.idle_secs)).await?;
+    let (segments, language) = session.finish().await?;
+
+    println!("\nTranscribed {} segments (language: {})", segments.len(), language);
+
+    Ok(())
+}
@@ -129,6 +129,7 @@ fn on_new_segment(_index: usize, segment: &Segment, stage: SegmentStage) {
         SegmentStage::Transcribe => "",
         SegmentStage::Translate => "🌐 ",
         SegmentStage::Align => "= ",
+        SegmentStage::Partial => "… ",
     };
     match &segment.speaker_id {
         Some(speaker) => println!("{prefix}[{speaker}] {}", segment.text),
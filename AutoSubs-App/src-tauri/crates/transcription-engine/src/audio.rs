@@ -1,26 +1,43 @@
 use eyre::{Result, bail, WrapErr};
 use hound::{WavReader, SampleFormat, WavSpec, WavWriter};
+use std::f64::consts::PI;
 
-pub fn read_wav(path: &str) -> Result<Vec<i16>> {
+/// Read the WAV file at `path` as mono 16-bit PCM, returned with its sample
+/// rate. Channels are averaged, and other integer depths and float files are
+/// converted.
+pub fn read_wav(path: &str) -> Result<(Vec<i16>, u32)> {
     tracing::debug!("wav reader read from {:?}", path);
     let reader = WavReader::open(path).context("failed to read file")?;
     tracing::debug!("parsing {}", path);
 
-    let channels = reader.spec().channels;
-    if reader.spec().channels != 1 {
-        bail!("expected mono audio file and found {} channels!", channels);
-    }
-    if reader.spec().sample_format != SampleFormat::Int {
-        bail!("expected integer sample format");
-    }
-    if reader.spec().sample_rate != 16000 {
-        bail!("expected 16KHz sample rate");
-    }
-    if reader.spec().bits_per_sample != 16 {
-        bail!("expected 16 bits per sample");
+    let spec = reader.spec();
+    let channels = spec.channels as usize;
+    if channels == 0 {
+        bail!("WAV file has no channels");
     }
+    let len = reader.len() as usize;
+    // Every sample scaled to -1.0..1.0, whatever the file holds.
+    let values: Box<dyn Iterator<Item = hound::Result<f64>>> = match spec.sample_format {
+        SampleFormat::Float => Box::new(reader.into_samples::<f32>().map(|s| s.map(f64::from))),
+        SampleFormat::Int => {
+            let scale = 1.0 / (1u64 << (spec.bits_per_sample.clamp(1, 32) - 1)) as f64;
+            Box::new(reader.into_samples::<i32>().map(move |s| s.map(|v| v as f64 * scale)))
+        }
+    };
 
-    reader.into_samples::<i16>().map(|x| x.context("sample")).collect()
+    // Sized up front: a multi-hour recording shouldn't be reallocated (and
+    // briefly held twice) while it is read.
+    let mut samples = Vec::with_capacity(len / channels);
+    let (mut frame, mut filled) = (0.0, 0);
+    for value in values {
+        frame += value.context("sample")?;
+        filled += 1;
+        if filled == channels {
+            samples.push(to_i16(frame / channels as f64));
+            (frame, filled) = (0.0, 0);
+        }
+    }
+    Ok((samples, spec.sample_rate))
 }
 
 pub fn write_wav(path: &str, samples: &[i16]) -> Result<()> {
@@ -35,4 +52,189 @@ pub fn write_wav(path: &str, samples: &[i16]) -> Result<()> {
         writer.write_sample(sample).context("failed to write sample")?;
     }
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Sample rate every stage of the pipeline works at.
+pub const SAMPLE_RATE: u32 = 16_000;
+
+/// Mono PCM held in memory, at any sample rate, for
+/// [`crate::Engine::transcribe_samples`].
+#[derive(Clone, Copy, Debug)]
+pub enum Samples<'a> {
+    I16(&'a [i16]),
+    /// Scaled to -1.0..1.0.
+    F32(&'a [f32]),
+}
+
+impl<'a> From<&'a [i16]> for Samples<'a> {
+    fn from(samples: &'a [i16]) -> Self {
+        Samples::I16(samples)
+    }
+}
+
+impl<'a> From<&'a [f32]> for Samples<'a> {
+    fn from(samples: &'a [f32]) -> Self {
+        Samples::F32(samples)
+    }
+}
+
+impl<'a> From<&'a Vec<i16>> for Samples<'a> {
+    fn from(samples: &'a Vec<i16>) -> Self {
+        Samples::I16(samples)
+    }
+}
+
+impl<'a> From<&'a Vec<f32>> for Samples<'a> {
+    fn from(samples: &'a Vec<f32>) -> Self {
+        Samples::F32(samples)
+    }
+}
+
+/// `samples` recorded at `sample_rate` as the 16 kHz i16 audio the pipeline
+/// reads, resampled if needed.
+pub fn to_pipeline_audio(samples: Samples<'_>, sample_rate: u32) -> Result<Vec<i16>> {
+    if sample_rate == 0 {
+        bail!("sample rate must be positive");
+    }
+    Ok(match samples {
+        Samples::I16(s) if sample_rate == SAMPLE_RATE => s.to_vec(),
+        Samples::F32(s) if sample_rate == SAMPLE_RATE => s.iter().map(|&x| to_i16(x as f64)).collect(),
+        Samples::I16(s) => resample(s.len(), |i| s[i] as f64 / 32768.0, sample_rate, SAMPLE_RATE),
+        Samples::F32(s) => resample(s.len(), |i| s[i] as f64, sample_rate, SAMPLE_RATE),
+    })
+}
+
+/// [`to_pipeline_audio`] for a buffer the caller is done with: 16 kHz audio is
+/// passed through as is rather than copied.
+pub fn to_pipeline_audio_owned(samples: Vec<i16>, sample_rate: u32) -> Result<Vec<i16>> {
+    if sample_rate == SAMPLE_RATE {
+        return Ok(samples);
+    }
+    to_pipeline_audio(Samples::I16(&samples), sample_rate)
+}
+
+fn to_i16(x: f64) -> i16 {
+    // `as` saturates, so clipped input stays at full scale.
+    (x * 32768.0).round() as i16
+}
+
+/// Windowed-sinc (Hann) resampling of the `len` samples read by `at`, low-passed
+/// below the lower of the two Nyquist frequencies so downsampling doesn't alias.
+/// The kernel is tabulated for [`PHASES`] fractional offsets, which is plenty
+/// for speech.
+fn resample(len: usize, at: impl Fn(usize) -> f64, from: u32, to: u32) -> Vec<i16> {
+    const ZERO_CROSSINGS: f64 = 8.0;
+    const PHASES: usize = 256;
+
+    let step = from as f64 / to as f64; // input samples per output sample
+    let cutoff = (1.0 / step).min(1.0);
+    let half_width = ZERO_CROSSINGS / cutoff;
+    let reach = half_width.ceil() as isize;
+
+    let sinc = |x: f64| if x == 0.0 { 1.0 } else { (PI * x).sin() / (PI * x) };
+    let kernel: Vec<Vec<f64>> = (0..=PHASES)
+        .map(|phase| {
+            let frac = phase as f64 / PHASES as f64;
+            let row: Vec<f64> = (-reach..=reach)
+                .map(|k| {
+                    let d = k as f64 - frac;
+                    if d.abs() >= half_width {
+                        return 0.0;
+                    }
+                    sinc(d * cutoff) * (0.5 + 0.5 * (PI * d / half_width).cos())
+                })
+                .collect();
+            let gain: f64 = row.iter().sum();
+            row.into_iter().map(|w| w / gain).collect()
+        })
+        .collect();
+
+    let out_len = (len as u64 * to as u64 / from as u64) as usize;
+    (0..out_len)
+        .map(|n| {
+            let t = n as f64 * step;
+            let base = t.floor();
+            let row = &kernel[((t - base) * PHASES as f64).round() as usize];
+            let first = base as isize - reach;
+            let acc: f64 = row
+                .iter()
+                .zip(first..)
+                .filter(|&(_, i)| i >= 0 && (i as usize) < len)
+                .map(|(w, i)| w * at(i as usize))
+                .sum();
+            to_i16(acc)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tone(freq: f64, rate: u32, seconds: f64) -> Vec<f32> {
+        let n = (rate as f64 * seconds) as usize;
+        (0..n).map(|i| (0.5 * (2.0 * PI * freq * i as f64 / rate as f64).sin()) as f32).collect()
+    }
+
+    /// Amplitude of `freq` in `samples` (16 kHz), by correlation.
+    fn amplitude(samples: &[i16], freq: f64) -> f64 {
+        let (mut re, mut im) = (0.0, 0.0);
+        for (i, &s) in samples.iter().enumerate() {
+            let phase = 2.0 * PI * freq * i as f64 / 16_000.0;
+            re += s as f64 / 32768.0 * phase.cos();
+            im += s as f64 / 32768.0 * phase.sin();
+        }
+        2.0 * (re * re + im * im).sqrt() / samples.len() as f64
+    }
+
+    #[test]
+    fn wav_files_are_read_at_their_own_rate_and_downmixed() {
+        let path = std::env::temp_dir().join(format!("autosubs-read-wav-{}.wav", std::process::id()));
+        let spec = WavSpec { channels: 2, sample_rate: 44_100, bits_per_sample: 24, sample_format: SampleFormat::Int };
+        let mut writer = WavWriter::create(&path, spec).unwrap();
+        for (left, right) in [(0, 0), (1 << 22, 0), (-(1 << 23), -(1 << 23)), (1 << 20, 3 << 20)] {
+            writer.write_sample(left).unwrap();
+            writer.write_sample(right).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        let read = read_wav(&path.to_string_lossy());
+        std::fs::remove_file(&path).unwrap();
+        let (samples, rate) = read.unwrap();
+        assert_eq!(rate, 44_100);
+        assert_eq!(samples, [0, 8192, -32768, 8192]);
+    }
+
+    #[test]
+    fn pipeline_rate_audio_is_only_converted() {
+        let audio = to_pipeline_audio(Samples::F32(&[0.0, 0.5, -1.0, 2.0]), 16_000).unwrap();
+        assert_eq!(audio, [0, 16384, -32768, i16::MAX]);
+        assert!(to_pipeline_audio(Samples::I16(&[1]), 0).is_err());
+    }
+
+    #[test]
+    fn owned_pipeline_rate_audio_keeps_its_buffer() {
+        let samples = vec![1i16, -2, 3];
+        let buffer = samples.as_ptr();
+        let audio = to_pipeline_audio_owned(samples, 16_000).unwrap();
+        assert_eq!(audio.as_ptr(), buffer);
+        assert_eq!(audio, [1, -2, 3]);
+        assert_eq!(to_pipeline_audio_owned(vec![0; 48], 48_000).unwrap().len(), 16);
+        assert!(to_pipeline_audio_owned(vec![1], 0).is_err());
+    }
+
+    #[test]
+    fn resampling_keeps_speech_and_drops_what_16_khz_cannot_hold() {
+        let mut input = tone(440.0, 48_000, 1.0);
+        // 11 kHz is above the 8 kHz Nyquist limit: it must not fold down to 5 kHz.
+        for (x, alias) in input.iter_mut().zip(tone(11_000.0, 48_000, 1.0)) {
+            *x += alias;
+        }
+        let audio = to_pipeline_audio(Samples::F32(&input), 48_000).unwrap();
+
+        assert_eq!(audio.len(), 16_000);
+        let body = &audio[1_000..15_000];
+        assert!((amplitude(body, 440.0) - 0.5).abs() < 0.01, "{}", amplitude(body, 440.0));
+        assert!(amplitude(body, 5_000.0) < 0.01, "{}", amplitude(body, 5_000.0));
+    }
+}
@@ -1,5 +1,6 @@
+use crate::audio::Samples;
 use crate::formatting::{PostProcessConfig, TextCase, TextDensity, process_segments};
-use crate::types::{Callbacks, LabeledProgressFn, NewSegmentFn, Segment, SpeechSegment};
+use crate::types::{Callbacks, LabeledProgressFn, NewSegmentFn, Segment, SharedAudio, SpeechSegment};
 use std::path::PathBuf;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
@@ -12,7 +13,8 @@ pub struct ContentFormatting {
     pub censored_words: Vec<String>,
 }
 
-use crate::manifest::{self, Engine as ModelEngine};
+use crate::engines::backend::Capabilities;
+use crate::manifest;
 
 #[derive(Clone, Debug)]
 pub struct EngineConfig {
@@ -50,13 +52,13 @@ impl Default for EngineConfig {
 /// Splits the audio into chunks of roughly `chunk_seconds` while trying to place
 /// boundaries on short silence windows instead of cutting through speech. This
 /// avoids splitting a word or syllable when the audio crosses a chunk edge.
-fn fixed_chunk_fallback(audio_samples: &[i16], chunk_seconds: f64) -> Vec<SpeechSegment> {
+fn fixed_chunk_fallback(audio: &SharedAudio, chunk_seconds: f64) -> Vec<SpeechSegment> {
     const SAMPLE_RATE: f64 = 16000.0;
     const SEARCH_WINDOW_SECONDS: f64 = 0.5; // look ~0.5 s before the target for silence
 
     let chunk_samples = (chunk_seconds * SAMPLE_RATE) as usize;
     let search_window = (SEARCH_WINDOW_SECONDS * SAMPLE_RATE) as usize;
-    let total = audio_samples.len();
+    let total = audio.len();
 
     let mut segments = Vec::new();
     let mut start = 0usize;
@@ -65,13 +67,14 @@ fn fixed_chunk_fallback(audio_samples: &[i16], chunk_seconds: f64) -> Vec<Speech
         let split = if target == total {
             total
         } else {
-            crate::utils::find_low_energy_split(audio_samples, start, target, search_window)
+            crate::utils::find_low_energy_split(audio, start, target, search_window)
         };
 
         segments.push(SpeechSegment {
             start: start as f64 / SAMPLE_RATE,
             end: split as f64 / SAMPLE_RATE,
-            samples: audio_samples[start..split].to_vec(),
+            audio: audio.clone(),
+            range: start..split,
             speaker_id: None,
         });
 
@@ -86,7 +89,7 @@ fn fixed_chunk_fallback(audio_samples: &[i16], chunk_seconds: f64) -> Vec<Speech
 async fn prepare_speech_segments(
     models: &mut crate::model_manager::ModelManager,
     cfg: &EngineConfig,
-    audio_samples: &[i16],
+    audio: &SharedAudio,
     options: &crate::TranscribeOptions,
     progress: Option<&LabeledProgressFn>,
     speakers_identified: Option<&crate::types::SpeakersIdentifiedFn>,
@@ -125,7 +128,7 @@ async fn prepare_speech_segments(
             progress.map(|_| &diarize_progress as &diarize::ProgressFn<'_>);
 
         let diarized = diarize::diarize(
-            audio_samples,
+            audio,
             16000,
             &diarize_options,
             diarize_progress_callback,
@@ -158,14 +161,14 @@ async fn prepare_speech_segments(
         let vad_model_path_str = vad_model_path.to_string_lossy().to_string();
         tracing::info!(
             "VAD: running speech detection on {} samples ({:.2}s of audio)",
-            audio_samples.len(),
-            audio_samples.len() as f64 / 16000.0
+            audio.len(),
+            audio.len() as f64 / 16000.0
         );
         if let Some(callback) = progress {
             callback(0, crate::ProgressType::Analyze, "progressSteps.analyze.vad");
         }
         let vad_start = std::time::Instant::now();
-        let speech_segments = crate::vad::get_segments(&vad_model_path_str, audio_samples)
+        let speech_segments = crate::vad::get_segments(&vad_model_path_str, audio)
             .map_err(|e| eyre::eyre!("{:?}", e))?;
         tracing::info!(
             "VAD: detected {} speech segment(s) in {:.2}s",
@@ -179,8 +182,9 @@ async fn prepare_speech_segments(
     } else {
         vec![SpeechSegment {
             start: 0.0,
-            end: audio_samples.len() as f64 / 16000.0,
-            samples: audio_samples.to_vec(),
+            end: audio.len() as f64 / 16000.0,
+            audio: audio.clone(),
+            range: 0..audio.len(),
             speaker_id: None,
         }]
     };
@@ -192,10 +196,10 @@ async fn prepare_speech_segments(
         tracing::warn!(
             "speech detection returned 0 segments; falling back to fixed 30s chunks"
         );
-        let fallback = fixed_chunk_fallback(audio_samples, 30.0);
+        let fallback = fixed_chunk_fallback(audio, 30.0);
         tracing::info!(
             "fallback: split {}s of audio into {} fixed chunk(s)",
-            audio_samples.len() as f64 / 16000.0,
+            audio.len() as f64 / 16000.0,
             fallback.len()
         );
         return Ok(fallback);
@@ -204,8 +208,8 @@ async fn prepare_speech_segments(
     Ok(speech_segments)
 }
 
-fn resolve_native_target(
-    engine_kind: ModelEngine,
+pub(crate) fn resolve_native_target(
+    caps: &Capabilities,
     from_lang: &str,
     translate_to: Option<&str>,
     use_native: bool,
@@ -222,15 +226,10 @@ fn resolve_native_target(
     if from_lang != "auto" && from_lang == target {
         return None;
     }
-    match engine_kind {
-        ModelEngine::Whisper if target == "en" => Some(target.to_string()),
-        ModelEngine::Canary
-            if crate::engines::canary::canary_supports_translation(from_lang, target) =>
-        {
-            Some(target.to_string())
-        }
-        _ => None,
-    }
+    caps.native_translation
+        .as_ref()
+        .filter(|native| native.supports(from_lang, target))
+        .map(|_| target.to_string())
 }
 
 fn align_segments(
@@ -326,6 +325,42 @@ fn align_segments(
     Ok(())
 }
 
+/// The ASR model file for `model`: the pre-resolved `asr_model_path` (from
+/// `prepare_models`) when set, else the cached copy, downloaded if missing.
+pub(crate) async fn ensure_asr_model(
+    models: &crate::model_manager::ModelManager,
+    cfg: &EngineConfig,
+    model: &str,
+    cb: &Callbacks,
+) -> eyre::Result<PathBuf> {
+    if let Some(p) = &cfg.asr_model_path {
+        let path = PathBuf::from(p);
+        if !path.exists() {
+            match manifest::get(model) {
+                Some(_) => eyre::bail!("ASR model path does not exist: {}", p),
+                None => eyre::bail!("Whisper model path does not exist: {}", p),
+            }
+        }
+        return Ok(path);
+    }
+    // Models not in the manifest fall back to Whisper (legacy behavior).
+    match manifest::get(model) {
+        Some(entry) => {
+            models
+                .ensure_model(entry, cb.progress.as_deref(), cb.is_cancelled.as_deref())
+                .await
+        }
+        None if crate::engines::backend::registered_model(model).is_some() => {
+            eyre::bail!("no model file for '{model}': set EngineConfig::asr_model_path")
+        }
+        None => {
+            models
+                .ensure_whisper_model(model, cb.progress.as_deref(), cb.is_cancelled.as_deref())
+                .await
+        }
+    }
+}
+
 fn build_post_process_config(
     output_lang: &str,
     density: Option<TextDensity>,
@@ -365,15 +400,20 @@ fn build_post_process_config(
     pp_cfg
 }
 
+/// Transcription engine. Keep one `Engine` for several files to load their
+/// ASR model once.
 pub struct Engine {
-    cfg: EngineConfig,
-    models: crate::model_manager::ModelManager,
+    pub(crate) cfg: EngineConfig,
+    pub(crate) models: crate::model_manager::ModelManager,
+    /// ASR model from the previous run, reused when the next one needs it.
+    pub(crate) loaded: crate::engines::LoadedModel,
 }
 
 impl Engine {
     pub fn new(cfg: EngineConfig) -> Self {
         Self {
             models: crate::model_manager::ModelManager::new(cfg.cache_dir.clone()),
+            loaded: Default::default(),
             cfg,
         }
     }
@@ -403,6 +443,8 @@ impl Engine {
             None => {
                 if let Some(p) = &cfg.asr_model_path {
                     PathBuf::from(p)
+                } else if crate::engines::backend::registered_model(&options.model).is_some() {
+                    eyre::bail!("no model file for '{}': set EngineConfig::asr_model_path", options.model)
                 } else {
                     self.models.ensure_whisper_model(&options.model, progress, is_cancelled).await?
                 }
@@ -434,6 +476,10 @@ impl Engine {
         Ok(cfg)
     }
 
+    /// Transcribe the WAV file at `audio_path`, downmixed to mono and
+    /// resampled to 16 kHz as in [`Self::transcribe_samples`]. Returns the
+    /// transcript segments, the same segments formatted as subtitle cues, and
+    /// the language of the output.
     #[allow(clippy::too_many_arguments)]
     pub async fn transcribe_audio(
         &mut self,
@@ -445,54 +491,81 @@ impl Engine {
         content_formatting: Option<ContentFormatting>,
         cb: Option<Callbacks>,
     ) -> eyre::Result<(Vec<Segment>, Vec<Segment>, String)> {
-        let cb = cb.unwrap_or_default();
         if !std::path::PathBuf::from(audio_path).exists() {
             eyre::bail!("audio file doesn't exist")
         }
+        let (samples, sample_rate) = crate::audio::read_wav(audio_path)?;
+        // Moved into the pipeline rather than copied: a long recording is held
+        // once.
+        let audio = crate::audio::to_pipeline_audio_owned(samples, sample_rate)?;
+        self.transcribe_shared(
+            Arc::new(audio),
+            options,
+            max_lines,
+            density,
+            custom_max_chars_per_line,
+            content_formatting,
+            cb,
+        )
+        .await
+    }
 
-        // Route to the appropriate engine based on the manifest. Models not in
-        // the manifest fall back to Whisper (legacy behavior).
-        let model_entry = manifest::get(&options.model);
-        let engine_kind = model_entry
-            .map(|e| e.engine)
-            .unwrap_or(ModelEngine::Whisper);
-
-        // Ensure/download the appropriate model. If a pre-resolved path is
-        // present (from prepare_models), use it and skip the cache check.
-        let _model_path = match model_entry {
-            Some(entry) => {
-                if let Some(p) = &self.cfg.asr_model_path {
-                    let path = PathBuf::from(p);
-                    if !path.exists() {
-                        eyre::bail!("ASR model path does not exist: {}", p);
-                    }
-                    path
-                } else {
-                    self.models
-                        .ensure_model(entry, cb.progress.as_deref(), cb.is_cancelled.as_deref())
-                        .await?
-                }
-            }
-            None => {
-                if let Some(p) = &self.cfg.asr_model_path {
-                    let path = PathBuf::from(p);
-                    if !path.exists() {
-                        eyre::bail!("Whisper model path does not exist: {}", p);
-                    }
-                    path
-                } else {
-                    self.models
-                        .ensure_whisper_model(&options.model, cb.progress.as_deref(), cb.is_cancelled.as_deref())
-                        .await?
-                }
-            }
-        };
+    /// [`Self::transcribe_audio`] for audio already in memory: mono PCM
+    /// (`&[i16]`, or `&[f32]` in -1.0..1.0) recorded at `sample_rate`, which is
+    /// resampled to 16 kHz if needed.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn transcribe_samples<'a>(
+        &mut self,
+        samples: impl Into<Samples<'a>>,
+        sample_rate: u32,
+        options: crate::TranscribeOptions,
+        max_lines: Option<usize>,
+        density: Option<TextDensity>,
+        custom_max_chars_per_line: Option<usize>,
+        content_formatting: Option<ContentFormatting>,
+        cb: Option<Callbacks>,
+    ) -> eyre::Result<(Vec<Segment>, Vec<Segment>, String)> {
+        let audio = crate::audio::to_pipeline_audio(samples.into(), sample_rate)?;
+        self.transcribe_shared(
+            Arc::new(audio),
+            options,
+            max_lines,
+            density,
+            custom_max_chars_per_line,
+            content_formatting,
+            cb,
+        )
+        .await
+    }
 
-        let original_samples = crate::audio::read_wav(audio_path)?;
+    /// The pipeline behind both entry points: VAD or diarization, ASR, forced
+    /// alignment and formatting, all reading ranges of `original_samples`.
+    #[allow(clippy::too_many_arguments)]
+    async fn transcribe_shared(
+        &mut self,
+        original_samples: SharedAudio,
+        options: crate::TranscribeOptions,
+        max_lines: Option<usize>,
+        density: Option<TextDensity>,
+        custom_max_chars_per_line: Option<usize>,
+        content_formatting: Option<ContentFormatting>,
+        cb: Option<Callbacks>,
+    ) -> eyre::Result<(Vec<Segment>, Vec<Segment>, String)> {
+        let cb = cb.unwrap_or_default();
         if original_samples.is_empty() {
-            eyre::bail!("audio file contains no samples")
+            eyre::bail!("audio contains no samples")
         }
 
+        // Route to the backend registered for the model's engine. Models not
+        // in the manifest fall back to Whisper (legacy behavior) unless they
+        // were registered with another backend.
+        let backend = crate::engines::backend::for_model(&options.model)?;
+        let caps = backend.capabilities(&options.model);
+
+        // Ensure/download the appropriate model. If a pre-resolved path is
+        // present (from prepare_models), use it and skip the cache check.
+        let _model_path = ensure_asr_model(&self.models, &self.cfg, &options.model, &cb).await?;
+
         let speech_segments = prepare_speech_segments(
             &mut self.models,
             &self.cfg,
@@ -504,7 +577,7 @@ impl Engine {
         )
         .await?;
 
-        let num_samples: usize = speech_segments.iter().map(|s| s.samples.len()).sum();
+        let num_samples: usize = speech_segments.iter().map(|s| s.range.len()).sum();
         let audio_duration_sec = num_samples as f64 / 16000.0;
 
         tracing::info!(
@@ -524,12 +597,9 @@ impl Engine {
         let user_offset = options.offset.unwrap_or(0.0);
         let alignment_cancellation: Option<Arc<dyn Fn() -> bool + Send + Sync>> =
             cb.is_cancelled.clone();
-        let engine_cancellation = alignment_cancellation
-            .clone()
-            .map(|cancelled| Box::new(move || cancelled()) as Box<dyn Fn() -> bool + Send + Sync>);
 
         let native_target =
-            resolve_native_target(engine_kind, &from_lang, translate_to.as_deref(), use_native);
+            resolve_native_target(&caps, &from_lang, translate_to.as_deref(), use_native);
 
         let mut engine_cfg = self.cfg.clone();
         // DTW costs a chunk of memory and forces flash attention off, so only pay
@@ -580,15 +650,16 @@ impl Engine {
             });
 
             let (_segments, detected_lang) = crate::engines::run_engine(
-                engine_kind,
+                &*backend,
                 _model_path.as_path(),
                 speech_segments,
                 &options,
                 native_target.as_deref(),
                 &engine_cfg,
+                &mut self.loaded,
                 cb.progress.as_deref(),
                 Some(&*submit_cb),
-                engine_cancellation,
+                alignment_cancellation.clone(),
             )
             .await?;
 
@@ -632,15 +703,16 @@ impl Engine {
             }
         } else {
             crate::engines::run_engine(
-                engine_kind,
+                &*backend,
                 _model_path.as_path(),
                 speech_segments,
                 &options,
                 native_target.as_deref(),
                 &engine_cfg,
+                &mut self.loaded,
                 cb.progress.as_deref(),
                 cb.new_segment_callback.as_deref(),
-                engine_cancellation,
+                alignment_cancellation.clone(),
             )
             .await?
         };
@@ -648,10 +720,7 @@ impl Engine {
         // Choose effective language: detected if present, otherwise the user-provided from_lang
         let effective_lang: &str = detected_lang.as_deref().unwrap_or(&from_lang);
 
-        let has_native_word_timestamps = matches!(
-            engine_kind,
-            ModelEngine::Parakeet | ModelEngine::SenseVoice
-        );
+        let has_native_word_timestamps = caps.word_timings;
 
         if enable_forced_alignment && translate_to.is_none() && !has_native_word_timestamps {
             if alignment_cancellation
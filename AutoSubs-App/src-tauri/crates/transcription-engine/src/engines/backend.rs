@@ -0,0 +1,261 @@
+//! Pluggable ASR backends.
+//!
+//! A backend loads one kind of model ([`AsrBackend`]) and the loaded model
+//! transcribes audio one chunk at a time ([`AsrModel`]); the shared driver in
+//! [`crate::engines::run_engine`] handles chunking, timestamps, callbacks and
+//! cancellation. Backends are registered by name — the built-in ones under
+//! their `models.json` engine names — and a model routes to the backend named
+//! by its manifest entry.
+//!
+//! To use a model AutoSubs doesn't ship, register a backend for it and map the
+//! model id to it, then point [`EngineConfig::asr_model_path`] at the model:
+//!
+//! ```ignore
+//! backend::register("in_house", InHouseBackend::new());
+//! backend::register_model("in-house-v2", "in_house");
+//! ```
+//!
+//! [`EngineConfig::asr_model_path`]: crate::EngineConfig
+
+use crate::engine::EngineConfig;
+use crate::types::{SegmentStats, TranscribeOptions, WordTimestamp};
+use eyre::{Result, eyre};
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, PoisonError, RwLock};
+
+/// What a backend's models can do, used to route translation and alignment and
+/// to split long speech before it reaches the model.
+#[derive(Debug, Clone, Default)]
+pub struct Capabilities {
+    /// Language pairs the model translates itself; `None` if it only transcribes.
+    pub native_translation: Option<NativeTranslation>,
+    /// Word timings good enough that forced alignment is skipped.
+    pub word_timings: bool,
+    /// Source languages (ISO 639-1) the model accepts, or `None` for any.
+    pub languages: Option<Vec<String>>,
+    /// Longest chunk handed to [`AsrModel::transcribe_chunk`], in seconds.
+    /// Longer speech is split at a quiet point first; `None` passes each speech
+    /// region whole.
+    pub max_chunk_seconds: Option<f64>,
+}
+
+/// The translations a model performs while transcribing.
+#[derive(Debug, Clone, Default)]
+pub struct NativeTranslation {
+    /// Source languages it translates from, or `None` for any (auto-detected
+    /// included).
+    pub from: Option<Vec<String>>,
+    /// Target languages it translates into.
+    pub to: Vec<String>,
+}
+
+impl NativeTranslation {
+    pub fn supports(&self, from: &str, to: &str) -> bool {
+        self.to.iter().any(|l| l == to)
+            && self.from.as_ref().is_none_or(|langs| langs.iter().any(|l| l == from))
+    }
+}
+
+/// Text recognised in one chunk of audio.
+#[derive(Debug, Clone, Default)]
+pub struct ChunkSegment {
+    /// Seconds from the start of the chunk.
+    pub start: f64,
+    pub end: f64,
+    pub text: String,
+    /// Word (or token) timings, relative to the chunk like `start`. Left empty,
+    /// words are interpolated across the segment unless `keep_empty_words`.
+    pub words: Vec<WordTimestamp>,
+    /// Leave the segment without words when `words` is empty, for models whose
+    /// timings are trusted as is.
+    pub keep_empty_words: bool,
+    /// Decoder statistics, with `seek` relative to the chunk.
+    pub stats: Option<SegmentStats>,
+}
+
+/// The run a model is about to transcribe, passed to [`AsrModel::prepare`].
+pub struct RunOptions<'a> {
+    pub options: &'a TranscribeOptions,
+    /// Language to translate into while transcribing, when routing chose the
+    /// model's native translation (see [`Capabilities::native_translation`]).
+    pub native_target: Option<&'a str>,
+    /// For models that can stop in the middle of a chunk; the driver checks it
+    /// between chunks regardless.
+    pub is_cancelled: Option<Arc<dyn Fn() -> bool + Send + Sync>>,
+}
+
+/// Loads models of one kind. Implementations are registered with [`register`].
+pub trait AsrBackend: Send + Sync {
+    /// What `model_id`, one of this backend's models, can do.
+    fn capabilities(&self, model_id: &str) -> Capabilities;
+
+    /// Load the model at `model_path` (a file or directory, as the backend's
+    /// models are laid out in the cache).
+    fn load(&self, model_path: &Path, options: &TranscribeOptions, cfg: &EngineConfig) -> Result<Box<dyn AsrModel>>;
+
+    /// A loaded model is reused by the next run with the same model and key.
+    /// Include any load setting that changes the loaded model.
+    fn cache_key(&self, model_path: &Path, cfg: &EngineConfig) -> String {
+        crate::engines::model_key(model_path, cfg.use_gpu)
+    }
+}
+
+/// A loaded model. Audio is mono 16 kHz, scaled to -1.0..1.0.
+pub trait AsrModel: Send {
+    /// Called before the first chunk of every run, to apply its options (source
+    /// language, translation target) or reject them.
+    fn prepare(&mut self, _run: &RunOptions<'_>) -> Result<()> {
+        Ok(())
+    }
+
+    fn transcribe_chunk(&mut self, samples: &[f32]) -> Result<Vec<ChunkSegment>>;
+
+    /// The language of this run's transcript, if known.
+    fn detected_lang(&self) -> Option<String>;
+
+    /// Another worker for decoding chunks in parallel (see
+    /// `AdvancedTranscribe::workers`) that shares this model's weights.
+    /// `None` makes the driver load a fresh copy from the backend instead.
+    fn fork(&self) -> Option<Result<Box<dyn AsrModel>>> {
+        None
+    }
+}
+
+type Backends = HashMap<String, Arc<dyn AsrBackend>>;
+
+static BACKENDS: Lazy<RwLock<Backends>> = Lazy::new(|| RwLock::new(builtin_backends()));
+
+/// Backend names for model ids that aren't in the manifest.
+static MODELS: Lazy<RwLock<HashMap<String, String>>> = Lazy::new(Default::default);
+
+fn builtin_backends() -> Backends {
+    use crate::engines::*;
+    use crate::manifest::Engine as ModelEngine;
+
+    let builtin: [(ModelEngine, Arc<dyn AsrBackend>); 8] = [
+        (ModelEngine::Whisper, Arc::new(whisper::WhisperBackend)),
+        (ModelEngine::Parakeet, Arc::new(parakeet::ParakeetBackend)),
+        (ModelEngine::Moonshine, Arc::new(moonshine::MoonshineBackend)),
+        (ModelEngine::Canary, Arc::new(canary::CanaryBackend)),
+        (ModelEngine::Cohere, Arc::new(cohere::CohereBackend)),
+        (ModelEngine::Gigaam, Arc::new(gigaam::GigaamBackend)),
+        (ModelEngine::SenseVoice, Arc::new(sense_voice::SenseVoiceBackend)),
+        (ModelEngine::OmniAsr, Arc::new(omni_asr::OmniAsrBackend)),
+    ];
+    builtin
+        .into_iter()
+        .map(|(engine, backend)| (engine.name().to_string(), backend))
+        .collect()
+}
+
+/// Register `backend` under `name`, replacing any backend (built-in included)
+/// already registered under it.
+pub fn register(name: &str, backend: impl AsrBackend + 'static) {
+    BACKENDS
+        .write()
+        .unwrap_or_else(PoisonError::into_inner)
+        .insert(name.to_string(), Arc::new(backend));
+}
+
+/// Transcribe model `model_id` with the backend registered as `backend`. Only
+/// needed for models outside the manifest, which otherwise load with Whisper.
+pub fn register_model(model_id: &str, backend: &str) {
+    MODELS
+        .write()
+        .unwrap_or_else(PoisonError::into_inner)
+        .insert(model_id.to_string(), backend.to_string());
+}
+
+/// The backend registered under `name`.
+pub fn get(name: &str) -> Option<Arc<dyn AsrBackend>> {
+    BACKENDS
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .get(name)
+        .cloned()
+}
+
+/// The backend `model_id` was registered with by [`register_model`].
+pub(crate) fn registered_model(model_id: &str) -> Option<String> {
+    MODELS
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .get(model_id)
+        .cloned()
+}
+
+/// The backend that transcribes `model_id`: its manifest engine, else the one
+/// it was registered with, else Whisper (legacy behavior for unknown ids).
+pub fn for_model(model_id: &str) -> Result<Arc<dyn AsrBackend>> {
+    let name = match crate::manifest::get(model_id) {
+        Some(entry) => entry.engine.name().to_string(),
+        None => registered_model(model_id).unwrap_or_else(|| "whisper".to_string()),
+    };
+    get(&name).ok_or_else(|| eyre!("no transcription backend registered as '{name}' (model '{model_id}')"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Echo;
+
+    impl AsrModel for Echo {
+        fn transcribe_chunk(&mut self, samples: &[f32]) -> Result<Vec<ChunkSegment>> {
+            Ok(vec![ChunkSegment {
+                end: samples.len() as f64 / 16_000.0,
+                text: "echo".into(),
+                ..Default::default()
+            }])
+        }
+
+        fn detected_lang(&self) -> Option<String> {
+            None
+        }
+    }
+
+    impl AsrBackend for Echo {
+        fn capabilities(&self, _: &str) -> Capabilities {
+            Capabilities {
+                languages: Some(vec!["en".into()]),
+                ..Default::default()
+            }
+        }
+
+        fn load(&self, _: &Path, _: &TranscribeOptions, _: &EngineConfig) -> Result<Box<dyn AsrModel>> {
+            Ok(Box::new(Echo))
+        }
+    }
+
+    #[test]
+    fn native_translation_matches_language_pairs() {
+        let to_english = NativeTranslation { from: None, to: vec!["en".into()] };
+        assert!(to_english.supports("auto", "en"));
+        assert!(to_english.supports("de", "en"));
+        assert!(!to_english.supports("en", "de"));
+
+        let european = NativeTranslation {
+            from: Some(vec!["de".into(), "en".into()]),
+            to: vec!["de".into(), "en".into()],
+        };
+        assert!(european.supports("de", "en"));
+        assert!(!european.supports("auto", "en"));
+        assert!(!european.supports("ja", "en"));
+    }
+
+    #[test]
+    fn models_route_to_registered_backends() {
+        register("echo-test", Echo);
+        register_model("echo-test-model", "echo-test");
+        let backend = for_model("echo-test-model").unwrap();
+        assert_eq!(backend.capabilities("echo-test-model").languages, Some(vec!["en".to_string()]));
+
+        register_model("orphan-test-model", "not-registered");
+        assert!(for_model("orphan-test-model").is_err());
+
+        // Unknown ids keep loading as Whisper models.
+        assert!(for_model("some-whisper-finetune").unwrap().capabilities("some-whisper-finetune").native_translation.is_some());
+    }
+}
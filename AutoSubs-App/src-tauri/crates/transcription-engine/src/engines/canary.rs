@@ -1,14 +1,16 @@
 //! Canary speech recognition backend.
 
-use crate::engines::onnx::{run_onnx_pipeline, OnnxEngine, WordTiming};
-use crate::types::{LabeledProgressFn, NewSegmentFn, ProgressType, Segment, SpeechSegment, TranscribeOptions};
+use crate::engine::EngineConfig;
+use crate::engines::backend::{AsrBackend, AsrModel, Capabilities, NativeTranslation, RunOptions};
+use crate::engines::onnx::{load_model, OnnxEngine, WordTiming};
+use crate::types::TranscribeOptions;
 use eyre::{eyre, Result};
 use std::path::Path;
 use transcribe_rs::onnx::{
     canary::{CanaryModel, CanaryParams},
     Quantization,
 };
-use transcribe_rs::{SpeechModel, TranscriptionResult};
+use transcribe_rs::TranscriptionResult;
 
 // Canary encodes a whole clip per call; cap chunk length to bound memory.
 const MAX_SEGMENT_SECONDS: f64 = 30.0;
@@ -18,12 +20,6 @@ const CANARY_TRANSLATION_LANGUAGES: &[&str] = &[
     "pl", "pt", "ro", "sk", "sl", "es", "sv", "ru", "uk",
 ];
 
-pub fn canary_supports_translation(from: &str, to: &str) -> bool {
-    from != "auto"
-        && CANARY_TRANSLATION_LANGUAGES.contains(&from)
-        && CANARY_TRANSLATION_LANGUAGES.contains(&to)
-}
-
 pub struct CanaryEngine {
     model: CanaryModel,
     params: CanaryParams,
@@ -31,8 +27,6 @@ pub struct CanaryEngine {
 }
 
 impl OnnxEngine for CanaryEngine {
-    const MAX_SEGMENT_SECONDS: f64 = MAX_SEGMENT_SECONDS;
-
     fn load(model_path: &Path) -> Result<Self> {
         let model = CanaryModel::load(model_path, &Quantization::Int8)
             .map_err(|e| eyre!("Failed to load Canary model: {}", e))?;
@@ -44,6 +38,22 @@ impl OnnxEngine for CanaryEngine {
         })
     }
 
+    fn prepare(&mut self, run: &RunOptions<'_>) -> Result<()> {
+        let lang = run.options.lang.clone().unwrap_or_else(|| "auto".to_string());
+        if lang == "auto" {
+            eyre::bail!(
+                "Canary cannot auto-detect the source language. Please select an explicit \
+                 source language from Canary's supported set (e.g. en, de, es, fr, ...) or \
+                 use an auto-detecting engine such as Whisper, SenseVoice, or Parakeet."
+            );
+        }
+
+        self.params.language = Some(lang.clone());
+        self.params.target_language = run.native_target.map(|t| t.to_string());
+        self.detected_lang = Some(run.native_target.map(|t| t.to_string()).unwrap_or(lang));
+        Ok(())
+    }
+
     fn transcribe_chunk(&mut self, samples: &[f32]) -> Result<TranscriptionResult> {
         self.model
             .transcribe_with(samples, &self.params)
@@ -59,63 +69,23 @@ impl OnnxEngine for CanaryEngine {
     }
 }
 
-pub async fn transcribe_canary(
-    model_path: &Path,
-    speech_segments: Vec<SpeechSegment>,
-    options: &TranscribeOptions,
-    native_target: Option<&str>,
-    use_gpu: Option<bool>,
-    progress_callback: Option<&LabeledProgressFn>,
-    new_segment_callback: Option<&NewSegmentFn>,
-    abort_callback: Option<Box<dyn Fn() -> bool + Send + Sync>>,
-) -> Result<(Vec<Segment>, Option<String>)> {
-    tracing::debug!("Canary transcribe called with model: {:?}", model_path);
-
-    if abort_callback.as_ref().map(|c| c()).unwrap_or(false) {
-        eyre::bail!("Transcription cancelled");
+pub struct CanaryBackend;
+
+impl AsrBackend for CanaryBackend {
+    fn capabilities(&self, _model_id: &str) -> Capabilities {
+        let languages: Vec<String> = CANARY_TRANSLATION_LANGUAGES.iter().map(|l| l.to_string()).collect();
+        Capabilities {
+            native_translation: Some(NativeTranslation {
+                from: Some(languages.clone()),
+                to: languages.clone(),
+            }),
+            languages: Some(languages),
+            max_chunk_seconds: Some(MAX_SEGMENT_SECONDS),
+            ..Default::default()
+        }
     }
 
-    let lang = options.lang.clone().unwrap_or_else(|| "auto".to_string());
-    if lang == "auto" {
-        eyre::bail!(
-            "Canary cannot auto-detect the source language. Please select an explicit \
-             source language from Canary's supported set (e.g. en, de, es, fr, ...) or \
-             use an auto-detecting engine such as Whisper, SenseVoice, or Parakeet."
-        );
+    fn load(&self, model_path: &Path, _options: &TranscribeOptions, cfg: &EngineConfig) -> Result<Box<dyn AsrModel>> {
+        load_model::<CanaryEngine>(model_path, cfg.use_gpu)
     }
-
-    if let Some(cb) = progress_callback {
-        cb(0, ProgressType::Analyze, "progressSteps.analyze.loading");
-    }
-    let mut engine = crate::engines::onnx::load_with_directml_fallback(use_gpu, || CanaryEngine::load(model_path))?;
-    if let Some(cb) = progress_callback {
-        cb(100, ProgressType::Analyze, "progressSteps.analyze.loading");
-    }
-
-    let supported_languages = engine.model.capabilities().languages;
-    if !supported_languages.is_empty() && !supported_languages.contains(&lang.as_str()) {
-        eyre::bail!(
-            "Canary does not support source language '{}'. Supported languages: {}",
-            lang,
-            supported_languages.join(", "),
-        );
-    }
-
-    engine.params.language = Some(lang.clone());
-    engine.params.target_language = native_target.map(|t| t.to_string());
-    engine.detected_lang = if let Some(t) = native_target {
-        Some(t.to_string())
-    } else {
-        Some(lang)
-    };
-
-    run_onnx_pipeline(
-        engine,
-        speech_segments,
-        options.offset.unwrap_or(0.0),
-        progress_callback,
-        new_segment_callback,
-        abort_callback,
-    )
-    .await
 }
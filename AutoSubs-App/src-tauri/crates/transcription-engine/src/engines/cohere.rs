@@ -1,14 +1,16 @@
 //! Cohere speech recognition backend.
 
-use crate::engines::onnx::{run_onnx_pipeline, OnnxEngine, WordTiming};
-use crate::types::{LabeledProgressFn, NewSegmentFn, ProgressType, Segment, SpeechSegment, TranscribeOptions};
+use crate::engine::EngineConfig;
+use crate::engines::backend::{AsrBackend, AsrModel, Capabilities, RunOptions};
+use crate::engines::onnx::{load_model, OnnxEngine, WordTiming};
+use crate::types::TranscribeOptions;
 use eyre::{eyre, Result};
 use std::path::Path;
 use transcribe_rs::onnx::{
     cohere::{CohereModel, CohereParams},
     Quantization,
 };
-use transcribe_rs::{SpeechModel, TranscriptionResult};
+use transcribe_rs::TranscriptionResult;
 
 // Cohere decodes autoregressively per clip; cap chunk length to bound work/memory.
 const MAX_SEGMENT_SECONDS: f64 = 30.0;
@@ -20,8 +22,6 @@ pub struct CohereEngine {
 }
 
 impl OnnxEngine for CohereEngine {
-    const MAX_SEGMENT_SECONDS: f64 = MAX_SEGMENT_SECONDS;
-
     fn load(model_path: &Path) -> Result<Self> {
         let model = CohereModel::load(model_path, &Quantization::Int4)
             .map_err(|e| eyre!("Failed to load Cohere model: {}", e))?;
@@ -33,6 +33,21 @@ impl OnnxEngine for CohereEngine {
         })
     }
 
+    fn prepare(&mut self, run: &RunOptions<'_>) -> Result<()> {
+        let lang = run.options.lang.clone().unwrap_or_else(|| "auto".to_string());
+        if lang == "auto" {
+            eyre::bail!(
+                "Cohere cannot auto-detect the source language. Please select an explicit \
+                 source language from Cohere's supported set (e.g. en, de, fr, ja, zh, ...) or \
+                 use an auto-detecting engine such as Whisper, SenseVoice, or Parakeet."
+            );
+        }
+
+        self.params.language = Some(lang.clone());
+        self.detected_lang = Some(lang);
+        Ok(())
+    }
+
     fn transcribe_chunk(&mut self, samples: &[f32]) -> Result<TranscriptionResult> {
         self.model
             .transcribe_with(samples, &self.params)
@@ -48,57 +63,18 @@ impl OnnxEngine for CohereEngine {
     }
 }
 
-pub async fn transcribe_cohere(
-    model_path: &Path,
-    speech_segments: Vec<SpeechSegment>,
-    options: &TranscribeOptions,
-    use_gpu: Option<bool>,
-    progress_callback: Option<&LabeledProgressFn>,
-    new_segment_callback: Option<&NewSegmentFn>,
-    abort_callback: Option<Box<dyn Fn() -> bool + Send + Sync>>,
-) -> Result<(Vec<Segment>, Option<String>)> {
-    tracing::debug!("Cohere transcribe called with model: {:?}", model_path);
-
-    if abort_callback.as_ref().map(|c| c()).unwrap_or(false) {
-        eyre::bail!("Transcription cancelled");
-    }
-
-    let lang = options.lang.clone().unwrap_or_else(|| "auto".to_string());
-    if lang == "auto" {
-        eyre::bail!(
-            "Cohere cannot auto-detect the source language. Please select an explicit \
-             source language from Cohere's supported set (e.g. en, de, fr, ja, zh, ...) or \
-             use an auto-detecting engine such as Whisper, SenseVoice, or Parakeet."
-        );
-    }
+pub struct CohereBackend;
 
-    if let Some(cb) = progress_callback {
-        cb(0, ProgressType::Analyze, "progressSteps.analyze.loading");
-    }
-    let mut engine = crate::engines::onnx::load_with_directml_fallback(use_gpu, || CohereEngine::load(model_path))?;
-    if let Some(cb) = progress_callback {
-        cb(100, ProgressType::Analyze, "progressSteps.analyze.loading");
+impl AsrBackend for CohereBackend {
+    fn capabilities(&self, model_id: &str) -> Capabilities {
+        Capabilities {
+            languages: crate::manifest::languages(model_id),
+            max_chunk_seconds: Some(MAX_SEGMENT_SECONDS),
+            ..Default::default()
+        }
     }
 
-    let supported_languages = engine.model.capabilities().languages;
-    if !supported_languages.is_empty() && !supported_languages.contains(&lang.as_str()) {
-        eyre::bail!(
-            "Cohere does not support source language '{}'. Supported languages: {}",
-            lang,
-            supported_languages.join(", "),
-        );
+    fn load(&self, model_path: &Path, _options: &TranscribeOptions, cfg: &EngineConfig) -> Result<Box<dyn AsrModel>> {
+        load_model::<CohereEngine>(model_path, cfg.use_gpu)
     }
-
-    engine.params.language = Some(lang.clone());
-    engine.detected_lang = Some(lang);
-
-    run_onnx_pipeline(
-        engine,
-        speech_segments,
-        options.offset.unwrap_or(0.0),
-        progress_callback,
-        new_segment_callback,
-        abort_callback,
-    )
-    .await
 }
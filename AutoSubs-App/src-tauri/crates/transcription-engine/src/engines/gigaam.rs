@@ -1,7 +1,9 @@
 //! GigaAM (Sber) Russian speech recognition backend.
 
-use crate::engines::onnx::{run_onnx_pipeline, OnnxEngine, WordTiming};
-use crate::types::{LabeledProgressFn, NewSegmentFn, ProgressType, Segment, SpeechSegment, TranscribeOptions};
+use crate::engine::EngineConfig;
+use crate::engines::backend::{AsrBackend, AsrModel, Capabilities};
+use crate::engines::onnx::{load_model, OnnxEngine, WordTiming};
+use crate::types::TranscribeOptions;
 use eyre::{eyre, Result};
 use std::path::Path;
 use transcribe_rs::onnx::{
@@ -16,10 +18,6 @@ pub struct GigaamEngine {
 }
 
 impl OnnxEngine for GigaamEngine {
-    // The Conformer encoder attends over the whole chunk, so memory grows
-    // quadratically with its length; cap it like the other ONNX engines.
-    const MAX_SEGMENT_SECONDS: f64 = 25.0;
-
     fn load(model_path: &Path) -> Result<Self> {
         let model = GigaAMModel::load(model_path, &Quantization::Int8)
             .map_err(|e| eyre!("Failed to load GigaAM model: {}", e))?;
@@ -47,36 +45,20 @@ impl OnnxEngine for GigaamEngine {
     }
 }
 
-pub async fn transcribe_gigaam(
-    model_path: &Path,
-    speech_segments: Vec<SpeechSegment>,
-    options: &TranscribeOptions,
-    use_gpu: Option<bool>,
-    progress_callback: Option<&LabeledProgressFn>,
-    new_segment_callback: Option<&NewSegmentFn>,
-    abort_callback: Option<Box<dyn Fn() -> bool + Send + Sync>>,
-) -> Result<(Vec<Segment>, Option<String>)> {
-    tracing::debug!("GigaAM transcribe called with model: {:?}", model_path);
+pub struct GigaamBackend;
 
-    if abort_callback.as_ref().map(|c| c()).unwrap_or(false) {
-        eyre::bail!("Transcription cancelled");
+impl AsrBackend for GigaamBackend {
+    fn capabilities(&self, model_id: &str) -> Capabilities {
+        Capabilities {
+            languages: crate::manifest::languages(model_id),
+            // The Conformer encoder attends over the whole chunk, so memory grows
+            // quadratically with its length; cap it like the other ONNX engines.
+            max_chunk_seconds: Some(25.0),
+            ..Default::default()
+        }
     }
 
-    if let Some(cb) = progress_callback {
-        cb(0, ProgressType::Analyze, "progressSteps.analyze.loading");
-    }
-    let engine = crate::engines::onnx::load_with_directml_fallback(use_gpu, || GigaamEngine::load(model_path))?;
-    if let Some(cb) = progress_callback {
-        cb(100, ProgressType::Analyze, "progressSteps.analyze.loading");
+    fn load(&self, model_path: &Path, _options: &TranscribeOptions, cfg: &EngineConfig) -> Result<Box<dyn AsrModel>> {
+        load_model::<GigaamEngine>(model_path, cfg.use_gpu)
     }
-
-    run_onnx_pipeline(
-        engine,
-        speech_segments,
-        options.offset.unwrap_or(0.0),
-        progress_callback,
-        new_segment_callback,
-        abort_callback,
-    )
-    .await
 }
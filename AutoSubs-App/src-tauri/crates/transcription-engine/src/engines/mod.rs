@@ -7,13 +7,22 @@
 //! - **SenseVoice**: FunAudioLLM SenseVoice via transcribe-rs (ONNX format)
 //! - **GigaAM**: Sber's Russian GigaAM v3 CTC via transcribe-rs (ONNX format)
 //! - **OmniAsr**: Facebook Omni-ASR 300M CTC via ORT (ONNX format)
+//!
+//! Each one implements [`backend::AsrBackend`] and is registered under its
+//! manifest engine name; [`run_engine`] drives whichever backend a model uses.
 
 use crate::engine::EngineConfig;
-use crate::types::{LabeledProgressFn, NewSegmentFn, ProgressType, Segment, SpeechSegment, TranscribeOptions};
-use crate::manifest::Engine as ModelEngine;
-use eyre::{eyre, Result};
+use crate::types::{LabeledProgressFn, NewSegmentFn, ProgressType, Segment, SegmentStage, SpeechSegment, TranscribeOptions};
+use crate::utils::{interpolate_word_timestamps, push_segment_clamped, split_speech_segment};
+use backend::{AsrBackend, AsrModel, RunOptions};
+use eyre::{Result, bail};
+use std::any::Any;
+use std::collections::BTreeMap;
 use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, mpsc};
 
+pub mod backend;
 pub mod whisper;
 
 pub mod onnx;
@@ -26,155 +35,349 @@ pub mod parakeet;
 pub mod sense_voice;
 
 // Re-export commonly used items
-pub use whisper::{create_context, run_transcription_pipeline, SHOULD_CANCEL};
+pub use whisper::{create_context, SHOULD_CANCEL};
+
+/// The ASR model the last run loaded, kept by [`crate::Engine`] so that
+/// transcribing several files with one engine loads the model once. A run that
+/// needs a different model (or different load settings) replaces it.
+#[derive(Default)]
+pub struct LoadedModel {
+    slot: Option<(String, Box<dyn Any + Send>)>,
+}
+
+impl LoadedModel {
+    /// Take the model loaded under `key`, or drop whatever is held and call
+    /// `load`. Hand the model back with [`Self::keep`] when the run is done.
+    pub(crate) fn take_or_load<T: Send + 'static>(&mut self, key: &str, load: impl FnOnce() -> Result<T>) -> Result<T> {
+        let held = self.slot.take().filter(|(k, _)| k == key);
+        if let Some(model) = held.and_then(|(_, model)| model.downcast::<T>().ok()) {
+            tracing::info!("reusing loaded model ({key})");
+            return Ok(*model);
+        }
+        load()
+    }
 
-pub use canary::transcribe_canary;
-pub use cohere::transcribe_cohere;
-pub use gigaam::transcribe_gigaam;
-pub use moonshine::transcribe_moonshine;
-pub use parakeet::transcribe_parakeet;
-pub use omni_asr::transcribe_omni_asr;
-pub use sense_voice::transcribe_sense_voice;
+    pub(crate) fn keep<T: Send + 'static>(&mut self, key: String, model: T) {
+        self.slot = Some((key, Box::new(model)));
+    }
+}
 
+/// [`LoadedModel`] key for a model file loaded with the given GPU preference.
+pub(crate) fn model_key(model_path: &Path, use_gpu: Option<bool>) -> String {
+    format!("{}|gpu={use_gpu:?}", model_path.display())
+}
+
+/// Transcribe `speech_segments` with `backend`, loading its model from
+/// `model_path` unless `loaded` already holds it. Speech longer than the
+/// backend's chunk limit is split first; segment times are absolute (including
+/// `options.offset`), and the transcript language is returned with them.
 #[allow(clippy::too_many_arguments)]
 pub async fn run_engine(
-    engine_kind: ModelEngine,
+    backend: &dyn AsrBackend,
     model_path: &Path,
     speech_segments: Vec<SpeechSegment>,
     options: &TranscribeOptions,
     native_target: Option<&str>,
     cfg: &EngineConfig,
+    loaded: &mut LoadedModel,
     progress: Option<&LabeledProgressFn>,
     new_segment_callback: Option<&NewSegmentFn>,
-    abort_callback: Option<Box<dyn Fn() -> bool + Send + Sync>>,
+    is_cancelled: Option<Arc<dyn Fn() -> bool + Send + Sync>>,
 ) -> Result<(Vec<Segment>, Option<String>)> {
-    let use_gpu = cfg.use_gpu;
-    match engine_kind {
-        ModelEngine::Parakeet => {
-            crate::engines::parakeet::transcribe_parakeet(
-                model_path,
-                speech_segments,
-                options,
-                use_gpu,
-                progress,
-                new_segment_callback,
-                abort_callback,
-            )
-            .await
+    tracing::debug!("{} transcribe called with model: {:?}", options.model, model_path);
+    let cancelled = || is_cancelled.as_ref().is_some_and(|c| c());
+    if cancelled() {
+        bail!("Transcription cancelled");
+    }
+
+    let caps = backend.capabilities(&options.model);
+    let lang = options.lang.as_deref().unwrap_or("auto");
+    let unsupported = caps
+        .languages
+        .as_ref()
+        .filter(|languages| lang != "auto" && !languages.iter().any(|l| l == lang));
+    if let Some(languages) = unsupported {
+        bail!(
+            "{} does not support source language '{}'. Supported languages: {}",
+            options.model,
+            lang,
+            languages.join(", "),
+        );
+    }
+
+    let chunks: Vec<SpeechSegment> = match caps.max_chunk_seconds {
+        Some(max) => speech_segments.iter().flat_map(|seg| split_speech_segment(seg, max)).collect(),
+        None => speech_segments,
+    };
+    let workers = options.advanced.as_ref().and_then(|a| a.workers).unwrap_or(1).max(1);
+    let needed = workers.min(chunks.len()).max(1);
+
+    if let Some(cb) = progress {
+        cb(0, ProgressType::Analyze, "progressSteps.analyze.loading");
+    }
+    let key = format!("{}|{}", options.model, backend.cache_key(model_path, cfg));
+    let mut pool = loaded.take_or_load(&key, || backend.load(model_path, options, cfg).map(|model| vec![model]))?;
+    pool.truncate(workers);
+    let grown = (pool.len()..needed).try_for_each(|_| {
+        let model = match pool[0].fork() {
+            Some(forked) => forked?,
+            None => backend.load(model_path, options, cfg)?,
+        };
+        pool.push(model);
+        Ok(())
+    });
+    if let Some(cb) = progress {
+        cb(100, ProgressType::Analyze, "progressSteps.analyze.loading");
+    }
+
+    let run = RunOptions { options, native_target, is_cancelled: is_cancelled.clone() };
+    let result = grown.and_then(|()| {
+        let pool = &mut pool[..needed];
+        pool.iter_mut().try_for_each(|model| model.prepare(&run))?;
+        if pool.len() > 1 {
+            tracing::info!("decoding {} chunks with {} workers", chunks.len(), pool.len());
+        }
+        transcribe_chunks(
+            pool,
+            &chunks,
+            options.offset.unwrap_or(0.0),
+            progress,
+            new_segment_callback,
+            &cancelled,
+        )
+    });
+    loaded.keep(key, pool);
+    result
+}
+
+/// Decode `chunks` on the models in `pool`, one chunk per model at a time.
+/// Segments are reported and collected in chunk order whatever order the
+/// workers finish in.
+fn transcribe_chunks(
+    pool: &mut [Box<dyn AsrModel>],
+    chunks: &[SpeechSegment],
+    user_offset: f64,
+    progress: Option<&LabeledProgressFn>,
+    new_segment: Option<&NewSegmentFn>,
+    cancelled: &(dyn Fn() -> bool + Sync),
+) -> Result<(Vec<Segment>, Option<String>)> {
+    let total = chunks.len().max(1);
+    let mut segments: Vec<Segment> = Vec::with_capacity(chunks.len());
+    let mut seg_index = 0usize;
+    let mut emit = |i: usize, chunk_segments: Vec<Segment>| {
+        for segment in chunk_segments {
+            if let Some(cb) = new_segment {
+                cb(seg_index, &segment, SegmentStage::Transcribe);
+            }
+            seg_index += 1;
+            push_segment_clamped(&mut segments, segment);
         }
-        ModelEngine::Moonshine => {
-            let (variant, _lang) = crate::engines::moonshine::moonshine_variant_from_model_name(&options.model)
-                .ok_or_else(|| eyre!("Unknown Moonshine model: {}", options.model))?;
-
-            crate::engines::moonshine::transcribe_moonshine(
-                model_path,
-                variant,
-                speech_segments,
-                options,
-                use_gpu,
-                progress,
-                new_segment_callback,
-                abort_callback,
-            )
-            .await
+
+        if let Some(progress_callback) = progress {
+            let pct = ((i + 1) as f64 / total as f64 * 100.0) as i32;
+            progress_callback(pct, ProgressType::Transcribe, "progressSteps.transcribe");
         }
-        ModelEngine::Whisper => {
-            if let Some(cb) = progress {
-                cb(0, ProgressType::Analyze, "progressSteps.analyze.loading");
+    };
+
+    if let [model] = pool {
+        for (i, chunk) in chunks.iter().enumerate() {
+            if cancelled() {
+                bail!("Transcription cancelled");
             }
-            tracing::info!(
-                "Whisper: loading model context (model={}, use_gpu={:?})",
-                options.model,
-                cfg.use_gpu
-            );
-            let ctx_start = std::time::Instant::now();
-            let ctx = crate::engines::whisper::create_context(
-                model_path,
-                &options.model,
-                cfg.gpu_device,
-                cfg.use_gpu,
-                cfg.enable_dtw,
-                cfg.enable_flash_attn,
-            )
-            .map_err(|e| eyre!("Failed to create Whisper context: {}", e))?;
-            tracing::info!(
-                "Whisper: model context ready in {:.2}s",
-                ctx_start.elapsed().as_secs_f64()
-            );
-            if let Some(cb) = progress {
-                cb(100, ProgressType::Analyze, "progressSteps.analyze.loading");
+            let chunk_segments = decode_chunk(&mut **model, chunk, user_offset)?;
+            if cancelled() {
+                bail!("Transcription cancelled");
             }
+            emit(i, chunk_segments);
+        }
+        return Ok((segments, model.detected_lang()));
+    }
+
+    let next = AtomicUsize::new(0);
+    let stop = AtomicBool::new(false);
+    let mut language = None;
+    let mut failure = None;
+    std::thread::scope(|scope| {
+        let (tx, rx) = mpsc::channel();
+        for model in pool.iter_mut() {
+            let (tx, next, stop) = (tx.clone(), &next, &stop);
+            scope.spawn(move || {
+                while !stop.load(Ordering::Relaxed) && !cancelled() {
+                    let i = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(chunk) = chunks.get(i) else { break };
+                    let decoded = decode_chunk(&mut **model, chunk, user_offset);
+                    if tx.send((i, decoded, model.detected_lang())).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(tx);
 
-            crate::engines::whisper::run_transcription_pipeline(
-                ctx,
-                speech_segments,
-                options.clone(),
-                progress,
-                new_segment_callback,
-                abort_callback,
-            )
-            .await
+        // Hold chunks that finish early until every chunk before them is in.
+        let mut pending = BTreeMap::new();
+        let mut in_order = 0;
+        for (i, decoded, lang) in rx {
+            match decoded {
+                Ok(chunk_segments) => {
+                    pending.insert(i, (chunk_segments, lang));
+                }
+                Err(e) => {
+                    stop.store(true, Ordering::Relaxed);
+                    failure.get_or_insert(e);
+                }
+            }
+            if failure.is_some() {
+                continue;
+            }
+            while let Some((chunk_segments, lang)) = pending.remove(&in_order) {
+                if language.is_none() {
+                    language = lang;
+                }
+                emit(in_order, chunk_segments);
+                in_order += 1;
+            }
         }
-        ModelEngine::SenseVoice => {
-            crate::engines::sense_voice::transcribe_sense_voice(
-                model_path,
-                speech_segments,
-                options,
-                use_gpu,
-                progress,
-                new_segment_callback,
-                abort_callback,
-            )
-            .await
+    });
+
+    if let Some(e) = failure {
+        return Err(e);
+    }
+    if cancelled() {
+        bail!("Transcription cancelled");
+    }
+    Ok((segments, language.or_else(|| pool[0].detected_lang())))
+}
+
+/// Transcribe one chunk into segments with absolute times.
+fn decode_chunk(model: &mut dyn AsrModel, chunk: &SpeechSegment, user_offset: f64) -> Result<Vec<Segment>> {
+    let samples: Vec<f32> = chunk.samples().iter().map(|&s| s as f32 / 32768.0).collect();
+    let recognised = model.transcribe_chunk(&samples)?;
+
+    let base_offset = chunk.start + user_offset;
+    let seek = (chunk.start * 100.0).round().max(0.0) as usize;
+    let mut segments = Vec::with_capacity(recognised.len());
+    for part in recognised {
+        let text = part.text.trim().to_string();
+        if text.is_empty() {
+            continue;
         }
-        ModelEngine::Canary => {
-            crate::engines::canary::transcribe_canary(
-                model_path,
-                speech_segments,
-                options,
-                native_target,
-                use_gpu,
-                progress,
-                new_segment_callback,
-                abort_callback,
-            )
-            .await
+        let start = base_offset + part.start;
+        let end = base_offset + part.end;
+        let words = if part.words.is_empty() && !part.keep_empty_words {
+            interpolate_word_timestamps(&text, start, end)
+        } else {
+            part.words
+                .into_iter()
+                .map(|mut w| {
+                    w.start += base_offset;
+                    w.end += base_offset;
+                    w
+                })
+                .collect()
+        };
+
+        segments.push(Segment {
+            speaker_id: chunk.speaker_id.clone(),
+            start,
+            end,
+            text,
+            words: (!words.is_empty()).then_some(words),
+            stats: part.stats.map(|mut stats| {
+                stats.seek += seek;
+                stats
+            }),
+        });
+    }
+    Ok(segments)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use backend::ChunkSegment;
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    /// Reports the chunk's start as its text, taking longer for earlier chunks
+    /// so parallel workers finish out of order.
+    struct Slow;
+
+    impl AsrModel for Slow {
+        fn transcribe_chunk(&mut self, samples: &[f32]) -> Result<Vec<ChunkSegment>> {
+            let start = samples[0] * 32768.0;
+            std::thread::sleep(Duration::from_millis(40 - 10 * start as u64));
+            Ok(vec![ChunkSegment { end: 0.5, text: format!("chunk {start}"), ..Default::default() }])
         }
-        ModelEngine::Cohere => {
-            crate::engines::cohere::transcribe_cohere(
-                model_path,
-                speech_segments,
-                options,
-                use_gpu,
-                progress,
-                new_segment_callback,
-                abort_callback,
-            )
-            .await
+
+        fn detected_lang(&self) -> Option<String> {
+            None
         }
-        ModelEngine::Gigaam => {
-            crate::engines::gigaam::transcribe_gigaam(
-                model_path,
-                speech_segments,
-                options,
-                use_gpu,
-                progress,
-                new_segment_callback,
-                abort_callback,
-            )
-            .await
+    }
+
+    #[test]
+    fn parallel_workers_report_segments_in_chunk_order() {
+        let chunks: Vec<SpeechSegment> = (0..4)
+            .map(|i| SpeechSegment {
+                start: i as f64,
+                end: i as f64 + 0.5,
+                audio: Arc::new(vec![i as i16; 8_000]),
+                range: 0..8_000,
+                speaker_id: None,
+            })
+            .collect();
+        let reported = Arc::new(Mutex::new(Vec::new()));
+        let sink = reported.clone();
+        let on_segment = move |index: usize, segment: &Segment, _: SegmentStage| {
+            sink.lock().unwrap().push((index, segment.text.clone()));
+        };
+
+        let mut pool: Vec<Box<dyn AsrModel>> = (0..4).map(|_| Box::new(Slow) as Box<dyn AsrModel>).collect();
+        let (segments, _) = transcribe_chunks(&mut pool, &chunks, 10.0, None, Some(&on_segment), &|| false).unwrap();
+
+        let texts: Vec<&str> = segments.iter().map(|s| s.text.as_str()).collect();
+        assert_eq!(texts, ["chunk 0", "chunk 1", "chunk 2", "chunk 3"]);
+        assert_eq!(segments[2].start, 12.0);
+        let reported = reported.lock().unwrap();
+        assert!(reported.iter().enumerate().all(|(i, (index, text))| *index == i && *text == texts[i]));
+    }
+
+    /// One two-word segment with no word timings.
+    struct Wordless {
+        keep_empty_words: bool,
+    }
+
+    impl AsrModel for Wordless {
+        fn transcribe_chunk(&mut self, _: &[f32]) -> Result<Vec<ChunkSegment>> {
+            Ok(vec![ChunkSegment {
+                end: 1.0,
+                text: "two words".into(),
+                keep_empty_words: self.keep_empty_words,
+                ..Default::default()
+            }])
         }
-        ModelEngine::OmniAsr => {
-            crate::engines::omni_asr::transcribe_omni_asr(
-                model_path,
-                speech_segments,
-                options,
-                use_gpu,
-                progress,
-                new_segment_callback,
-                abort_callback,
-            )
-            .await
+
+        fn detected_lang(&self) -> Option<String> {
+            None
         }
     }
+
+    #[test]
+    fn missing_words_are_interpolated_unless_kept_empty() {
+        let chunk = SpeechSegment {
+            start: 2.0,
+            end: 3.0,
+            audio: Arc::new(vec![0; 16_000]),
+            range: 0..16_000,
+            speaker_id: None,
+        };
+        let interpolated = decode_chunk(&mut Wordless { keep_empty_words: false }, &chunk, 0.0).unwrap();
+        let words = interpolated[0].words.as_deref().unwrap();
+        assert_eq!(words.len(), 2);
+        assert_eq!((words[0].start, words[1].end), (2.0, 3.0));
+
+        let kept = decode_chunk(&mut Wordless { keep_empty_words: true }, &chunk, 0.0).unwrap();
+        assert_eq!((kept[0].start, kept[0].end), (2.0, 3.0));
+        assert!(kept[0].words.is_none());
+    }
 }
@@ -1,5 +1,7 @@
-use crate::engines::onnx::{run_onnx_pipeline, OnnxEngine, WordTiming};
-use crate::types::{LabeledProgressFn, NewSegmentFn, ProgressType, Segment, SpeechSegment, TranscribeOptions};
+use crate::engine::EngineConfig;
+use crate::engines::backend::{AsrBackend, AsrModel, Capabilities};
+use crate::engines::onnx::{load_with_directml_fallback, OnnxEngine, WordTiming};
+use crate::types::TranscribeOptions;
 use eyre::{eyre, Result};
 use std::path::Path;
 use transcribe_rs::onnx::{
@@ -65,8 +67,6 @@ impl MoonshineEngine {
 }
 
 impl OnnxEngine for MoonshineEngine {
-    const MAX_SEGMENT_SECONDS: f64 = 64.0;
-
     fn load(model_path: &Path) -> Result<Self> {
         let model_name = model_path
             .file_name()
@@ -93,37 +93,24 @@ impl OnnxEngine for MoonshineEngine {
     }
 }
 
-pub async fn transcribe_moonshine(
-    model_path: &Path,
-    variant: MoonshineVariant,
-    speech_segments: Vec<SpeechSegment>,
-    options: &TranscribeOptions,
-    use_gpu: Option<bool>,
-    progress_callback: Option<&LabeledProgressFn>,
-    new_segment_callback: Option<&NewSegmentFn>,
-    abort_callback: Option<Box<dyn Fn() -> bool + Send + Sync>>,
-) -> Result<(Vec<Segment>, Option<String>)> {
-    tracing::debug!("Moonshine transcribe called with model: {:?}", model_path);
-
-    if abort_callback.as_ref().map(|c| c()).unwrap_or(false) {
-        eyre::bail!("Transcription cancelled");
+pub struct MoonshineBackend;
+
+impl AsrBackend for MoonshineBackend {
+    fn capabilities(&self, model_id: &str) -> Capabilities {
+        Capabilities {
+            // Each variant is trained on one language.
+            languages: moonshine_variant_from_model_name(model_id)
+                .and_then(|(_, lang)| lang)
+                .map(|lang| vec![lang.to_string()]),
+            max_chunk_seconds: Some(64.0),
+            ..Default::default()
+        }
     }
 
-    if let Some(cb) = progress_callback {
-        cb(0, ProgressType::Analyze, "progressSteps.analyze.loading");
+    fn load(&self, model_path: &Path, options: &TranscribeOptions, cfg: &EngineConfig) -> Result<Box<dyn AsrModel>> {
+        let (variant, _lang) = moonshine_variant_from_model_name(&options.model)
+            .ok_or_else(|| eyre!("Unknown Moonshine model: {}", options.model))?;
+        let engine = load_with_directml_fallback(cfg.use_gpu, || MoonshineEngine::load(model_path, variant))?;
+        Ok(Box::new(engine))
     }
-    let engine = crate::engines::onnx::load_with_directml_fallback(use_gpu, || MoonshineEngine::load(model_path, variant))?;
-    if let Some(cb) = progress_callback {
-        cb(100, ProgressType::Analyze, "progressSteps.analyze.loading");
-    }
-
-    run_onnx_pipeline(
-        engine,
-        speech_segments,
-        options.offset.unwrap_or(0.0),
-        progress_callback,
-        new_segment_callback,
-        abort_callback,
-    )
-    .await
 }
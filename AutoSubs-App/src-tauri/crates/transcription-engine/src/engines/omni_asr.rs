@@ -3,11 +3,13 @@
 //! Uses the ONNX-converted Facebook Omni-ASR CTC model directly through
 //! `ort`. The 1B CTC variant supports 1600+ languages, and does not produce
 //! capitalization or punctuation. Word timestamps are not
-//! emitted by the model, so the shared ONNX driver interpolates them over each
+//! emitted by the model, so the shared driver interpolates them over each
 //! chunk. Forced alignment (when enabled) refines those timings afterwards.
 
-use crate::engines::onnx::{run_onnx_pipeline, OnnxEngine, WordTiming};
-use crate::types::{LabeledProgressFn, NewSegmentFn, ProgressType, Segment, SpeechSegment, TranscribeOptions};
+use crate::engine::EngineConfig;
+use crate::engines::backend::{AsrBackend, AsrModel, Capabilities};
+use crate::engines::onnx::{load_model, OnnxEngine, WordTiming};
+use crate::types::TranscribeOptions;
 use eyre::{bail, eyre, Context, Result};
 use ndarray::{Array2, Array3};
 use ort::session::Session;
@@ -134,9 +136,6 @@ impl OmniAsrEngine {
 }
 
 impl OnnxEngine for OmniAsrEngine {
-    /// The 1B CTC model is trained on ≤30s chunks and rejects audio ≥40s.
-    const MAX_SEGMENT_SECONDS: f64 = 30.0;
-
     fn load(model_path: &Path) -> Result<Self> {
         Self::load(model_path)
     }
@@ -197,36 +196,18 @@ impl OnnxEngine for OmniAsrEngine {
     }
 }
 
-pub async fn transcribe_omni_asr(
-    model_path: &Path,
-    speech_segments: Vec<SpeechSegment>,
-    options: &TranscribeOptions,
-    use_gpu: Option<bool>,
-    progress_callback: Option<&LabeledProgressFn>,
-    new_segment_callback: Option<&NewSegmentFn>,
-    abort_callback: Option<Box<dyn Fn() -> bool + Send + Sync>>,
-) -> Result<(Vec<Segment>, Option<String>)> {
-    tracing::debug!("Omni-ASR transcribe called with model: {:?}", model_path);
-
-    if abort_callback.as_ref().map(|c| c()).unwrap_or(false) {
-        bail!("Transcription cancelled");
-    }
+pub struct OmniAsrBackend;
 
-    if let Some(cb) = progress_callback {
-        cb(0, ProgressType::Analyze, "progressSteps.analyze.loading");
-    }
-    let engine = crate::engines::onnx::load_with_directml_fallback(use_gpu, || OmniAsrEngine::load(model_path))?;
-    if let Some(cb) = progress_callback {
-        cb(100, ProgressType::Analyze, "progressSteps.analyze.loading");
+impl AsrBackend for OmniAsrBackend {
+    fn capabilities(&self, _model_id: &str) -> Capabilities {
+        Capabilities {
+            // The 1B CTC model is trained on ≤30s chunks and rejects audio ≥40s.
+            max_chunk_seconds: Some(30.0),
+            ..Default::default()
+        }
     }
 
-    run_onnx_pipeline(
-        engine,
-        speech_segments,
-        options.offset.unwrap_or(0.0),
-        progress_callback,
-        new_segment_callback,
-        abort_callback,
-    )
-    .await
+    fn load(&self, model_path: &Path, _options: &TranscribeOptions, cfg: &EngineConfig) -> Result<Box<dyn AsrModel>> {
+        load_model::<OmniAsrEngine>(model_path, cfg.use_gpu)
+    }
 }
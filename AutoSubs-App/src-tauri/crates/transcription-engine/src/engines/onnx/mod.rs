@@ -1,8 +1,6 @@
-use crate::types::{
-    LabeledProgressFn, NewSegmentFn, ProgressType, Segment, SpeechSegment, WordTimestamp,
-};
-use crate::utils::{interpolate_word_timestamps, push_segment_clamped, split_speech_segment};
-use eyre::{bail, Result};
+use crate::engines::backend::{AsrModel, ChunkSegment, RunOptions};
+use crate::types::WordTimestamp;
+use eyre::Result;
 use transcribe_rs::{TranscriptionResult, TranscriptionSegment};
 #[cfg(all(target_os = "windows", feature = "directml"))]
 use transcribe_rs::{get_ort_accelerator, set_ort_accelerator, OrtAccelerator};
@@ -11,7 +9,7 @@ use transcribe_rs::{get_ort_accelerator, set_ort_accelerator, OrtAccelerator};
 #[derive(Clone, Copy)]
 pub enum WordTiming {
     /// Real per-token/word segments. The mapper receives transcribe-rs segments
-    /// and the chunk's absolute base offset.
+    /// and a base offset to add to their times.
     FromTokens {
         map: fn(&[TranscriptionSegment], f64) -> Vec<WordTimestamp>,
         interpolate_on_empty: bool,
@@ -20,17 +18,62 @@ pub enum WordTiming {
     Interpolated,
 }
 
-/// Per-engine adapter for ONNX-backed models.
+/// Per-engine adapter for ONNX-backed models. Every `OnnxEngine` is an
+/// [`AsrModel`], with its transcribe-rs results turned into chunk segments.
 pub trait OnnxEngine: Sized {
-    /// Maximum chunk length in seconds.
-    const MAX_SEGMENT_SECONDS: f64;
-
     fn load(model_path: &std::path::Path) -> Result<Self>;
+
+    /// Apply a run's options before its first chunk; see [`AsrModel::prepare`].
+    fn prepare(&mut self, _run: &RunOptions<'_>) -> Result<()> {
+        Ok(())
+    }
+
     fn transcribe_chunk(&mut self, samples: &[f32]) -> Result<TranscriptionResult>;
     fn word_timing(&self) -> WordTiming;
     fn detected_lang(&self) -> Option<String>;
 }
 
+impl<E: OnnxEngine + Send + 'static> AsrModel for E {
+    fn prepare(&mut self, run: &RunOptions<'_>) -> Result<()> {
+        OnnxEngine::prepare(self, run)
+    }
+
+    fn transcribe_chunk(&mut self, samples: &[f32]) -> Result<Vec<ChunkSegment>> {
+        let result = OnnxEngine::transcribe_chunk(self, samples)?;
+        let text = result.text.trim().to_string();
+        if text.is_empty() {
+            return Ok(Vec::new());
+        }
+        let duration = samples.len() as f64 / 16_000.0;
+
+        let (start, end, words, keep_empty_words) = match self.word_timing() {
+            WordTiming::FromTokens { map, interpolate_on_empty } => {
+                let words = map(result.segments.as_deref().unwrap_or(&[]), 0.0);
+                match (words.first(), words.last()) {
+                    (Some(first), Some(last)) if interpolate_on_empty => (first.start, last.end, words, false),
+                    _ => (0.0, duration, words, !interpolate_on_empty),
+                }
+            }
+            WordTiming::Interpolated => (0.0, duration, Vec::new(), false),
+        };
+
+        Ok(vec![ChunkSegment { start, end, text, words, keep_empty_words, stats: None }])
+    }
+
+    fn detected_lang(&self) -> Option<String> {
+        OnnxEngine::detected_lang(self)
+    }
+}
+
+/// Load an ONNX engine as an [`AsrModel`], with the DirectML fallback below.
+pub fn load_model<E: OnnxEngine + Send + 'static>(
+    model_path: &std::path::Path,
+    use_gpu: Option<bool>,
+) -> Result<Box<dyn AsrModel>> {
+    let engine = load_with_directml_fallback(use_gpu, || E::load(model_path))?;
+    Ok(Box::new(engine))
+}
+
 #[cfg(all(target_os = "windows", feature = "directml"))]
 static ONNX_ACCEL_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
 
@@ -84,98 +127,3 @@ where
     #[cfg(not(all(target_os = "windows", feature = "directml")))]
     loader()
 }
-
-/// Shared driver for the ONNX engines.
-pub async fn run_onnx_pipeline<E: OnnxEngine>(
-    mut engine: E,
-    speech_segments: Vec<SpeechSegment>,
-    user_offset: f64,
-    progress: Option<&LabeledProgressFn>,
-    new_segment: Option<&NewSegmentFn>,
-    abort: Option<Box<dyn Fn() -> bool + Send + Sync>>,
-) -> Result<(Vec<Segment>, Option<String>)> {
-    let cancelled = || abort.as_ref().map(|c| c()).unwrap_or(false);
-
-    let mut expanded: Vec<SpeechSegment> = Vec::new();
-    for seg in &speech_segments {
-        expanded.extend(split_speech_segment(seg, E::MAX_SEGMENT_SECONDS));
-    }
-
-    let total = expanded.len().max(1);
-    let mut segments: Vec<Segment> = Vec::new();
-
-    for (i, speech_segment) in expanded.iter().enumerate() {
-        if cancelled() {
-            bail!("Transcription cancelled");
-        }
-
-        let samples: Vec<f32> = speech_segment
-            .samples
-            .iter()
-            .map(|&s| s as f32 / 32768.0)
-            .collect();
-
-        let result = engine
-            .transcribe_chunk(&samples)
-            .map_err(|e| eyre::eyre!("{}", e))?;
-
-        if cancelled() {
-            bail!("Transcription cancelled");
-        }
-
-        let text = result.text.trim().to_string();
-        if text.is_empty() {
-            if let Some(progress_callback) = progress {
-                let pct = ((i + 1) as f64 / total as f64 * 100.0) as i32;
-                progress_callback(pct, ProgressType::Transcribe, "progressSteps.transcribe");
-            }
-            continue;
-        }
-
-        let seg_start = speech_segment.start + user_offset;
-        let seg_end = speech_segment.end + user_offset;
-
-        let word_timing = engine.word_timing();
-        let mut words = match word_timing {
-            WordTiming::FromTokens { map, interpolate_on_empty } => {
-                let mapped = map(result.segments.as_deref().unwrap_or(&[]), seg_start);
-                if mapped.is_empty() && interpolate_on_empty {
-                    interpolate_word_timestamps(&text, seg_start, seg_end)
-                } else {
-                    mapped
-                }
-            }
-            WordTiming::Interpolated => interpolate_word_timestamps(&text, seg_start, seg_end),
-        };
-
-        let (segment_start, segment_end) = match word_timing {
-            WordTiming::FromTokens { interpolate_on_empty: true, .. } if !words.is_empty() => {
-                (words.first().unwrap().start, words.last().unwrap().end)
-            }
-            WordTiming::FromTokens { interpolate_on_empty: true, .. } => (seg_start, seg_end),
-            WordTiming::FromTokens { interpolate_on_empty: false, .. } => (seg_start, seg_start + (speech_segment.end - speech_segment.start)),
-            WordTiming::Interpolated => (seg_start, seg_end),
-        };
-
-        let segment = Segment {
-            speaker_id: speech_segment.speaker_id.clone(),
-            start: segment_start,
-            end: segment_end,
-            text,
-            words: (!words.is_empty()).then_some(std::mem::take(&mut words)),
-        };
-
-        if let Some(cb) = new_segment {
-            cb(i, &segment, crate::types::SegmentStage::Transcribe);
-        }
-
-        push_segment_clamped(&mut segments, segment);
-
-        if let Some(progress_callback) = progress {
-            let pct = ((i + 1) as f64 / total as f64 * 100.0) as i32;
-            progress_callback(pct, ProgressType::Transcribe, "progressSteps.transcribe");
-        }
-    }
-
-    Ok((segments, engine.detected_lang()))
-}
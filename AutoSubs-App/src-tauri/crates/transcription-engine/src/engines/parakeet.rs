@@ -1,7 +1,9 @@
 //! Parakeet (NeMo) speech recognition backend.
 
-use crate::engines::onnx::{run_onnx_pipeline, OnnxEngine, WordTiming};
-use crate::types::{LabeledProgressFn, NewSegmentFn, ProgressType, Segment, SpeechSegment, TranscribeOptions, WordTimestamp};
+use crate::engine::EngineConfig;
+use crate::engines::backend::{AsrBackend, AsrModel, Capabilities};
+use crate::engines::onnx::{load_model, OnnxEngine, WordTiming};
+use crate::types::{TranscribeOptions, WordTimestamp};
 use eyre::{eyre, Result};
 use std::path::Path;
 use transcribe_rs::onnx::{
@@ -46,8 +48,6 @@ fn parakeet_segments_to_words(segments: &[TranscriptionSegment], base_offset: f6
 }
 
 impl OnnxEngine for ParakeetEngine {
-    const MAX_SEGMENT_SECONDS: f64 = 30.0;
-
     fn load(model_path: &Path) -> Result<Self> {
         let model = ParakeetModel::load(model_path, &Quantization::Int8)
             .map_err(|e| eyre!("Failed to load Parakeet model: {}", e))?;
@@ -76,36 +76,18 @@ impl OnnxEngine for ParakeetEngine {
     }
 }
 
-pub async fn transcribe_parakeet(
-    model_path: &Path,
-    speech_segments: Vec<SpeechSegment>,
-    options: &TranscribeOptions,
-    use_gpu: Option<bool>,
-    progress_callback: Option<&LabeledProgressFn>,
-    new_segment_callback: Option<&NewSegmentFn>,
-    abort_callback: Option<Box<dyn Fn() -> bool + Send + Sync>>,
-) -> Result<(Vec<Segment>, Option<String>)> {
-    tracing::debug!("Parakeet transcribe called with model: {:?}", model_path);
-
-    if abort_callback.as_ref().map(|c| c()).unwrap_or(false) {
-        eyre::bail!("Transcription cancelled");
-    }
+pub struct ParakeetBackend;
 
-    if let Some(cb) = progress_callback {
-        cb(0, ProgressType::Analyze, "progressSteps.analyze.loading");
-    }
-    let engine = crate::engines::onnx::load_with_directml_fallback(use_gpu, || ParakeetEngine::load(model_path))?;
-    if let Some(cb) = progress_callback {
-        cb(100, ProgressType::Analyze, "progressSteps.analyze.loading");
+impl AsrBackend for ParakeetBackend {
+    fn capabilities(&self, _model_id: &str) -> Capabilities {
+        Capabilities {
+            word_timings: true,
+            max_chunk_seconds: Some(30.0),
+            ..Default::default()
+        }
     }
 
-    run_onnx_pipeline(
-        engine,
-        speech_segments,
-        options.offset.unwrap_or(0.0),
-        progress_callback,
-        new_segment_callback,
-        abort_callback,
-    )
-    .await
+    fn load(&self, model_path: &Path, _options: &TranscribeOptions, cfg: &EngineConfig) -> Result<Box<dyn AsrModel>> {
+        load_model::<ParakeetEngine>(model_path, cfg.use_gpu)
+    }
 }
@@ -1,9 +1,9 @@
 //! SenseVoice speech recognition backend.
 
-use crate::engines::onnx::{run_onnx_pipeline, OnnxEngine, WordTiming};
-use crate::types::{
-    LabeledProgressFn, NewSegmentFn, ProgressType, Segment, SpeechSegment, TranscribeOptions, WordTimestamp,
-};
+use crate::engine::EngineConfig;
+use crate::engines::backend::{AsrBackend, AsrModel, Capabilities, RunOptions};
+use crate::engines::onnx::{load_model, OnnxEngine, WordTiming};
+use crate::types::{TranscribeOptions, WordTimestamp};
 use eyre::{eyre, Result};
 use std::path::Path;
 use transcribe_rs::onnx::{
@@ -79,8 +79,6 @@ fn sense_voice_segments_to_words(segments: &[TranscriptionSegment], base_offset:
 }
 
 impl OnnxEngine for SenseVoiceEngine {
-    const MAX_SEGMENT_SECONDS: f64 = MAX_SEGMENT_SECONDS;
-
     fn load(model_path: &Path) -> Result<Self> {
         let model = SenseVoiceModel::load(model_path, &Quantization::Int8)
             .map_err(|e| eyre!("Failed to load SenseVoice model: {}", e))?;
@@ -95,6 +93,13 @@ impl OnnxEngine for SenseVoiceEngine {
         })
     }
 
+    fn prepare(&mut self, run: &RunOptions<'_>) -> Result<()> {
+        let lang = run.options.lang.clone().unwrap_or_else(|| "auto".to_string());
+        self.params.language = Some(lang.clone());
+        self.detected_lang = if lang == "auto" { None } else { Some(lang) };
+        Ok(())
+    }
+
     fn transcribe_chunk(&mut self, samples: &[f32]) -> Result<TranscriptionResult> {
         self.model
             .transcribe_with(samples, &self.params)
@@ -110,42 +115,20 @@ impl OnnxEngine for SenseVoiceEngine {
     }
 }
 
-pub async fn transcribe_sense_voice(
-    model_path: &Path,
-    speech_segments: Vec<SpeechSegment>,
-    options: &TranscribeOptions,
-    use_gpu: Option<bool>,
-    progress_callback: Option<&LabeledProgressFn>,
-    new_segment_callback: Option<&NewSegmentFn>,
-    abort_callback: Option<Box<dyn Fn() -> bool + Send + Sync>>,
-) -> Result<(Vec<Segment>, Option<String>)> {
-    tracing::debug!("SenseVoice transcribe called with model: {:?}", model_path);
-
-    if abort_callback.as_ref().map(|c| c()).unwrap_or(false) {
-        eyre::bail!("Transcription cancelled");
-    }
+pub struct SenseVoiceBackend;
 
-    if let Some(cb) = progress_callback {
-        cb(0, ProgressType::Analyze, "progressSteps.analyze.loading");
-    }
-    let mut engine = crate::engines::onnx::load_with_directml_fallback(use_gpu, || SenseVoiceEngine::load(model_path))?;
-    if let Some(cb) = progress_callback {
-        cb(100, ProgressType::Analyze, "progressSteps.analyze.loading");
+impl AsrBackend for SenseVoiceBackend {
+    fn capabilities(&self, _model_id: &str) -> Capabilities {
+        Capabilities {
+            word_timings: true,
+            max_chunk_seconds: Some(MAX_SEGMENT_SECONDS),
+            ..Default::default()
+        }
     }
 
-    let lang = options.lang.clone().unwrap_or_else(|| "auto".to_string());
-    engine.params.language = Some(lang.clone());
-    engine.detected_lang = if lang == "auto" { None } else { Some(lang) };
-
-    run_onnx_pipeline(
-        engine,
-        speech_segments,
-        options.offset.unwrap_or(0.0),
-        progress_callback,
-        new_segment_callback,
-        abort_callback,
-    )
-    .await
+    fn load(&self, model_path: &Path, _options: &TranscribeOptions, cfg: &EngineConfig) -> Result<Box<dyn AsrModel>> {
+        load_model::<SenseVoiceEngine>(model_path, cfg.use_gpu)
+    }
 }
 
 #[cfg(test)]
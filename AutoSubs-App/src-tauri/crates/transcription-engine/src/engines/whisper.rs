@@ -1,10 +1,13 @@
-use crate::types::{SpeechSegment, Segment, WordTimestamp, TranscribeOptions, LabeledProgressFn, NewSegmentFn, ProgressType};
-use eyre::{Result, bail, WrapErr, OptionExt};
+use crate::engine::EngineConfig;
+use crate::engines::backend::{AsrBackend, AsrModel, Capabilities, ChunkSegment, NativeTranslation, RunOptions};
+use crate::engines::model_key;
+use crate::types::{SegmentStats, WordTimestamp, TranscribeOptions};
+use eyre::{Result, bail, eyre, WrapErr, OptionExt};
 use std::path::Path;
-use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters, WhisperSegment, DtwParameters, DtwMode, DtwModelPreset};
+use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters, WhisperSegment, WhisperState, DtwParameters, DtwMode, DtwModelPreset};
 use std::panic::{catch_unwind, AssertUnwindSafe};
-use std::sync::Mutex;
-use crate::utils::{calculate_dtw_mem_size, cs_to_s, push_segment_clamped};
+use std::sync::{Arc, Mutex};
+use crate::utils::{calculate_dtw_mem_size, cs_to_s};
 
 type ProgressCallbackType = once_cell::sync::Lazy<Mutex<Option<Box<dyn Fn(i32) + Send + Sync>>>>;
 static PROGRESS_CALLBACK: ProgressCallbackType = once_cell::sync::Lazy::new(|| Mutex::new(None));
@@ -184,8 +187,6 @@ pub fn create_context(
     }
 }
 
-use crate::utils::interpolate_word_timestamps;
-
 // Returns true if `s` is *only* a control marker like "[_BEG_]" or "[_TT_320]".
 fn is_whole_control_token(s: &str) -> bool {
     let t = s.trim_matches('\0').trim();
@@ -351,58 +352,72 @@ fn get_token_timestamps(seg: &WhisperSegment) -> Vec<WordTimestamp> {
     spans
 }
 
-// Pass in path to normalised mono 16k PCM16 audio file
-pub async fn run_transcription_pipeline(
-    ctx: WhisperContext,
-    speech_segments: Vec<SpeechSegment>,
-    options: TranscribeOptions,
-    progress_callback: Option<&LabeledProgressFn>,
-    new_segment_callback: Option<&NewSegmentFn>,
-    abort_callback: Option<Box<dyn Fn() -> bool + Send + Sync>>,
-) -> Result<(Vec<Segment>, Option<String>)> {
-    tracing::debug!("Transcribe called with {:?}", options);
-
-    // Create Whisper state
-    let mut state = ctx.create_state().context("failed to create state")?;
-    let mut params = setup_params(&options);
-
-
-    // DEFINE ABORT CALLBACK
-    if let Some(abort_callback) = abort_callback {
-        params.set_abort_callback_safe(abort_callback);
-    }
-
-    // DEFINE PROGRESS CALLBACK (no-op bridge; per-segment progress is emitted below)
-    params.set_progress_callback_safe(|progress| {
-        if let Ok(mut cb) = PROGRESS_CALLBACK.lock() {
-            if let Some(cb) = cb.as_mut() { cb(progress); }
+// Decoder statistics reported alongside the segment (OpenAI verbose_json fields).
+// `seek` is left at the chunk start (0); the driver offsets it. The mean
+// log-probability skips control tokens like "[_BEG_]".
+fn get_segment_stats(seg: &WhisperSegment, temperature: f32) -> SegmentStats {
+    let n = seg.n_tokens();
+    let mut tokens = Vec::with_capacity(n.max(0) as usize);
+    let mut logprob_sum = 0.0f64;
+    let mut text_tokens = 0usize;
+    for i in 0..n {
+        let Some(tok) = seg.get_token(i) else { continue };
+        tokens.push(tok.token_id());
+        let is_control = tok
+            .to_bytes()
+            .ok()
+            .and_then(|b| std::str::from_utf8(b).ok())
+            .is_some_and(|s| is_whole_control_token(s.trim_end_matches('\0')));
+        if !is_control {
+            logprob_sum += tok.token_data().plog as f64;
+            text_tokens += 1;
         }
-    });
-
-    let mut empty_segments = 0;
-    let mut total_chars = 0;
+    }
+    SegmentStats {
+        seek: 0,
+        tokens,
+        temperature,
+        avg_logprob: if text_tokens > 0 { logprob_sum / text_tokens as f64 } else { 0.0 },
+        no_speech_prob: seg.no_speech_probability() as f64,
+    }
+}
 
-    // Apply this offset directly when producing segment and word timestamps
-    let user_offset = options.offset.unwrap_or(0.0);
+/// A loaded Whisper model. Each run gets a fresh decoder state, kept across
+/// its chunks so whisper.cpp can carry context from one to the next. Parallel
+/// workers are forked off one context, each with a state of its own.
+pub struct WhisperModel {
+    ctx: Arc<WhisperContext>,
+    state: Option<WhisperState>,
+    options: TranscribeOptions,
+    abort: Option<Arc<dyn Fn() -> bool + Send + Sync>>,
+    detected_lang: Option<String>,
+}
 
-    // List for subtitle segments
-    let mut segments: Vec<Segment> = Vec::with_capacity(speech_segments.len());
-    let mut detected_lang: Option<String> = None;
+impl AsrModel for WhisperModel {
+    fn prepare(&mut self, run: &RunOptions<'_>) -> Result<()> {
+        tracing::debug!("Transcribe called with {:?}", run.options);
+        self.state = Some(self.ctx.create_state().context("failed to create state")?);
+        self.options = run.options.clone();
+        self.abort = run.is_cancelled.clone();
+        self.detected_lang = run.options.lang.clone().filter(|lang| lang != "auto");
+        Ok(())
+    }
 
+    fn transcribe_chunk(&mut self, samples: &[f32]) -> Result<Vec<ChunkSegment>> {
+        let state = self.state.as_mut().ok_or_eyre("Whisper model used before prepare")?;
+        let mut params = setup_params(&self.options);
 
-    if let Some(lang) = options.lang.as_deref() {
-        if lang != "auto" {
-            detected_lang = Some(lang.to_string());
+        // DEFINE ABORT CALLBACK
+        if let Some(abort) = self.abort.clone() {
+            params.set_abort_callback_safe(move || abort());
         }
-    }
-
-    let mut seg_index = 0usize;
-    for (i, speech_segment) in speech_segments.iter().enumerate() {
-        let original_samples = speech_segment.samples.clone();
 
-        // Convert integer samples to float for Whisper
-        let mut samples = vec![0.0f32; original_samples.len()];
-        whisper_rs::convert_integer_to_float_audio(&original_samples, &mut samples)?;
+        // DEFINE PROGRESS CALLBACK (no-op bridge; per-chunk progress is emitted by the driver)
+        params.set_progress_callback_safe(|progress| {
+            if let Ok(mut cb) = PROGRESS_CALLBACK.lock() {
+                if let Some(cb) = cb.as_mut() { cb(progress); }
+            }
+        });
 
         // Note: we deliberately do NOT chain a manual `initial_prompt` across
         // VAD chunks. With `single_segment=false`, whisper.cpp handles
@@ -413,107 +428,127 @@ pub async fn run_transcription_pipeline(
         // in `setup_params`.
 
         // Transcribe the segment
-        state.full(params.clone(), &samples).context("failed to transcribe")?;
+        state.full(params, samples).context("failed to transcribe")?;
 
         // If no language was specified, detect it
-        if detected_lang.is_none() {
+        if self.detected_lang.is_none() {
             let id = state.full_lang_id_from_state();
-            detected_lang = Some(whisper_rs::get_lang_str(id).unwrap_or("en").to_string()); // convert id to language code
+            self.detected_lang = Some(whisper_rs::get_lang_str(id).unwrap_or("en").to_string()); // convert id to language code
         }
 
-        let num_segments = state.full_n_segments();
-        tracing::debug!("found {} sentence segments", num_segments);
+        tracing::debug!("found {} sentence segments", state.full_n_segments());
 
-        // Base offset for this chunk relative to the full audio timeline,
-        // including any user-specified global offset
-        let base_offset = speech_segment.start + user_offset;
+        let temperature = self.options.advanced.as_ref().and_then(|a| a.temperature).unwrap_or(0.0);
+        // Native translation emits target-language text whose token timings
+        // don't line up with the words, so those get interpolated instead.
+        let translated = self.options.use_native_translation.unwrap_or(false)
+            && self.options.translate_target.as_deref() == Some("en");
 
+        let mut segments = Vec::new();
         for seg in state.as_iter() {
             // Get the transcribed text from the state
-            let mut text: String = seg.to_str().unwrap().to_string();
-            text = text.trim_start().to_string(); // remove Whisper's typical leading space
-
-            // Use the segment's start/end times (convert from centiseconds to seconds)
-            // and offset by the speech segment's start to get absolute times
-            let approx_start = base_offset + cs_to_s(seg.start_timestamp());
-            let approx_end = base_offset + cs_to_s(seg.end_timestamp());
-    
-            tracing::debug!(
-                "Seg approx [{:.2}-{:.2}] text_len={} text={:?}",
-                approx_start, approx_end, text.len(), text
-            );
-    
+            let text = seg.to_str().unwrap().trim_start().to_string(); // remove Whisper's typical leading space
+
+            // Segment times within the chunk (centiseconds to seconds)
+            let approx_start = cs_to_s(seg.start_timestamp());
+            let approx_end = cs_to_s(seg.end_timestamp());
+
             if text.trim().is_empty() {
                 // With `single_segment=false`, whisper.cpp may emit trailing
                 // empty sub-segments from padded-silence regions. Skip them
                 // entirely rather than committing empty cues.
-                empty_segments += 1;
                 tracing::debug!(
                     "Skipping empty/whitespace seg in [{:.2}-{:.2}]",
                     approx_start, approx_end
                 );
                 continue;
             }
-        
-            // Choose word timestamps strategy and apply offset where needed in one place
-            let translated = options.use_native_translation.unwrap_or(false)
-                && options.translate_target.as_deref() == Some("en");
-            let word_timestamps: Vec<WordTimestamp> = if translated {
-                // Interpolated times are already absolute via approx_* (which include base_offset)
-                interpolate_word_timestamps(&text, approx_start, approx_end)
-            } else {
-                let mut w = get_token_timestamps(&seg);
-                for t in &mut w { t.start += base_offset; t.end += base_offset; } // Offset all word timestamps by base_offset
-                w
-            };
 
-            // Derive segment bounds with sensible fallbacks, and include words if any
-            let seg_start = word_timestamps.first().map(|w| w.start).unwrap_or(approx_start);
-            let seg_end = word_timestamps.last().map(|w| w.end).unwrap_or(approx_end);
+            let words = if translated { Vec::new() } else { get_token_timestamps(&seg) };
+
+            // Derive segment bounds from the words, falling back to the segment times
+            let start = words.first().map(|w| w.start).unwrap_or(approx_start);
+            let end = words.last().map(|w| w.end).unwrap_or(approx_end);
             tracing::debug!(
-                "Seg word_timestamps count={} bounds [{:.2}-{:.2}]",
-                word_timestamps.len(), seg_start, seg_end
+                "Seg word_timestamps count={} bounds [{:.2}-{:.2}] text={:?}",
+                words.len(), start, end, text
             );
-            let words_opt = (!word_timestamps.is_empty()).then_some(word_timestamps);
-        
-            // Embedding and speaker identification (speaker diarization) - if enabled
-            let mut speaker_id = None;
-            if num_segments > 0 {
-                speaker_id = speech_segment.speaker_id.clone();
-            }
-
-            total_chars += text.len();
 
-            let segment = Segment {
-                speaker_id,
-                start: seg_start,
-                end: seg_end,
+            segments.push(ChunkSegment {
+                start,
+                end,
                 text,
-                words: words_opt,
-            };
+                words,
+                keep_empty_words: false,
+                stats: Some(get_segment_stats(&seg, temperature)),
+            });
+        }
 
-            // Emit new segment to callback
-            if let Some(cb) = new_segment_callback {
-                cb(seg_index, &segment, crate::types::SegmentStage::Transcribe);
-                seg_index += 1;
-            }
+        Ok(segments)
+    }
 
-            // Emit progress update to callback
-            if let Some(progress_callback) = progress_callback {
-                tracing::trace!("progress: {} * {} / 100", i, speech_segments.len());
-                let progress = ((i + 1) as f64 / speech_segments.len() as f64 * 100.0) as i32;
-                progress_callback(progress, ProgressType::Transcribe, "progressSteps.transcribe");
-            }
-            push_segment_clamped(&mut segments, segment);
-        }
+    fn detected_lang(&self) -> Option<String> {
+        self.detected_lang.clone()
     }
 
-    tracing::debug!("Empty segments: {}", empty_segments);
-    tracing::debug!("Total characters: {}", total_chars);
-    tracing::debug!("Segments: {}", segments.len());
+    fn fork(&self) -> Option<Result<Box<dyn AsrModel>>> {
+        Some(Ok(Box::new(WhisperModel {
+            ctx: self.ctx.clone(),
+            state: None,
+            options: self.options.clone(),
+            abort: None,
+            detected_lang: None,
+        })))
+    }
+}
+
+pub struct WhisperBackend;
 
-    // Clear progress bridge to avoid dangling references beyond this async call
-    if let Ok(mut slot) = PROGRESS_CALLBACK.lock() { *slot = None; }
+impl AsrBackend for WhisperBackend {
+    fn capabilities(&self, _model_id: &str) -> Capabilities {
+        Capabilities {
+            // Whisper translates any language it recognises into English.
+            native_translation: Some(NativeTranslation { from: None, to: vec!["en".to_string()] }),
+            ..Default::default()
+        }
+    }
 
-    Ok((segments, detected_lang))
+    fn load(&self, model_path: &Path, options: &TranscribeOptions, cfg: &EngineConfig) -> Result<Box<dyn AsrModel>> {
+        tracing::info!(
+            "Whisper: loading model context (model={}, use_gpu={:?})",
+            options.model,
+            cfg.use_gpu
+        );
+        let ctx_start = std::time::Instant::now();
+        let ctx = create_context(
+            model_path,
+            &options.model,
+            cfg.gpu_device,
+            cfg.use_gpu,
+            cfg.enable_dtw,
+            cfg.enable_flash_attn,
+        )
+        .map_err(|e| eyre!("Failed to create Whisper context: {}", e))?;
+        tracing::info!(
+            "Whisper: model context ready in {:.2}s",
+            ctx_start.elapsed().as_secs_f64()
+        );
+        Ok(Box::new(WhisperModel {
+            ctx: Arc::new(ctx),
+            state: None,
+            options: options.clone(),
+            abort: None,
+            detected_lang: None,
+        }))
+    }
+
+    fn cache_key(&self, model_path: &Path, cfg: &EngineConfig) -> String {
+        format!(
+            "{}|device={:?}|dtw={:?}|flash_attn={:?}",
+            model_key(model_path, cfg.use_gpu),
+            cfg.gpu_device,
+            cfg.enable_dtw,
+            cfg.enable_flash_attn
+        )
+    }
 }
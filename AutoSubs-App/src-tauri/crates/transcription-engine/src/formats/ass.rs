@@ -0,0 +1,375 @@
+//! Advanced SubStation Alpha (`.ass`) writer and parser.
+//!
+//! Emits a `Default` style plus one style per speaker (coloured from
+//! [`SpeakerColors`](super::SpeakerColors)), and one `Dialogue:` line per cue.
+//! With [`ExportOptions::karaoke`] each word is prefixed with a `{\k}` tag so
+//! Aegisub, Kdenlive and libass-based players highlight words as they are spoken.
+
+use super::{
+    CueBuilder, ExportOptions, ParsedSubtitles, SpeakerColors, parse_hex_color, parse_timestamp,
+    speakers_in_order, words_by_line,
+};
+use crate::types::{Segment, WordTimestamp};
+use eyre::{Result, bail};
+
+/// Script resolution; style sizes and margins below are in these units.
+const PLAY_RES_X: u32 = 1920;
+const PLAY_RES_Y: u32 = 1080;
+
+const STYLE_FORMAT: &str = "Format: Name, Fontname, Fontsize, PrimaryColour, SecondaryColour, OutlineColour, BackColour, Bold, Italic, Underline, StrikeOut, ScaleX, ScaleY, Spacing, Angle, BorderStyle, Outline, Shadow, Alignment, MarginL, MarginR, MarginV, Encoding";
+const EVENT_FORMAT: &str = "Format: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text";
+
+/// `H:MM:SS.cc` timestamp (ASS times are in centiseconds).
+pub fn timestamp(seconds: f64) -> String {
+    let cs = centiseconds(seconds);
+    format!(
+        "{}:{:02}:{:02}.{:02}",
+        cs / 360_000,
+        (cs / 6000) % 60,
+        (cs / 100) % 60,
+        cs % 100
+    )
+}
+
+fn centiseconds(seconds: f64) -> u64 {
+    (seconds.max(0.0) * 100.0).round() as u64
+}
+
+/// `#RRGGBB[AA]` to ASS `&HAABBGGRR`. ASS alpha is inverted: `00` is opaque.
+fn ass_color(hex: &str) -> Option<String> {
+    let (r, g, b, a) = parse_hex_color(hex)?;
+    Some(format!("&H{:02X}{:02X}{:02X}{:02X}", 255 - a, b, g, r))
+}
+
+/// Style fields that vary per speaker; everything else comes from `Default`.
+struct Style {
+    primary: String,
+    outline_color: String,
+    back_color: String,
+    border_style: u8,
+}
+
+impl Style {
+    fn default_style() -> Self {
+        Self {
+            primary: "&H00FFFFFF".into(),
+            outline_color: "&H00000000".into(),
+            back_color: "&H80000000".into(),
+            border_style: 1,
+        }
+    }
+
+    /// Apply speaker colours. A border colour switches to an opaque box
+    /// (`BorderStyle` 3); VSFilter paints the box with `OutlineColour` and
+    /// libass with `BackColour`, so both are set.
+    fn for_speaker(colors: Option<&SpeakerColors>) -> Self {
+        let mut style = Self::default_style();
+        let Some(colors) = colors else {
+            return style;
+        };
+        if let Some(c) = colors.fill.as_deref().and_then(ass_color) {
+            style.primary = c;
+        }
+        if let Some(c) = colors.outline.as_deref().and_then(ass_color) {
+            style.outline_color = c;
+        }
+        if let Some(c) = colors.border.as_deref().and_then(ass_color) {
+            style.outline_color = c.clone();
+            style.back_color = c;
+            style.border_style = 3;
+        }
+        style
+    }
+
+    fn line(&self, name: &str) -> String {
+        format!(
+            "Style: {name},Arial,64,{},&H00A0A0A0,{},{},0,0,0,0,100,100,0,0,{},3,0,2,60,60,50,1\n",
+            self.primary, self.outline_color, self.back_color, self.border_style
+        )
+    }
+}
+
+/// Style and actor names are comma-separated fields, so commas are replaced.
+fn field(name: &str) -> String {
+    name.replace(',', ";").replace(['\n', '\r'], " ").trim().to_string()
+}
+
+/// ASS has no escape character: `\n`, `\N` and `\h` are always line breaks and
+/// hard spaces, and `{` always opens an override block. So a zero-width joiner
+/// goes between a backslash and those letters, braces become their fullwidth
+/// forms, and line breaks become `\N`.
+fn escape_text(text: &str) -> String {
+    let text = text.trim().replace("\r\n", "\n");
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if matches!(chars.peek(), Some('n' | 'N' | 'h')) => out.push_str("\\\u{200D}"),
+            '{' => out.push('\u{FF5B}'),
+            '}' => out.push('\u{FF5D}'),
+            '\n' => out.push_str("\\N"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Cue text with a `{\kNN}` tag before every word. Durations run from one
+/// word's start to the next, so gaps are absorbed by the preceding word and the
+/// tags always sum to the cue duration.
+fn karaoke_text(seg: &Segment, lines: &[Vec<&WordTimestamp>]) -> String {
+    let cue_start = centiseconds(seg.start);
+    let cue_end = centiseconds(seg.end.max(seg.start));
+    let words: Vec<&WordTimestamp> = lines.iter().flatten().copied().collect();
+    let starts: Vec<u64> = words
+        .iter()
+        .map(|w| centiseconds(w.start).clamp(cue_start, cue_end))
+        .collect();
+
+    let mut out = String::new();
+    if let Some(&first) = starts.first().filter(|&&first| first > cue_start) {
+        out.push_str(&format!("{{\\k{}}}", first - cue_start));
+    }
+    let mut index = 0;
+    for (line_no, line) in lines.iter().enumerate() {
+        if line_no > 0 {
+            out.push_str("\\N");
+        }
+        for (i, word) in line.iter().enumerate() {
+            let next = starts.get(index + 1).copied().unwrap_or(cue_end);
+            let duration = next.saturating_sub(starts[index]);
+            let text = if i == 0 { word.text.trim_start() } else { word.text.as_str() };
+            out.push_str(&format!("{{\\k{duration}}}{}", escape_text_keep_space(text)));
+            index += 1;
+        }
+    }
+    out
+}
+
+/// Like [`escape_text`] but keeps the leading space that separates words.
+fn escape_text_keep_space(text: &str) -> String {
+    let leading = &text[..text.len() - text.trim_start().len()];
+    format!("{leading}{}", escape_text(text))
+}
+
+/// Script header, speaker styles and one `Dialogue:` line per cue.
+pub fn render(segments: &[Segment], opts: &ExportOptions) -> String {
+    let mut out = String::new();
+    out.push_str("[Script Info]\n");
+    out.push_str("; Generated by AutoSubs\n");
+    out.push_str("ScriptType: v4.00+\n");
+    out.push_str(&format!("PlayResX: {PLAY_RES_X}\nPlayResY: {PLAY_RES_Y}\n"));
+    out.push_str("WrapStyle: 0\n");
+    out.push_str("ScaledBorderAndShadow: yes\n");
+    if let Some(lang) = opts.language.as_deref().filter(|l| !l.is_empty() && *l != "auto") {
+        out.push_str(&format!("Language: {lang}\n"));
+    }
+    out.push('\n');
+
+    out.push_str("[V4+ Styles]\n");
+    out.push_str(STYLE_FORMAT);
+    out.push('\n');
+    out.push_str(&Style::default_style().line("Default"));
+    let mut written: Vec<String> = vec!["Default".into()];
+    for id in speakers_in_order(segments) {
+        let name = field(&opts.speaker_name(id));
+        if written.contains(&name) {
+            continue;
+        }
+        out.push_str(&Style::for_speaker(opts.speaker_colors.get(id)).line(&name));
+        written.push(name);
+    }
+    out.push('\n');
+
+    out.push_str("[Events]\n");
+    out.push_str(EVENT_FORMAT);
+    out.push('\n');
+    for seg in segments {
+        let (style, actor) = match seg.speaker_id.as_deref() {
+            Some(id) => {
+                let name = field(&opts.speaker_name(id));
+                (name.clone(), name)
+            }
+            None => ("Default".to_string(), String::new()),
+        };
+        let text = match opts.karaoke.then(|| words_by_line(seg)).flatten() {
+            Some(lines) => karaoke_text(seg, &lines),
+            None => escape_text(&seg.text),
+        };
+        out.push_str(&format!(
+            "Dialogue: 0,{},{},{style},{actor},0,0,0,,{text}\n",
+            timestamp(seg.start),
+            timestamp(seg.end.max(seg.start)),
+        ));
+    }
+    out
+}
+
+/// Parse the `Dialogue:` lines of an `[Events]` section. Field positions come
+/// from the section's `Format:` line (SSA v4 files included). The speaker is
+/// the actor name, or the style when it isn't `Default`; override blocks are
+/// stripped and `\N` becomes a line break.
+pub fn parse(content: &str) -> Result<ParsedSubtitles> {
+    let mut cues = CueBuilder::default();
+    let mut in_events = false;
+    let mut format: Option<Vec<String>> = None;
+    for line in content.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_events = line.eq_ignore_ascii_case("[Events]");
+            continue;
+        }
+        if !in_events {
+            continue;
+        }
+        if let Some(fields) = line.strip_prefix("Format:") {
+            format = Some(fields.split(',').map(|f| f.trim().to_ascii_lowercase()).collect());
+            continue;
+        }
+        let Some(values) = line.strip_prefix("Dialogue:") else {
+            continue;
+        };
+        let Some(format) = format.as_ref() else {
+            bail!("Dialogue line before the [Events] Format line");
+        };
+        // Text is always last and may itself contain commas.
+        let values: Vec<&str> = values.splitn(format.len(), ',').map(str::trim).collect();
+        let get = |name: &str| {
+            format
+                .iter()
+                .position(|f| f == name)
+                .and_then(|i| values.get(i).copied())
+        };
+        let (Some(start), Some(end)) = (
+            get("start").and_then(parse_timestamp),
+            get("end").and_then(parse_timestamp),
+        ) else {
+            tracing::warn!("skipping malformed ASS dialogue: {line}");
+            continue;
+        };
+        let speaker = get("name")
+            .filter(|n| !n.is_empty())
+            .or_else(|| get("style").filter(|s| !s.is_empty() && *s != "Default"));
+        cues.push(start, end, &unescape_text(get("text").unwrap_or_default()), speaker);
+    }
+    if format.is_none() {
+        bail!("no [Events] section found");
+    }
+    Ok(cues.finish())
+}
+
+/// Inverse of [`escape_text`]: drop `{...}` override blocks, decode `\N`, `\n`
+/// and `\h`, drop the joiner after an escaped backslash and turn fullwidth
+/// braces back into ASCII ones. Any other backslash is literal text.
+fn unescape_text(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' => {
+                for c in chars.by_ref() {
+                    if c == '}' {
+                        break;
+                    }
+                }
+            }
+            '\\' => match chars.peek() {
+                Some('N' | 'n') => {
+                    chars.next();
+                    out.push('\n');
+                }
+                Some('h') => {
+                    chars.next();
+                    out.push(' ');
+                }
+                Some('\u{200D}') => {
+                    chars.next();
+                    out.push('\\');
+                }
+                _ => out.push('\\'),
+            },
+            '\u{FF5B}' => out.push('{'),
+            '\u{FF5D}' => out.push('}'),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::tests::{seg, word};
+
+    #[test]
+    fn timestamps_use_centiseconds() {
+        assert_eq!(timestamp(3723.456), "1:02:03.46");
+        assert_eq!(timestamp(0.0), "0:00:00.00");
+    }
+
+    #[test]
+    fn colors_convert_to_bgr_with_inverted_alpha() {
+        assert_eq!(ass_color("#FF8000").as_deref(), Some("&H000080FF"));
+        assert_eq!(ass_color("#00000080").as_deref(), Some("&H7F000000"));
+    }
+
+    #[test]
+    fn speakers_get_their_own_styles() {
+        let mut opts = ExportOptions::default();
+        opts.speaker_names.insert("1".into(), "Host, Main".into());
+        opts.speaker_colors.insert(
+            "1".into(),
+            SpeakerColors { fill: Some("#ffff00".into()), ..Default::default() },
+        );
+        let out = render(
+            &[seg(0.0, 1.0, "Hi {there}", Some("1")), seg(1.0, 2.0, "Bye", Some("2"))],
+            &opts,
+        );
+        assert!(out.contains("Style: Host; Main,Arial,64,&H0000FFFF,"));
+        assert!(out.contains("Style: Speaker 2,Arial,64,&H00FFFFFF,"));
+        assert!(out.contains("Dialogue: 0,0:00:00.00,0:00:01.00,Host; Main,Host; Main,0,0,0,,Hi \u{FF5B}there\u{FF5D}\n"));
+        assert!(out.contains("Dialogue: 0,0:00:01.00,0:00:02.00,Speaker 2,Speaker 2,0,0,0,,Bye\n"));
+    }
+
+    #[test]
+    fn karaoke_tags_cover_the_whole_cue() {
+        let mut cue = seg(1.0, 3.0, "Hello big\nworld", None);
+        cue.words = Some(vec![
+            word("Hello", 1.2, 1.5),
+            word(" big", 1.6, 2.0),
+            word(" world", 2.2, 2.8),
+        ]);
+        let opts = ExportOptions { karaoke: true, ..Default::default() };
+        let out = render(&[cue], &opts);
+        assert!(
+            out.ends_with(",Default,,0,0,0,,{\\k20}{\\k40}Hello{\\k60} big\\N{\\k80}world\n"),
+            "{out}"
+        );
+    }
+
+    #[test]
+    fn text_cannot_start_overrides_or_line_breaks() {
+        let escaped = escape_text("C:\\new\\Hi\\h {\\b1}\r\nnext");
+        assert_eq!(escaped, "C:\\\u{200D}new\\Hi\\\u{200D}h \u{FF5B}\\b1\u{FF5D}\\Nnext");
+        assert_eq!(unescape_text(&escaped), "C:\\new\\Hi\\h {\\b1}\nnext");
+    }
+
+    #[test]
+    fn parses_dialogue_using_the_format_line() {
+        let content = "[Script Info]\nScriptType: v4.00+\n\n[Events]\n\
+                       Format: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text\n\
+                       Comment: 0,0:00:00.00,0:00:01.00,Default,,0,0,0,,ignored\n\
+                       Dialogue: 0,0:00:01.50,0:00:03.00,Host,,0,0,0,,{\\k20}Hi, {\\b1}you\\Nthere\n\
+                       Dialogue: 0,0:00:04.00,0:00:05.00,Default,Guest,0,0,0,,a\\hb C:\\\u{200D}new \\{c}\n";
+        let parsed = parse(content).unwrap();
+        assert_eq!(parsed.segments.len(), 2);
+        assert_eq!(parsed.segments[0].text, "Hi, you\nthere");
+        assert_eq!((parsed.segments[0].start, parsed.segments[0].end), (1.5, 3.0));
+        assert_eq!(parsed.segments[1].text, "a b C:\\new \\");
+        let names: Vec<_> = parsed
+            .segments
+            .iter()
+            .map(|s| parsed.speaker_names[s.speaker_id.as_deref().unwrap()].as_str())
+            .collect();
+        assert_eq!(names, ["Host", "Guest"]);
+    }
+}
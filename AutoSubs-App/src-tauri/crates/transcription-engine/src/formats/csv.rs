@@ -0,0 +1,157 @@
+//! CSV and TSV tables for spreadsheets and data analysis.
+//!
+//! Each file starts with a header row. By default there is one row per cue:
+//!
+//! `cue, start, end, speaker, text, avg_probability`
+//!
+//! With [`ExportOptions::word_rows`] there is one row per word of
+//! `Segment::words` instead, numbered by the cue it belongs to:
+//!
+//! `cue, start, end, speaker, word, probability`
+//!
+//! Cues without word data get word timings interpolated across the cue and no
+//! probability. Times are seconds with millisecond precision; probabilities
+//! are left empty when the engine did not report them. Cue text keeps no line
+//! breaks, so each row is one line in either dialect.
+
+use super::ExportOptions;
+use crate::types::{Segment, WordTimestamp};
+use crate::utils::interpolate_word_timestamps;
+
+/// Field separator and quoting rules.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Dialect {
+    /// Comma-separated, quoted as RFC 4180 requires.
+    Csv,
+    /// Tab-separated, with tabs and line breaks in fields replaced by spaces.
+    Tsv,
+}
+
+impl Dialect {
+    fn separator(self) -> char {
+        match self {
+            Self::Csv => ',',
+            Self::Tsv => '\t',
+        }
+    }
+
+    fn field(self, value: &str) -> String {
+        match self {
+            Self::Csv if value.contains([',', '"', '\n', '\r']) => {
+                format!("\"{}\"", value.replace('"', "\"\""))
+            }
+            Self::Csv => value.to_string(),
+            Self::Tsv => value.replace(['\t', '\n', '\r'], " "),
+        }
+    }
+
+    fn row(self, fields: &[String]) -> String {
+        let sep = self.separator().to_string();
+        let mut line = fields.iter().map(|f| self.field(f)).collect::<Vec<_>>().join(&sep);
+        line.push('\n');
+        line
+    }
+}
+
+fn seconds(t: f64) -> String {
+    format!("{:.3}", t.max(0.0))
+}
+
+fn probability(p: Option<f64>) -> String {
+    p.map(|p| format!("{p:.4}")).unwrap_or_default()
+}
+
+/// Mean probability of the words that report one.
+fn average_probability(words: &[WordTimestamp]) -> Option<f64> {
+    let known: Vec<f64> = words.iter().filter_map(|w| w.probability).map(f64::from).collect();
+    (!known.is_empty()).then(|| known.iter().sum::<f64>() / known.len() as f64)
+}
+
+/// Render a table of cues, or of words when `opts.word_rows` is set.
+pub fn render(segments: &[Segment], dialect: Dialect, opts: &ExportOptions) -> String {
+    let header = if opts.word_rows {
+        ["cue", "start", "end", "speaker", "word", "probability"]
+    } else {
+        ["cue", "start", "end", "speaker", "text", "avg_probability"]
+    };
+    let mut out = dialect.row(&header.map(str::to_string));
+
+    let cues = segments.iter().filter(|s| !s.text.trim().is_empty());
+    for (i, seg) in cues.enumerate() {
+        let cue = (i + 1).to_string();
+        let speaker = seg.speaker_id.as_deref().map(|id| opts.speaker_name(id)).unwrap_or_default();
+        let text = seg.text.lines().map(str::trim).filter(|l| !l.is_empty()).collect::<Vec<_>>().join(" ");
+        let words = match seg.words.as_deref() {
+            Some(words) if !words.is_empty() => words.to_vec(),
+            _ => interpolate_word_timestamps(&text, seg.start, seg.end.max(seg.start)),
+        };
+
+        if opts.word_rows {
+            for w in words.iter().filter(|w| !w.text.trim().is_empty()) {
+                out.push_str(&dialect.row(&[
+                    cue.clone(),
+                    seconds(w.start),
+                    seconds(w.end),
+                    speaker.clone(),
+                    w.text.trim().to_string(),
+                    probability(w.probability.map(f64::from)),
+                ]));
+            }
+        } else {
+            out.push_str(&dialect.row(&[
+                cue,
+                seconds(seg.start),
+                seconds(seg.end),
+                speaker,
+                text,
+                probability(average_probability(&words)),
+            ]));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::tests::{seg, word};
+
+    fn segments() -> Vec<Segment> {
+        let mut first = seg(0.5, 2.0, "Hello, \"world\"\nagain", Some("1"));
+        first.words = Some(vec![
+            WordTimestamp { probability: Some(0.9), ..word("Hello,", 0.5, 0.9) },
+            WordTimestamp { probability: Some(0.5), ..word(" \"world\"", 1.0, 1.4) },
+            word(" again", 1.5, 2.0),
+        ]);
+        vec![first, seg(2.0, 2.5, " ", None), seg(3.0, 4.0, "Not\tyet", None)]
+    }
+
+    #[test]
+    fn cue_rows_average_the_known_word_probabilities() {
+        let mut opts = ExportOptions::default();
+        opts.speaker_names.insert("1".into(), "Ann, PhD".into());
+        let csv = render(&segments(), Dialect::Csv, &opts);
+        assert_eq!(
+            csv,
+            "cue,start,end,speaker,text,avg_probability\n\
+             1,0.500,2.000,\"Ann, PhD\",\"Hello, \"\"world\"\" again\",0.7000\n\
+             2,3.000,4.000,,Not\tyet,\n"
+        );
+
+        let tsv = render(&segments(), Dialect::Tsv, &opts);
+        assert_eq!(tsv.lines().nth(2), Some("2\t3.000\t4.000\t\tNot yet\t"));
+    }
+
+    #[test]
+    fn word_rows_come_from_segment_words() {
+        let opts = ExportOptions { word_rows: true, ..Default::default() };
+        let tsv = render(&segments(), Dialect::Tsv, &opts);
+        let rows: Vec<&str> = tsv.lines().collect();
+        assert_eq!(rows[0], "cue\tstart\tend\tspeaker\tword\tprobability");
+        assert_eq!(rows[1], "1\t0.500\t0.900\tSpeaker 1\tHello,\t0.9000");
+        assert_eq!(rows[2], "1\t1.000\t1.400\tSpeaker 1\t\"world\"\t0.5000");
+        assert_eq!(rows[3], "1\t1.500\t2.000\tSpeaker 1\tagain\t");
+        // Interpolated across the cue when the engine gave no word timings.
+        assert_eq!(rows[4..], ["2\t3.000\t3.500\t\tNot\t", "2\t3.500\t4.000\t\tyet\t"]);
+    }
+}
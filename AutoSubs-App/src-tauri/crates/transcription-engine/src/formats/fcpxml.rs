@@ -0,0 +1,182 @@
+//! Final Cut Pro X (`.fcpxml`) writer.
+//!
+//! Produces a library with one event and project whose sequence holds every
+//! cue as an iTT `<caption>` on lane 1 of a gap clip, tagged with the caption
+//! role for [`ExportOptions::language`]. Speakers get their own text style
+//! carrying their [`SpeakerColors`](super::SpeakerColors).
+//!
+//! All times are whole frames at [`ExportOptions::sequence_rate`]. Segment
+//! times are read relative to the sequence start, which is how transcripts
+//! made with a timeline offset already come back, and the sequence starts at
+//! [`ExportOptions::start_frame`].
+
+use super::{
+    ExportOptions, FrameRate, gcd, parse_hex_color, speakers_in_order, track_cues,
+    xml_escape,
+};
+use crate::types::Segment;
+
+const VERSION: &str = "1.9";
+
+/// FCPXML time value for a frame count, e.g. `1001/30000s` or `3600s`.
+fn rational(frames: u64, rate: FrameRate) -> String {
+    let (num, den) = (frames * rate.den as u64, rate.num as u64);
+    let g = gcd(num, den).max(1);
+    match (num / g, den / g) {
+        (n, 1) => format!("{n}s"),
+        (n, d) => format!("{n}/{d}s"),
+    }
+}
+
+/// `#RRGGBB[AA]` to FCPXML's `"r g b a"` unit floats.
+fn fcp_color(hex: &str) -> Option<String> {
+    let (r, g, b, a) = parse_hex_color(hex)?;
+    let unit = |v: u8| ((v as f64 / 255.0) * 1000.0).round() / 1000.0;
+    Some(format!("{} {} {} {}", unit(r), unit(g), unit(b), unit(a)))
+}
+
+/// Render an FCPXML document with one caption per cue.
+pub fn render(segments: &[Segment], opts: &ExportOptions) -> String {
+    let rate = opts.sequence_rate();
+    let lang = opts
+        .language
+        .as_deref()
+        .filter(|l| !l.is_empty() && *l != "auto")
+        .unwrap_or("en");
+    let role = format!("iTT?captionFormat=ITT.{}", xml_escape(lang));
+    let cues = track_cues(segments, rate);
+    let duration = cues.last().map_or(0, |&(_, end, _)| end);
+    let tc_start = rational(opts.start_frame, rate);
+
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<!DOCTYPE fcpxml>\n");
+    out.push_str(&format!("<fcpxml version=\"{VERSION}\">\n"));
+    out.push_str("  <resources>\n");
+    out.push_str(&format!(
+        "    <format id=\"r1\" frameDuration=\"{}\" width=\"1920\" height=\"1080\"/>\n",
+        rational(1, rate)
+    ));
+    out.push_str("  </resources>\n");
+    out.push_str("  <library>\n    <event name=\"AutoSubs\">\n      <project name=\"AutoSubs Captions\">\n");
+    out.push_str(&format!(
+        "        <sequence format=\"r1\" duration=\"{}\" tcStart=\"{tc_start}\" tcFormat=\"{}\">\n",
+        rational(duration, rate),
+        if rate.is_drop_frame() { "DF" } else { "NDF" }
+    ));
+    if cues.is_empty() {
+        out.push_str("          <spine/>\n");
+    } else {
+        out.push_str("          <spine>\n");
+        out.push_str(&format!(
+            "            <gap name=\"Gap\" offset=\"{tc_start}\" start=\"{tc_start}\" duration=\"{}\">\n",
+            rational(duration, rate)
+        ));
+        // Text styles are defined on a speaker's first caption and referenced
+        // after that; ids must be unique across the document.
+        let speakers = speakers_in_order(segments);
+        let mut defined = vec![false; speakers.len() + 1];
+        for (start, end, seg) in cues {
+            let style = seg
+                .speaker_id
+                .as_deref()
+                .and_then(|id| speakers.iter().position(|s| *s == id))
+                .map_or(0, |i| i + 1);
+            let text = seg.text.lines().map(str::trim).filter(|l| !l.is_empty()).collect::<Vec<_>>();
+            out.push_str(&format!(
+                "              <caption name=\"{}\" lane=\"1\" offset=\"{}\" duration=\"{}\" role=\"{role}\">\n",
+                xml_escape(&text.join(" ")),
+                rational(opts.start_frame + start, rate),
+                rational(end - start, rate)
+            ));
+            out.push_str(&format!(
+                "                <text placement=\"bottom\"><text-style ref=\"ts{style}\">{}</text-style></text>\n",
+                xml_escape(&text.join("\n"))
+            ));
+            if !defined[style] {
+                defined[style] = true;
+                let colors = seg.speaker_id.as_deref().and_then(|id| opts.speaker_colors.get(id));
+                let fill = colors.and_then(|c| c.fill.as_deref()).and_then(fcp_color);
+                let back = colors.and_then(|c| c.border.as_deref()).and_then(fcp_color);
+                out.push_str(&format!(
+                    "                <text-style-def id=\"ts{style}\"><text-style font=\"Helvetica\" fontSize=\"13\" fontFace=\"Regular\" fontColor=\"{}\" backgroundColor=\"{}\"/></text-style-def>\n",
+                    fill.as_deref().unwrap_or("1 1 1 1"),
+                    back.as_deref().unwrap_or("0 0 0 1")
+                ));
+            }
+            out.push_str("              </caption>\n");
+        }
+        out.push_str("            </gap>\n");
+        out.push_str("          </spine>\n");
+    }
+    out.push_str("        </sequence>\n      </project>\n    </event>\n  </library>\n</fcpxml>\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::SpeakerColors;
+    use crate::formats::tests::{Element, parse_xml, seg};
+
+    fn captions(root: &Element) -> Vec<&Element> {
+        let mut all = Vec::new();
+        root.walk(&mut all);
+        all.into_iter().filter(|e| e.name == "caption").collect()
+    }
+
+    #[test]
+    fn captions_sit_on_the_sequence_timeline_in_frames() {
+        let segments = vec![
+            seg(1.0, 2.5, "Fish & chips\nfor two", Some("1")),
+            seg(2.0, 3.0, "Overlapping.", Some("2")),
+            seg(4.0, 5.0, "  ", None),
+        ];
+        let mut opts = ExportOptions {
+            frame_rate: FrameRate::parse("29.97"),
+            language: Some("fr".into()),
+            ..Default::default()
+        };
+        opts.start_frame = opts.frame_rate.unwrap().parse_timecode("01:00:00;00").unwrap();
+        opts.speaker_colors.insert(
+            "2".into(),
+            SpeakerColors { fill: Some("#FF0000".into()), ..Default::default() },
+        );
+        let doc = render(&segments, &opts);
+        let root = parse_xml(&doc);
+        assert_eq!(root.name, "fcpxml");
+
+        let mut all = Vec::new();
+        root.walk(&mut all);
+        let format = all.iter().find(|e| e.name == "format").unwrap();
+        assert_eq!(format.attr("frameDuration"), Some("1001/30000s"));
+        let sequence = all.iter().find(|e| e.name == "sequence").unwrap();
+        assert_eq!(sequence.attr("tcFormat"), Some("DF"));
+        // 107892 frames of 1001/30000s.
+        assert_eq!(sequence.attr("tcStart"), Some("8999991/2500s"));
+
+        let caps = captions(&root);
+        assert_eq!(caps.len(), 2, "blank cues are dropped");
+        assert_eq!(caps[0].attr("role"), Some("iTT?captionFormat=ITT.fr"));
+        assert_eq!(caps[0].attr("offset"), Some(rational(107_892 + 30, FrameRate::parse("29.97").unwrap()).as_str()));
+        // Trimmed to end where the next caption starts (frame 60).
+        assert_eq!(caps[0].attr("duration"), Some("1001/1000s"));
+        assert!(doc.contains("Fish &amp; chips\nfor two</text-style>"));
+        assert!(doc.contains("id=\"ts2\"><text-style font=\"Helvetica\" fontSize=\"13\" fontFace=\"Regular\" fontColor=\"1 0 0 1\""));
+    }
+
+    #[test]
+    fn speaker_styles_are_defined_once() {
+        let segments = vec![
+            seg(0.0, 1.0, "One.", Some("1")),
+            seg(1.0, 2.0, "Two.", Some("1")),
+            seg(2.0, 3.0, "Three.", None),
+        ];
+        let doc = render(&segments, &ExportOptions::default());
+        parse_xml(&doc);
+        assert_eq!(doc.matches("<text-style-def id=\"ts1\"").count(), 1);
+        assert_eq!(doc.matches("ref=\"ts1\"").count(), 2);
+        assert!(doc.contains("ref=\"ts0\""));
+        assert!(doc.contains("frameDuration=\"1/25s\""));
+        assert!(doc.contains("role=\"iTT?captionFormat=ITT.en\""));
+        assert!(render(&[], &ExportOptions::default()).contains("<spine/>"));
+    }
+}
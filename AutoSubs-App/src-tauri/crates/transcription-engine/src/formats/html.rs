@@ -0,0 +1,144 @@
+//! Self-contained HTML transcript document.
+//!
+//! The same paragraphs and speaker turns as the Markdown document, in one file
+//! with inline styles and no external resources. Every paragraph has an anchor
+//! and its time links to it, so a reader can share a link to any point. With
+//! [`ExportOptions::media_url`] the page embeds a player for the recording and
+//! clicking a time seeks it there. Speaker headings take the speaker's fill
+//! colour when one is set.
+
+use super::text::paragraphs;
+use super::{ExportOptions, parse_hex_color, ts_clock, xml_escape};
+use crate::types::Segment;
+
+const STYLE: &str = "\
+body { font: 17px/1.6 system-ui, sans-serif; color: #222; background: #fff; margin: 0; }
+main { max-width: 44em; margin: 0 auto; padding: 2em 1em; }
+.player { position: sticky; top: 0; width: 100%; background: #fff; }
+video.player { max-height: 40vh; }
+h2 { font-size: 1.05em; margin: 1.8em 0 0.4em; }
+p { margin: 0 0 0.9em; }
+a.time { color: #777; font: 0.85em ui-monospace, monospace; text-decoration: none; margin-right: 0.6em; }
+a.time:hover { text-decoration: underline; }
+:target { background: #fff6c8; }
+";
+
+const SCRIPT: &str = "\
+const media = document.getElementById(\"media\");
+document.addEventListener(\"click\", (event) => {
+  const link = event.target.closest(\"a.time\");
+  if (!link) return;
+  event.preventDefault();
+  history.replaceState(null, \"\", link.hash);
+  media.currentTime = Number(link.dataset.t);
+  media.play();
+});
+";
+
+/// File extensions embedded with `<video>` rather than `<audio>`.
+const VIDEO_EXTENSIONS: &[&str] = &["mp4", "m4v", "mov", "webm", "mkv", "ogv"];
+
+fn player(url: &str) -> String {
+    let ext = url.rsplit('.').next().unwrap_or_default().to_ascii_lowercase();
+    let tag = if VIDEO_EXTENSIONS.contains(&ext.as_str()) { "video" } else { "audio" };
+    format!("<{tag} id=\"media\" class=\"player\" controls preload=\"metadata\" src=\"{}\"></{tag}>\n", xml_escape(url))
+}
+
+fn heading(id: &str, opts: &ExportOptions) -> String {
+    let color = opts
+        .speaker_colors
+        .get(id)
+        .and_then(|c| c.fill.as_deref())
+        .and_then(parse_hex_color)
+        .map(|(r, g, b, _)| format!(" style=\"color: #{r:02x}{g:02x}{b:02x}\""))
+        .unwrap_or_default();
+    format!("<h2{color}>{}</h2>\n", xml_escape(&opts.speaker_name(id)))
+}
+
+/// Render the transcript as a standalone HTML page.
+pub fn render(segments: &[Segment], opts: &ExportOptions) -> String {
+    let lang = opts.language.as_deref().filter(|l| !l.is_empty() && *l != "auto").unwrap_or("und");
+    let mut out = format!(
+        "<!DOCTYPE html>\n<html lang=\"{}\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>Transcript</title>\n<style>\n{STYLE}</style>\n</head>\n<body>\n<main>\n",
+        xml_escape(lang)
+    );
+    if let Some(url) = opts.media_url.as_deref() {
+        out.push_str(&player(url));
+    }
+
+    let paras = paragraphs(segments, true);
+    let mut n = 0;
+    for turn in paras.chunk_by(|a, b| a.speaker_id == b.speaker_id) {
+        if let Some(id) = turn[0].speaker_id.as_deref() {
+            out.push_str(&heading(id, opts));
+        }
+        for (i, p) in turn.iter().enumerate() {
+            n += 1;
+            out.push_str(&format!("<p id=\"p{n}\">"));
+            if i == 0 || opts.paragraph_timecodes {
+                out.push_str(&format!(
+                    "<a class=\"time\" href=\"#p{n}\" data-t=\"{:.3}\">{}</a>",
+                    p.start.max(0.0),
+                    ts_clock(p.start)
+                ));
+            }
+            let text = p.text.split_whitespace().collect::<Vec<_>>().join(" ");
+            out.push_str(&format!("{}</p>\n", xml_escape(&text)));
+        }
+    }
+
+    out.push_str("</main>\n");
+    if opts.media_url.is_some() {
+        out.push_str(&format!("<script>\n{SCRIPT}</script>\n"));
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::SpeakerColors;
+    use crate::formats::tests::seg;
+
+    #[test]
+    fn paragraphs_are_anchored_under_coloured_headings() {
+        let segments = vec![
+            seg(0.0, 1.0, "Fish & <chips>", Some("1")),
+            seg(9.0, 10.0, "Later.", Some("1")),
+            seg(12.25, 13.0, "Hi!", Some("2")),
+        ];
+        let mut opts = ExportOptions { language: Some("en".into()), ..Default::default() };
+        opts.speaker_names.insert("1".into(), "Ann".into());
+        opts.speaker_colors.insert("1".into(), SpeakerColors { fill: Some("#E64B3C".into()), ..Default::default() });
+
+        let out = render(&segments, &opts);
+        assert!(out.starts_with("<!DOCTYPE html>\n<html lang=\"en\">"));
+        assert!(out.contains(
+            "<h2 style=\"color: #e64b3c\">Ann</h2>\n\
+             <p id=\"p1\"><a class=\"time\" href=\"#p1\" data-t=\"0.000\">00:00:00</a>Fish &amp; &lt;chips&gt;</p>\n\
+             <p id=\"p2\">Later.</p>\n\
+             <h2>Speaker 2</h2>\n\
+             <p id=\"p3\"><a class=\"time\" href=\"#p3\" data-t=\"12.250\">00:00:12</a>Hi!</p>\n"
+        ));
+        assert!(!out.contains("<script>"));
+    }
+
+    #[test]
+    fn media_embeds_a_seeking_player() {
+        let opts = ExportOptions {
+            media_url: Some("interview.MOV".into()),
+            paragraph_timecodes: true,
+            ..Default::default()
+        };
+        let out = render(&[seg(0.0, 1.0, "One.", None), seg(5.0, 6.0, "Two.", None)], &opts);
+        assert!(out.contains("<video id=\"media\" class=\"player\" controls preload=\"metadata\" src=\"interview.MOV\">"));
+        assert!(out.contains("href=\"#p2\" data-t=\"5.000\">00:00:05</a>Two.</p>"));
+        assert!(out.contains("<script>\n"));
+
+        let audio = ExportOptions { media_url: Some("talk.mp3".into()), ..Default::default() };
+        assert!(render(&[], &audio).contains("<audio id=\"media\""));
+    }
+}
@@ -0,0 +1,126 @@
+//! LRC lyrics writer.
+//!
+//! Each rendered line gets a `[mm:ss.xx]` tag at the time it starts. With
+//! [`ExportOptions::karaoke`] the file is enhanced LRC: every word is preceded
+//! by a `<mm:ss.xx>` tag from `Segment::words`, and the line ends with a tag at
+//! its last word's end. LRC players hold a line until the next tag, so an empty
+//! line is written wherever the lyrics pause for [`CLEAR_GAP_SECS`] or more.
+//!
+//! Cues are best produced with [`PostProcessConfig::lyrics`], which breaks at
+//! sung line ends instead of subtitle line lengths.
+//!
+//! [`PostProcessConfig::lyrics`]: crate::formatting::PostProcessConfig::lyrics
+
+use super::{ExportOptions, words_by_line};
+use crate::types::{Segment, WordTimestamp};
+
+/// Pause after a line that clears it from the screen.
+const CLEAR_GAP_SECS: f64 = 1.0;
+
+/// `mm:ss.xx` at centisecond precision; minutes keep counting past 99.
+fn lrc_time(seconds: f64) -> String {
+    let cs = (seconds.max(0.0) * 100.0).round() as u64;
+    format!("{:02}:{:02}.{:02}", cs / 6000, (cs / 100) % 60, cs % 100)
+}
+
+/// One line of enhanced LRC: `<start>word <start>word<end>`. Spacing comes
+/// from the words themselves, so scripts without spaces stay unspaced.
+fn karaoke_line(words: &[&WordTimestamp]) -> String {
+    let mut out = String::new();
+    for (i, w) in words.iter().enumerate() {
+        let raw = if i == 0 { w.text.trim_start() } else { w.text.as_str() };
+        let trimmed = raw.trim_start();
+        out.push_str(&raw[..raw.len() - trimmed.len()]);
+        out.push_str(&format!("<{}>{}", lrc_time(w.start), trimmed.trim_end()));
+    }
+    if let Some(last) = words.last() {
+        out.push_str(&format!("<{}>", lrc_time(last.end)));
+    }
+    out
+}
+
+/// Render an LRC file, enhanced with per-word tags when `opts.karaoke` is set.
+pub fn render(segments: &[Segment], opts: &ExportOptions) -> String {
+    let cues: Vec<&Segment> = segments.iter().filter(|s| !s.text.trim().is_empty()).collect();
+    let mut out = String::from("[re:AutoSubs]\n");
+    if let Some(last) = cues.iter().map(|s| s.end).reduce(f64::max) {
+        let secs = last.max(0.0).round() as u64;
+        out.push_str(&format!("[length:{:02}:{:02}]\n", secs / 60, secs % 60));
+    }
+
+    let mut speaker: Option<&str> = None;
+    for (i, seg) in cues.iter().enumerate() {
+        // Label speaker turns, not every line.
+        let prefix = if seg.speaker_id.is_some() && seg.speaker_id.as_deref() != speaker {
+            opts.speaker_prefix(seg.speaker_id.as_deref())
+        } else {
+            String::new()
+        };
+        speaker = seg.speaker_id.as_deref();
+
+        // Tag each rendered line with its first word's start when the words
+        // can be matched to the lines; otherwise the cue is a single line.
+        let text: Vec<&str> = seg.text.lines().map(str::trim).filter(|l| !l.is_empty()).collect();
+        match words_by_line(seg).filter(|lines| lines.len() == text.len()) {
+            Some(lines) => {
+                for (n, (line, words)) in text.iter().zip(&lines).enumerate() {
+                    let start = words.first().map_or(seg.start, |w| w.start);
+                    let prefix = if n == 0 { prefix.as_str() } else { "" };
+                    let line = if opts.karaoke && !words.is_empty() { karaoke_line(words) } else { line.to_string() };
+                    out.push_str(&format!("[{}]{prefix}{line}\n", lrc_time(start)));
+                }
+            }
+            None => out.push_str(&format!("[{}]{prefix}{}\n", lrc_time(seg.start), text.join(" "))),
+        }
+
+        let clears = cues.get(i + 1).is_none_or(|next| next.start - seg.end >= CLEAR_GAP_SECS);
+        if clears {
+            out.push_str(&format!("[{}]\n", lrc_time(seg.end)));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::tests::{seg, word};
+
+    #[test]
+    fn lines_are_tagged_and_pauses_clear_the_screen() {
+        let segments = vec![
+            seg(12.0, 14.5, "Hello darkness", None),
+            seg(14.5, 17.25, "my old friend", None),
+            seg(20.0, 22.0, " ", None),
+            seg(605.126, 607.0, "I've come to talk", None),
+        ];
+        let out = render(&segments, &ExportOptions::default());
+        assert_eq!(
+            out,
+            "[re:AutoSubs]\n[length:10:07]\n\
+             [00:12.00]Hello darkness\n\
+             [00:14.50]my old friend\n\
+             [00:17.25]\n\
+             [10:05.13]I've come to talk\n\
+             [10:07.00]\n"
+        );
+    }
+
+    #[test]
+    fn karaoke_tags_every_word_and_labels_speaker_turns() {
+        let mut first = seg(1.0, 3.0, "Hello\nthere", Some("1"));
+        first.words = Some(vec![word("Hello", 1.0, 1.5), word(" there", 2.0, 2.75)]);
+        let mut second = seg(3.0, 4.0, "Again", Some("1"));
+        second.words = Some(vec![word("Again", 3.0, 4.0)]);
+        let mut opts = ExportOptions { karaoke: true, ..Default::default() };
+        opts.speaker_names.insert("1".into(), "Lead".into());
+
+        let out = render(&[first, second], &opts);
+        assert!(out.contains("[00:01.00]Lead: <00:01.00>Hello<00:01.50>\n"));
+        assert!(out.contains("[00:02.00]<00:02.00>there<00:02.75>\n"));
+        assert!(out.contains("[00:03.00]<00:03.00>Again<00:04.00>\n[00:04.00]\n"));
+
+        opts.karaoke = false;
+        assert!(render(&[seg(0.0, 1.0, "Plain", None)], &opts).contains("[00:00.00]Plain\n"));
+    }
+}
@@ -0,0 +1,106 @@
+//! Markdown transcript document.
+//!
+//! Cues are merged into paragraphs as in the plain-text transcript, with a
+//! `##` heading at each speaker turn. The first paragraph of a turn starts
+//! with its `[HH:MM:SS]` time; [`ExportOptions::paragraph_timecodes`] stamps
+//! every paragraph. With [`ExportOptions::media_url`] the times link to that
+//! point of the recording as media-fragment (`#t=`) URLs.
+
+use super::text::paragraphs;
+use super::{ExportOptions, ts_clock};
+use crate::types::Segment;
+
+/// Escape inline Markdown so transcribed text renders literally.
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '|') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    // Keep a leading "- ", "+ " or "1. " from becoming a list item.
+    let digits = out.find(|c: char| !c.is_ascii_digit()).unwrap_or(0);
+    if out.starts_with(['-', '+']) {
+        out.insert(0, '\\');
+    } else if digits > 0 && out[digits..].starts_with(['.', ')']) {
+        out.insert(digits, '\\');
+    }
+    out
+}
+
+/// `[HH:MM:SS]`, linked into the recording when there is one.
+fn timestamp(seconds: f64, opts: &ExportOptions) -> String {
+    let label = ts_clock(seconds);
+    match opts.media_url.as_deref() {
+        Some(url) => {
+            let target = format!("{url}#t={}", seconds.max(0.0).floor() as u64);
+            if target.contains(|c: char| c.is_whitespace() || c == '(' || c == ')') {
+                format!("[{label}](<{target}>)")
+            } else {
+                format!("[{label}]({target})")
+            }
+        }
+        None => format!("\\[{label}\\]"),
+    }
+}
+
+/// Render the transcript as a Markdown document.
+pub fn render(segments: &[Segment], opts: &ExportOptions) -> String {
+    let mut blocks = Vec::new();
+    let paras = paragraphs(segments, true);
+    for turn in paras.chunk_by(|a, b| a.speaker_id == b.speaker_id) {
+        if let Some(id) = turn[0].speaker_id.as_deref() {
+            blocks.push(format!("## {}", escape(&opts.speaker_name(id))));
+        }
+        for (i, p) in turn.iter().enumerate() {
+            let text = escape(&p.text.split_whitespace().collect::<Vec<_>>().join(" "));
+            if i == 0 || opts.paragraph_timecodes {
+                blocks.push(format!("{} {text}", timestamp(p.start, opts)));
+            } else {
+                blocks.push(text);
+            }
+        }
+    }
+    if blocks.is_empty() {
+        return String::new();
+    }
+    blocks.join("\n\n") + "\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::tests::seg;
+
+    #[test]
+    fn speaker_turns_get_headings_and_linked_times() {
+        let segments = vec![
+            seg(0.0, 1.0, "Hello,", Some("1")),
+            seg(1.2, 2.0, "world.", Some("1")),
+            seg(65.5, 67.0, "After *a* pause.", Some("1")),
+            seg(68.0, 69.0, "1. Reply", Some("2")),
+        ];
+        let mut opts = ExportOptions { media_url: Some("My Talk.mp3".into()), ..Default::default() };
+        opts.speaker_names.insert("1".into(), "Ann".into());
+        assert_eq!(
+            render(&segments, &opts),
+            "## Ann\n\n\
+             [00:00:00](<My Talk.mp3#t=0>) Hello, world.\n\n\
+             After \\*a\\* pause.\n\n\
+             ## Speaker 2\n\n\
+             [00:01:08](<My Talk.mp3#t=68>) 1\\. Reply\n"
+        );
+
+        opts.media_url = Some("talk.mp3".into());
+        opts.paragraph_timecodes = true;
+        assert!(render(&segments, &opts).contains("\n[00:01:05](talk.mp3#t=65) After"));
+    }
+
+    #[test]
+    fn undiarized_paragraphs_break_at_pauses() {
+        let segments = vec![seg(0.0, 1.0, "One.", None), seg(6.0, 7.0, "- Two.", None)];
+        let opts = ExportOptions { paragraph_timecodes: true, ..Default::default() };
+        assert_eq!(render(&segments, &opts), "\\[00:00:00\\] One.\n\n\\[00:00:06\\] \\- Two.\n");
+    }
+}
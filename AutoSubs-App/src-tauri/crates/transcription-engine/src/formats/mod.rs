@@ -0,0 +1,979 @@
+//! Subtitle and transcript export.
+//!
+//! Renders engine [`Segment`]s into the file formats AutoSubs can write. This is
+//! the single implementation shared by the desktop app (`export_subtitles`), the
+//! headless CLI and library users, so every path produces byte-identical output.
+//! SRT, VTT and ASS can also be [`parse`]d back into segments, so subtitles
+//! received from elsewhere can be reformatted, translated or re-timed.
+//!
+//! - **Text**: readable transcript, one speaker-labelled paragraph per turn
+//! - **Markdown** / **HTML**: transcript documents with speaker headings and
+//!   clickable timestamps
+//! - **SRT**: SubRip cues
+//! - **VTT**: WebVTT cues
+//! - **ASS**: Advanced SubStation Alpha with per-speaker styles and karaoke
+//! - **IMSC1** / **EBU-TT-D**: TTML profiles for broadcast and streaming delivery
+//! - **SCC**: CEA-608 line-21 captions, pop-on or roll-up
+//! - **FCPXML** / **xmeml**: caption timelines for Final Cut Pro and Premiere Pro
+//! - **OTIO**: OpenTimelineIO timeline of cue markers and speaker turns
+//! - **LRC**: synced lyrics, with per-word enhanced LRC tags for karaoke
+//! - **EBU STL**: binary Tech 3264 teletext subtitles (see [`render_bytes`])
+//! - **CSV** / **TSV**: spreadsheet tables with one row per cue or per word
+
+use crate::formatting::{ScriptProfile, profile_for_lang, profile_for_text};
+use crate::types::{Segment, WordTimestamp};
+use crate::utils::interpolate_word_timestamps;
+use eyre::{Result, bail};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+pub mod ass;
+pub mod csv;
+pub mod fcpxml;
+pub mod html;
+pub mod lrc;
+pub mod markdown;
+pub mod otio;
+pub mod scc;
+pub mod srt;
+pub mod stl;
+pub mod text;
+pub mod ttml;
+pub mod vtt;
+pub mod xmeml;
+
+/// Output formats supported by [`render`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SubtitleFormat {
+    Text,
+    /// Markdown transcript document.
+    Markdown,
+    /// Self-contained HTML transcript document.
+    Html,
+    Srt,
+    Vtt,
+    Ass,
+    /// IMSC1 Text Profile (TTML).
+    Imsc,
+    /// EBU-TT-D (TTML).
+    EbuTtD,
+    /// Scenarist CEA-608 captions.
+    Scc,
+    /// Final Cut Pro X caption timeline.
+    Fcpxml,
+    /// Final Cut Pro 7 XML interchange, as imported by Premiere Pro.
+    Xmeml,
+    /// OpenTimelineIO timeline.
+    Otio,
+    /// LRC synced lyrics (enhanced LRC with karaoke).
+    Lrc,
+    /// EBU STL (Tech 3264), a binary format; see [`render_bytes`].
+    Stl,
+    /// Comma-separated table.
+    Csv,
+    /// Tab-separated table.
+    Tsv,
+}
+
+impl SubtitleFormat {
+    /// Every format, in the order they are listed to users.
+    pub const ALL: &'static [SubtitleFormat] = &[
+        Self::Text,
+        Self::Markdown,
+        Self::Html,
+        Self::Srt,
+        Self::Vtt,
+        Self::Ass,
+        Self::Imsc,
+        Self::EbuTtD,
+        Self::Scc,
+        Self::Fcpxml,
+        Self::Xmeml,
+        Self::Otio,
+        Self::Lrc,
+        Self::Stl,
+        Self::Csv,
+        Self::Tsv,
+    ];
+
+    /// Parse a user-supplied format name or file extension, case-insensitively.
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().trim_start_matches('.').to_ascii_lowercase().as_str() {
+            "text" | "txt" => Some(Self::Text),
+            "markdown" | "md" => Some(Self::Markdown),
+            "html" | "htm" => Some(Self::Html),
+            "srt" => Some(Self::Srt),
+            "vtt" | "webvtt" => Some(Self::Vtt),
+            "ass" | "ssa" => Some(Self::Ass),
+            "imsc" | "imsc1" | "ttml" | "dfxp" => Some(Self::Imsc),
+            "ebu-tt-d" | "ebuttd" | "ebu-tt" | "ebuttd.xml" => Some(Self::EbuTtD),
+            "scc" => Some(Self::Scc),
+            "fcpxml" | "fcpx" | "fcp" => Some(Self::Fcpxml),
+            "xmeml" | "premiere" | "fcp7" | "xml" => Some(Self::Xmeml),
+            "otio" | "opentimelineio" => Some(Self::Otio),
+            "lrc" | "lyrics" => Some(Self::Lrc),
+            "stl" | "ebu-stl" | "ebustl" => Some(Self::Stl),
+            "csv" => Some(Self::Csv),
+            "tsv" | "tab" => Some(Self::Tsv),
+            _ => None,
+        }
+    }
+
+    /// Infer the format from a file path's extension, trying compound
+    /// extensions such as `.ebuttd.xml` before the last one.
+    pub fn from_path(path: &std::path::Path) -> Option<Self> {
+        let name = path.file_name()?.to_str()?;
+        name.match_indices('.').find_map(|(i, _)| Self::parse(&name[i + 1..]))
+    }
+
+    /// Conventional file extension, without the leading dot.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Text => "txt",
+            Self::Markdown => "md",
+            Self::Html => "html",
+            Self::Srt => "srt",
+            Self::Vtt => "vtt",
+            Self::Ass => "ass",
+            Self::Imsc => "ttml",
+            // Plain `.xml` is Premiere's xmeml; EBU-TT-D documents are
+            // conventionally named `.ebuttd.xml`.
+            Self::EbuTtD => "ebuttd.xml",
+            Self::Scc => "scc",
+            Self::Fcpxml => "fcpxml",
+            Self::Xmeml => "xml",
+            Self::Otio => "otio",
+            Self::Lrc => "lrc",
+            Self::Stl => "stl",
+            Self::Csv => "csv",
+            Self::Tsv => "tsv",
+        }
+    }
+
+    /// Whether files are binary, written with [`render_bytes`] rather than
+    /// [`render`].
+    pub fn is_binary(self) -> bool {
+        matches!(self, Self::Stl)
+    }
+
+    /// Whether [`parse`] can read this format back.
+    pub fn importable(self) -> bool {
+        matches!(self, Self::Srt | Self::Vtt | Self::Ass)
+    }
+
+    /// Human-readable name used in status messages.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Text => "transcript",
+            Self::Markdown => "Markdown",
+            Self::Html => "HTML",
+            Self::Srt => "SRT",
+            Self::Vtt => "VTT",
+            Self::Ass => "ASS",
+            Self::Imsc => "IMSC1",
+            Self::EbuTtD => "EBU-TT-D",
+            Self::Scc => "SCC",
+            Self::Fcpxml => "FCPXML",
+            Self::Xmeml => "xmeml",
+            Self::Otio => "OTIO",
+            Self::Lrc => "LRC",
+            Self::Stl => "EBU STL",
+            Self::Csv => "CSV",
+            Self::Tsv => "TSV",
+        }
+    }
+}
+
+/// Colours assigned to a speaker, as `#RRGGBB` (or `#RRGGBBAA`) hex strings.
+/// `None` leaves the format's default for that element.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SpeakerColors {
+    /// Text fill.
+    pub fill: Option<String>,
+    /// Glyph outline.
+    pub outline: Option<String>,
+    /// Background box behind the text.
+    pub border: Option<String>,
+}
+
+/// Options shared by every exporter.
+#[derive(Clone, Debug, Default)]
+pub struct ExportOptions {
+    /// Display names keyed by `Segment::speaker_id`. Speakers missing from the
+    /// map are labelled `Speaker {id}`.
+    pub speaker_names: HashMap<String, String>,
+    /// Speaker colours keyed by `Segment::speaker_id`, for formats with styling.
+    pub speaker_colors: HashMap<String, SpeakerColors>,
+    /// Output language of the transcript, for formats that declare one.
+    pub language: Option<String>,
+    /// Emit per-word timing from `Segment::words` where the format supports it.
+    pub karaoke: bool,
+    /// Video frame rate for formats with frame-based timing. `None` keeps
+    /// millisecond timing where the format allows it.
+    pub frame_rate: Option<FrameRate>,
+    /// Roll-up window depth (2–4 rows) for caption formats that support it;
+    /// `None` writes pop-on captions.
+    pub roll_up: Option<u8>,
+    /// Start timecode of the editing sequence as a frame count at
+    /// [`Self::sequence_rate`] (see [`FrameRate::parse_timecode`]), for
+    /// formats placed on an NLE timeline and the EBU STL start of programme.
+    /// Segment times are relative to the sequence start.
+    pub start_frame: u64,
+    /// Table formats (CSV/TSV) write one row per word of `Segment::words`
+    /// instead of one per cue.
+    pub word_rows: bool,
+    /// Recording the transcript belongs to, as a URL or path relative to the
+    /// exported file. Document formats (Markdown, HTML) link their timestamps
+    /// into it.
+    pub media_url: Option<String>,
+    /// Document formats stamp every paragraph with its time, not only the
+    /// first of each speaker turn.
+    pub paragraph_timecodes: bool,
+}
+
+impl ExportOptions {
+    /// Display name for a speaker id, e.g. `Speaker 1` or a user-assigned name.
+    pub fn speaker_name(&self, id: &str) -> String {
+        self.speaker_names
+            .get(id)
+            .cloned()
+            .unwrap_or_else(|| format!("Speaker {id}"))
+    }
+
+    /// Frame rate of an NLE sequence: [`Self::frame_rate`], or 25 fps when
+    /// unset. [`Self::start_frame`] counts frames at this rate.
+    pub fn sequence_rate(&self) -> FrameRate {
+        self.frame_rate.unwrap_or(FrameRate { num: 25, den: 1 })
+    }
+
+    /// `"{name}: "` prefix for a segment, or empty when it has no speaker.
+    pub(crate) fn speaker_prefix(&self, speaker_id: Option<&str>) -> String {
+        speaker_id
+            .map(|id| format!("{}: ", self.speaker_name(id)))
+            .unwrap_or_default()
+    }
+}
+
+/// Video frame rate as an exact ratio, e.g. `30000/1001` for 29.97 fps.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FrameRate {
+    pub num: u32,
+    pub den: u32,
+}
+
+impl FrameRate {
+    /// Parse `25`, `29.97` or `30000/1001`. The NTSC rates (23.976, 29.97,
+    /// 59.94) map to their exact `/1001` ratios.
+    pub fn parse(s: &str) -> Option<Self> {
+        let s = s.trim();
+        let rate = if let Some((num, den)) = s.split_once('/') {
+            Self { num: num.trim().parse().ok()?, den: den.trim().parse().ok()? }
+        } else {
+            let fps: f64 = s.parse().ok()?;
+            let nominal = fps.round();
+            if (nominal * 1000.0 / 1001.0 - fps).abs() < 0.01 && fps.fract() != 0.0 {
+                Self { num: nominal as u32 * 1000, den: 1001 }
+            } else {
+                let milli = (fps * 1000.0).round() as u64;
+                let g = gcd(milli, 1000);
+                Self { num: (milli / g) as u32, den: (1000 / g) as u32 }
+            }
+        };
+        (rate.num > 0 && rate.den > 0).then_some(rate)
+    }
+
+    /// Frames per second.
+    pub fn fps(self) -> f64 {
+        self.num as f64 / self.den as f64
+    }
+
+    /// Whole frames counted per timecode second (30 for 29.97).
+    pub fn nominal(self) -> u32 {
+        self.num.div_ceil(self.den)
+    }
+
+    /// Whether this is an NTSC `/1001` rate.
+    pub fn is_ntsc(self) -> bool {
+        self.den == 1001
+    }
+
+    /// Nearest frame number at `seconds`.
+    pub fn frames(self, seconds: f64) -> u64 {
+        (seconds.max(0.0) * self.fps()).round() as u64
+    }
+
+    /// `seconds` snapped to the nearest frame boundary.
+    pub fn snap(self, seconds: f64) -> f64 {
+        self.frames(seconds) as f64 / self.fps()
+    }
+
+    /// Whether timecode at this rate is drop-frame (29.97 and 59.94).
+    pub fn is_drop_frame(self) -> bool {
+        self.is_ntsc() && matches!(self.nominal(), 30 | 60)
+    }
+
+    /// Timecode numbers skipped at each drop-frame minute (2 at 29.97).
+    fn dropped_per_minute(self) -> u64 {
+        if self.is_drop_frame() { self.nominal() as u64 / 15 } else { 0 }
+    }
+
+    /// `HH:MM:SS:FF` timecode for a frame count, or drop-frame `HH:MM:SS;FF`
+    /// where [`Self::is_drop_frame`]. Drop-frame skips the first frame numbers
+    /// of every minute except each tenth.
+    pub fn timecode(self, frame: u64) -> String {
+        let nominal = self.nominal() as u64;
+        let drop = self.dropped_per_minute();
+        let per_min = nominal * 60 - drop;
+        let per_10_min = nominal * 600 - 9 * drop;
+        let (tens, rem) = (frame / per_10_min, frame % per_10_min);
+        let skipped = 9 * drop * tens + if rem > drop { drop * ((rem - drop) / per_min) } else { 0 };
+        let n = frame + skipped;
+        format!(
+            "{:02}:{:02}:{:02}{}{:02}",
+            n / (nominal * 3600),
+            (n / (nominal * 60)) % 60,
+            (n / nominal) % 60,
+            if drop > 0 { ';' } else { ':' },
+            n % nominal
+        )
+    }
+
+    /// Parse `HH:MM:SS:FF` (or `HH:MM:SS;FF`) timecode at this rate into a
+    /// frame count, the inverse of [`Self::timecode`].
+    pub fn parse_timecode(self, s: &str) -> Option<u64> {
+        let parts = s
+            .trim()
+            .split([':', ';', '.'])
+            .map(|p| p.parse::<u64>().ok())
+            .collect::<Option<Vec<_>>>()?;
+        let [h, m, sec, f] = parts[..] else {
+            return None;
+        };
+        let nominal = self.nominal() as u64;
+        if m >= 60 || sec >= 60 || f >= nominal {
+            return None;
+        }
+        let minutes = h * 60 + m;
+        let drop = self.dropped_per_minute();
+        if f < drop && sec == 0 && m % 10 != 0 {
+            return None;
+        }
+        Some((minutes * 60 + sec) * nominal + f - drop * (minutes - minutes / 10))
+    }
+}
+
+impl std::fmt::Display for FrameRate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.den == 1 {
+            write!(f, "{}", self.num)
+        } else {
+            write!(f, "{}/{}", self.num, self.den)
+        }
+    }
+}
+
+pub(crate) fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+/// Render `segments` in `format`. Binary formats ([`SubtitleFormat::is_binary`])
+/// are not valid UTF-8 and come back lossily converted; write those with
+/// [`render_bytes`].
+pub fn render(segments: &[Segment], format: SubtitleFormat, opts: &ExportOptions) -> String {
+    match format {
+        SubtitleFormat::Text => text::render(segments, opts),
+        SubtitleFormat::Markdown => markdown::render(segments, opts),
+        SubtitleFormat::Html => html::render(segments, opts),
+        SubtitleFormat::Srt => srt::render(segments, opts),
+        SubtitleFormat::Vtt => vtt::render(segments, opts),
+        SubtitleFormat::Ass => ass::render(segments, opts),
+        SubtitleFormat::Imsc => ttml::render(segments, ttml::Profile::Imsc1Text, opts),
+        SubtitleFormat::EbuTtD => ttml::render(segments, ttml::Profile::EbuTtD, opts),
+        SubtitleFormat::Scc => scc::render(segments, opts),
+        SubtitleFormat::Fcpxml => fcpxml::render(segments, opts),
+        SubtitleFormat::Xmeml => xmeml::render(segments, opts),
+        SubtitleFormat::Otio => otio::render(segments, opts),
+        SubtitleFormat::Lrc => lrc::render(segments, opts),
+        SubtitleFormat::Stl => String::from_utf8_lossy(&stl::render(segments, opts)).into_owned(),
+        SubtitleFormat::Csv => csv::render(segments, csv::Dialect::Csv, opts),
+        SubtitleFormat::Tsv => csv::render(segments, csv::Dialect::Tsv, opts),
+    }
+}
+
+/// Render `segments` in `format` as file contents, for text and binary
+/// formats alike.
+pub fn render_bytes(segments: &[Segment], format: SubtitleFormat, opts: &ExportOptions) -> Vec<u8> {
+    match format {
+        SubtitleFormat::Stl => stl::render(segments, opts),
+        text => render(segments, text, opts).into_bytes(),
+    }
+}
+
+/// Subtitles read back from a file by [`parse`].
+#[derive(Clone, Debug, Default)]
+pub struct ParsedSubtitles {
+    /// One segment per cue, with word timings interpolated across the cue.
+    pub segments: Vec<Segment>,
+    /// Speaker names from voice tags or styles, keyed by the `speaker_id`
+    /// assigned to them.
+    pub speaker_names: HashMap<String, String>,
+}
+
+/// Parse subtitle file contents in `format` into segments.
+pub fn parse(content: &str, format: SubtitleFormat) -> Result<ParsedSubtitles> {
+    let content = content.trim_start_matches('\u{FEFF}').replace("\r\n", "\n").replace('\r', "\n");
+    match format {
+        SubtitleFormat::Srt => srt::parse(&content),
+        SubtitleFormat::Vtt => vtt::parse(&content),
+        SubtitleFormat::Ass => ass::parse(&content),
+        SubtitleFormat::Text => bail!("plain-text transcripts have no cue timings and cannot be imported"),
+        other => bail!("importing {} files is not supported", other.name()),
+    }
+}
+
+/// Guess the format of subtitle file contents from its header.
+pub fn detect(content: &str) -> Option<SubtitleFormat> {
+    let head = content.trim_start_matches('\u{FEFF}').trim_start();
+    if head.starts_with("WEBVTT") {
+        Some(SubtitleFormat::Vtt)
+    } else if head.starts_with("[Script Info]") {
+        Some(SubtitleFormat::Ass)
+    } else if head.lines().take(3).any(|l| l.contains("-->")) {
+        Some(SubtitleFormat::Srt)
+    } else {
+        None
+    }
+}
+
+/// Accumulates parsed cues, assigning numeric speaker ids by first appearance.
+#[derive(Default)]
+pub(crate) struct CueBuilder {
+    out: ParsedSubtitles,
+}
+
+impl CueBuilder {
+    /// Add a cue. `text` keeps its line breaks; words are interpolated across
+    /// `[start, end]` since subtitle files carry no word timing.
+    pub(crate) fn push(&mut self, start: f64, end: f64, text: &str, speaker: Option<&str>) {
+        let text = text
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n");
+        if text.is_empty() {
+            return;
+        }
+        let end = end.max(start);
+        let speaker_id = speaker
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(|name| self.speaker_id(name));
+        let words = interpolate_word_timestamps(&text, start, end);
+        self.out.segments.push(Segment {
+            start,
+            end,
+            text,
+            words: (!words.is_empty()).then_some(words),
+            speaker_id,
+            stats: None,
+        });
+    }
+
+    /// Id for a speaker name. `Speaker N` keeps `N` (so AutoSubs' own exports
+    /// round-trip); other names get the lowest unused number.
+    fn speaker_id(&mut self, name: &str) -> String {
+        let names = &mut self.out.speaker_names;
+        if let Some((id, _)) = names.iter().find(|(_, n)| n.as_str() == name) {
+            return id.clone();
+        }
+        let id = name
+            .strip_prefix("Speaker ")
+            .map(str::trim)
+            .filter(|n| n.parse::<u32>().is_ok() && !names.contains_key(*n))
+            .map(str::to_string)
+            .unwrap_or_else(|| {
+                (1..)
+                    .map(|n: u32| n.to_string())
+                    .find(|n| !names.contains_key(n))
+                    .unwrap_or_default()
+            });
+        names.insert(id.clone(), name.to_string());
+        id
+    }
+
+    pub(crate) fn finish(self) -> ParsedSubtitles {
+        self.out
+    }
+}
+
+/// Parse `[[H:]MM:]SS[.,]fff` into seconds. The fraction may have any number
+/// of digits (ASS uses centiseconds, SRT/VTT milliseconds).
+pub(crate) fn parse_timestamp(s: &str) -> Option<f64> {
+    let s = s.trim();
+    let mut parts = s.rsplitn(3, ':');
+    let secs = parts.next()?;
+    let mins = parts.next().map(str::parse::<u64>).transpose().ok()?.unwrap_or(0);
+    let hours = parts.next().map(str::parse::<u64>).transpose().ok()?.unwrap_or(0);
+    let (whole, frac) = secs.split_once(['.', ',']).unwrap_or((secs, ""));
+    let whole: u64 = whole.parse().ok()?;
+    let frac = if frac.is_empty() {
+        0.0
+    } else {
+        frac.parse::<u64>().ok()? as f64 / 10f64.powi(frac.len() as i32)
+    };
+    Some((hours * 3600 + mins * 60 + whole) as f64 + frac)
+}
+
+/// Parse a `start --> end` timing line, ignoring anything after the end time
+/// (WebVTT cue settings, SRT coordinates).
+pub(crate) fn parse_timing_line(line: &str) -> Option<(f64, f64)> {
+    let (start, rest) = line.split_once("-->")?;
+    let end = rest.split_whitespace().next()?;
+    Some((parse_timestamp(start)?, parse_timestamp(end)?))
+}
+
+/// Strip HTML-style tags (`<i>`, `<c.red>`, `<00:00:01.000>`) and decode the
+/// entities SRT and WebVTT use.
+pub(crate) fn strip_tags(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut in_tag = false;
+    for c in text.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            _ if !in_tag => out.push(c),
+            _ => {}
+        }
+    }
+    out.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&nbsp;", " ")
+        .replace("&lrm;", "\u{200E}")
+        .replace("&rlm;", "\u{200F}")
+        .replace("&amp;", "&")
+}
+
+/// Split off the `Speaker N: ` prefix AutoSubs writes into SRT cues (and
+/// wrote into VTT cues before voice spans).
+/// Arbitrary `Name: ` prefixes are left alone since they are
+/// indistinguishable from ordinary text.
+pub(crate) fn split_speaker_prefix(text: &str) -> (Option<&str>, &str) {
+    text.split_once(": ")
+        .filter(|(name, _)| {
+            name.strip_prefix("Speaker ")
+                .is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()))
+        })
+        .map_or((None, text), |(name, rest)| (Some(name), rest))
+}
+
+/// Writing-system profile of the output: from the declared language when
+/// there is one, otherwise inferred from the text.
+pub(crate) fn script_profile(segments: &[Segment], opts: &ExportOptions) -> ScriptProfile {
+    match opts.language.as_deref().filter(|l| !l.is_empty() && *l != "auto") {
+        Some(lang) => profile_for_lang(lang),
+        None => {
+            let text: String = segments.iter().map(|s| s.text.as_str()).collect();
+            profile_for_text(&text)
+        }
+    }
+}
+
+/// Escape text for XML content and double-quoted attribute values.
+pub(crate) fn xml_escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            // Control characters other than tab/newline are not allowed in XML 1.0.
+            c if c.is_control() && c != '\t' && c != '\n' => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Distinct speaker ids in order of first appearance.
+pub(crate) fn speakers_in_order(segments: &[Segment]) -> Vec<&str> {
+    let mut ids: Vec<&str> = Vec::new();
+    for id in segments.iter().filter_map(|s| s.speaker_id.as_deref()) {
+        if !ids.contains(&id) {
+            ids.push(id);
+        }
+    }
+    ids
+}
+
+/// A cue's words grouped by the rendered line they appear on.
+///
+/// `process_segments` joins a cue's lines with `\n`, so each word is located in
+/// the text in order and a newline between two words starts a new line. Returns
+/// `None` when the cue has no words or the text no longer matches them (e.g.
+/// it was edited by hand), in which case callers fall back to plain text.
+pub(crate) fn words_by_line(seg: &Segment) -> Option<Vec<Vec<&WordTimestamp>>> {
+    let words = seg.words.as_ref().filter(|w| !w.is_empty())?;
+    let mut lines: Vec<Vec<&WordTimestamp>> = vec![Vec::new()];
+    let mut rest = seg.text.as_str();
+    for word in words {
+        let needle = word.text.trim();
+        if needle.is_empty() {
+            continue;
+        }
+        let pos = rest.find(needle)?;
+        if rest[..pos].contains('\n') && !lines.last().is_some_and(Vec::is_empty) {
+            lines.push(Vec::new());
+        }
+        lines.last_mut()?.push(word);
+        rest = &rest[pos + needle.len()..];
+    }
+    if !rest.trim().is_empty() {
+        return None;
+    }
+    Some(lines)
+}
+
+/// Parse `#RGB`, `#RRGGBB` or `#RRGGBBAA` into `(r, g, b, a)`.
+pub(crate) fn parse_hex_color(hex: &str) -> Option<(u8, u8, u8, u8)> {
+    let hex = hex.trim().trim_start_matches('#');
+    let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    match hex.len() {
+        3 => {
+            let mut rgb = hex.chars().map(|c| c.to_digit(16).map(|d| (d * 17) as u8));
+            Some((rgb.next()??, rgb.next()??, rgb.next()??, 255))
+        }
+        6 => Some((channel(0)?, channel(2)?, channel(4)?, 255)),
+        8 => Some((channel(0)?, channel(2)?, channel(4)?, channel(6)?)),
+        _ => None,
+    }
+}
+
+/// Split seconds into `(hours, minutes, seconds, milliseconds)`, rounding to the
+/// nearest millisecond and clamping negative times to zero.
+pub(crate) fn hms(seconds: f64) -> (u64, u64, u64, u64) {
+    let total_ms = (seconds.max(0.0) * 1000.0).round() as u64;
+    (
+        total_ms / 3_600_000,
+        (total_ms / 60_000) % 60,
+        (total_ms / 1000) % 60,
+        total_ms % 1000,
+    )
+}
+
+/// `HH:MM:SS` wall-clock timestamp.
+pub(crate) fn ts_clock(seconds: f64) -> String {
+    let (h, m, s, _) = hms(seconds);
+    format!("{h:02}:{m:02}:{s:02}")
+}
+
+/// Word-wrap `text` to rows of at most `columns` characters, keeping the
+/// cue's own line breaks. Words longer than a row are hard-split.
+pub(crate) fn wrap_rows(text: &str, columns: usize) -> Vec<String> {
+    let mut rows = Vec::new();
+    for line in text.lines() {
+        let mut row = String::new();
+        for word in line.split_whitespace() {
+            let mut word: Vec<char> = word.chars().collect();
+            // Hard-split words that cannot fit on any row.
+            while word.len() > columns {
+                if !row.is_empty() {
+                    rows.push(std::mem::take(&mut row));
+                }
+                rows.push(word.drain(..columns).collect());
+            }
+            let word: String = word.into_iter().collect();
+            let needed = if row.is_empty() { 0 } else { row.chars().count() + 1 } + word.chars().count();
+            if needed > columns {
+                rows.push(std::mem::take(&mut row));
+            }
+            if !row.is_empty() {
+                row.push(' ');
+            }
+            row.push_str(&word);
+        }
+        if !row.is_empty() {
+            rows.push(row);
+        }
+    }
+    rows
+}
+
+/// Non-empty cues as `(start, end)` frame ranges at `rate`, in start order,
+/// with each cue trimmed to end where the next begins. NLE caption tracks
+/// cannot hold overlapping clips.
+pub(crate) fn track_cues(segments: &[Segment], rate: FrameRate) -> Vec<(u64, u64, &Segment)> {
+    let mut cues: Vec<(u64, u64, &Segment)> = segments
+        .iter()
+        .filter(|seg| !seg.text.trim().is_empty())
+        .map(|seg| (rate.frames(seg.start), rate.frames(seg.end.max(seg.start)), seg))
+        .collect();
+    cues.sort_by_key(|&(start, _, _)| start);
+    for i in 1..cues.len() {
+        let next = cues[i].0;
+        cues[i - 1].1 = cues[i - 1].1.min(next);
+    }
+    cues.retain(|&(start, end, _)| end > start);
+    cues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    pub(crate) fn seg(start: f64, end: f64, text: &str, speaker: Option<&str>) -> Segment {
+        Segment {
+            start,
+            end,
+            text: text.to_string(),
+            words: None,
+            speaker_id: speaker.map(str::to_string),
+            stats: None,
+        }
+    }
+
+    pub(crate) fn word(text: &str, start: f64, end: f64) -> WordTimestamp {
+        WordTimestamp { text: text.to_string(), start, end, probability: None }
+    }
+
+    /// One element of a parsed document: name, attributes and children.
+    pub(crate) struct Element {
+        pub(crate) name: String,
+        pub(crate) attrs: Vec<(String, String)>,
+        pub(crate) children: Vec<Element>,
+    }
+
+    impl Element {
+        pub(crate) fn attr(&self, name: &str) -> Option<&str> {
+            self.attrs.iter().find(|(k, _)| k == name).map(|(_, v)| v.as_str())
+        }
+
+        pub(crate) fn walk<'a>(&'a self, out: &mut Vec<&'a Element>) {
+            out.push(self);
+            for child in &self.children {
+                child.walk(out);
+            }
+        }
+    }
+
+    /// Minimal XML parser: enough to check well-formedness (matched tags,
+    /// quoted attributes, valid entities) of the XML documents we write. An
+    /// optional `<!DOCTYPE>` line after the declaration is skipped.
+    pub(crate) fn parse_xml(doc: &str) -> Element {
+        let body = doc.strip_prefix("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n").expect("XML declaration");
+        let body = match body.strip_prefix("<!DOCTYPE ") {
+            Some(rest) => &rest[rest.find(">\n").expect("unterminated DOCTYPE") + 2..],
+            None => body,
+        };
+        let mut stack = vec![Element { name: String::new(), attrs: Vec::new(), children: Vec::new() }];
+        let mut rest = body;
+        while let Some(open) = rest.find('<') {
+            check_text(&rest[..open]);
+            let close = rest[open..].find('>').expect("unterminated tag") + open;
+            let tag = &rest[open + 1..close];
+            rest = &rest[close + 1..];
+            if let Some(name) = tag.strip_prefix('/') {
+                let el = stack.pop().expect("unbalanced close tag");
+                assert_eq!(el.name, name, "mismatched close tag");
+                stack.last_mut().expect("close tag at root").children.push(el);
+                continue;
+            }
+            let self_closing = tag.ends_with('/');
+            let tag = tag.trim_end_matches('/');
+            let (name, mut attrs_src) = tag.split_once(' ').unwrap_or((tag, ""));
+            let mut attrs = Vec::new();
+            while let Some(eq) = attrs_src.find("=\"") {
+                let key = attrs_src[..eq].trim().to_string();
+                let value_end = attrs_src[eq + 2..].find('"').expect("unterminated attribute") + eq + 2;
+                let value = &attrs_src[eq + 2..value_end];
+                check_text(value);
+                assert!(!attrs.iter().any(|(k, _): &(String, String)| *k == key), "duplicate attribute {key}");
+                attrs.push((key, value.to_string()));
+                attrs_src = &attrs_src[value_end + 1..];
+            }
+            assert!(attrs_src.trim().is_empty(), "stray attribute text: {attrs_src}");
+            let el = Element { name: name.to_string(), attrs, children: Vec::new() };
+            if self_closing {
+                stack.last_mut().unwrap().children.push(el);
+            } else {
+                stack.push(el);
+            }
+        }
+        assert!(rest.trim().is_empty());
+        assert_eq!(stack.len(), 1, "unclosed elements");
+        let mut root = stack.pop().unwrap();
+        assert_eq!(root.children.len(), 1, "exactly one root element");
+        root.children.pop().unwrap()
+    }
+
+    fn check_text(text: &str) {
+        let mut rest = text;
+        while let Some(amp) = rest.find('&') {
+            let semi = rest[amp..].find(';').expect("unterminated entity") + amp;
+            assert!(
+                ["amp", "lt", "gt", "quot", "apos"].contains(&&rest[amp + 1..semi]),
+                "unknown entity"
+            );
+            rest = &rest[semi + 1..];
+        }
+    }
+
+    #[test]
+    fn parse_accepts_names_and_extensions() {
+        assert_eq!(SubtitleFormat::parse("TXT"), Some(SubtitleFormat::Text));
+        assert_eq!(SubtitleFormat::parse(".srt"), Some(SubtitleFormat::Srt));
+        assert_eq!(SubtitleFormat::parse("webvtt"), Some(SubtitleFormat::Vtt));
+        assert_eq!(SubtitleFormat::parse("docx"), None);
+        assert_eq!(SubtitleFormat::parse("xml"), Some(SubtitleFormat::Xmeml));
+        assert_eq!(SubtitleFormat::parse("ebu-tt-d"), Some(SubtitleFormat::EbuTtD));
+        for format in SubtitleFormat::ALL {
+            assert_eq!(SubtitleFormat::parse(format.extension()), Some(*format));
+        }
+    }
+
+    #[test]
+    fn from_path_prefers_compound_extensions() {
+        use std::path::Path;
+        let from_path = |p: &str| SubtitleFormat::from_path(Path::new(p));
+        assert_eq!(from_path("cut.xml"), Some(SubtitleFormat::Xmeml));
+        assert_eq!(from_path("news.v2.ebuttd.xml"), Some(SubtitleFormat::EbuTtD));
+        assert_eq!(from_path("dir.d/Talk.SRT"), Some(SubtitleFormat::Srt));
+        assert_eq!(from_path("talk.final.srt"), Some(SubtitleFormat::Srt));
+        assert_eq!(from_path("README"), None);
+        for format in SubtitleFormat::ALL {
+            assert_eq!(from_path(&format!("out.{}", format.extension())), Some(*format));
+        }
+    }
+
+    #[test]
+    fn speaker_names_fall_back_to_numbered_labels() {
+        let mut opts = ExportOptions::default();
+        opts.speaker_names.insert("1".into(), "Alice".into());
+        assert_eq!(opts.speaker_prefix(Some("1")), "Alice: ");
+        assert_eq!(opts.speaker_prefix(Some("2")), "Speaker 2: ");
+        assert_eq!(opts.speaker_prefix(None), "");
+    }
+
+    #[test]
+    fn words_are_grouped_by_rendered_line() {
+        let mut cue = seg(0.0, 2.0, "Hello there,\ngeneral Kenobi.", None);
+        cue.words = Some(vec![
+            word("Hello", 0.0, 0.4),
+            word(" there,", 0.4, 0.8),
+            word(" general", 1.0, 1.4),
+            word(" Kenobi.", 1.4, 2.0),
+        ]);
+        let lines = words_by_line(&cue).unwrap();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1][0].text, " general");
+
+        cue.text = "Something else entirely".into();
+        assert!(words_by_line(&cue).is_none());
+    }
+
+    #[test]
+    fn hex_colors_parse_with_optional_alpha() {
+        assert_eq!(parse_hex_color("#ff8000"), Some((255, 128, 0, 255)));
+        assert_eq!(parse_hex_color("#fff"), Some((255, 255, 255, 255)));
+        assert_eq!(parse_hex_color("00000080"), Some((0, 0, 0, 128)));
+        assert_eq!(parse_hex_color("red"), None);
+    }
+
+    #[test]
+    fn frame_rates_parse_to_exact_ratios() {
+        assert_eq!(FrameRate::parse("29.97"), Some(FrameRate { num: 30000, den: 1001 }));
+        assert_eq!(FrameRate::parse("23.976"), Some(FrameRate { num: 24000, den: 1001 }));
+        assert_eq!(FrameRate::parse("25"), Some(FrameRate { num: 25, den: 1 }));
+        assert_eq!(FrameRate::parse("12.5"), Some(FrameRate { num: 25, den: 2 }));
+        assert_eq!(FrameRate::parse("60000/1001").map(|r| r.nominal()), Some(60));
+        assert_eq!(FrameRate::parse("0"), None);
+        let ntsc = FrameRate { num: 30000, den: 1001 };
+        assert_eq!(ntsc.frames(1.0), 30);
+        assert_eq!(ntsc.to_string(), "30000/1001");
+    }
+
+    #[test]
+    fn timecodes_count_drop_frames() {
+        let df = FrameRate { num: 30000, den: 1001 };
+        assert_eq!(df.parse_timecode("01:00:00;00"), Some(107_892));
+        assert_eq!(df.parse_timecode("00:01:00;00"), None);
+        let df60 = FrameRate { num: 60000, den: 1001 };
+        assert_eq!(df60.parse_timecode("00:10:00:00"), Some(35_964));
+        let film = FrameRate { num: 24000, den: 1001 };
+        assert_eq!(film.timecode(86_400), "01:00:00:00");
+        assert_eq!(film.parse_timecode("01:00:00:00"), Some(86_400));
+        for frame in [0, 1, 1799, 1800, 17_982, 123_456] {
+            assert_eq!(df.parse_timecode(&df.timecode(frame)), Some(frame));
+            assert_eq!(df60.parse_timecode(&df60.timecode(frame)), Some(frame));
+        }
+        assert_eq!(FrameRate { num: 25, den: 1 }.parse_timecode("00:00:01:25"), None);
+        assert_eq!(FrameRate { num: 25, den: 1 }.parse_timecode("10:00:00"), None);
+    }
+
+    #[test]
+    fn timestamps_parse_in_every_notation() {
+        assert_eq!(parse_timestamp("01:02:03,456"), Some(3723.456));
+        assert_eq!(parse_timestamp("1:02:03.45"), Some(3723.45));
+        assert_eq!(parse_timestamp("02:03.5"), Some(123.5));
+        assert_eq!(parse_timestamp("abc"), None);
+        assert_eq!(
+            parse_timing_line("00:00:01.000 --> 00:00:02.500 align:start line:90%"),
+            Some((1.0, 2.5))
+        );
+    }
+
+    #[test]
+    fn speaker_ids_are_assigned_by_first_appearance() {
+        let mut cues = CueBuilder::default();
+        cues.push(0.0, 1.0, "a", Some("Alice"));
+        cues.push(1.0, 2.0, "b", Some("Speaker 1"));
+        cues.push(2.0, 3.0, "c", Some("Alice"));
+        cues.push(3.0, 4.0, "d", Some("Speaker 3"));
+        let parsed = cues.finish();
+        let ids: Vec<_> = parsed.segments.iter().map(|s| s.speaker_id.clone().unwrap()).collect();
+        assert_eq!(ids, ["1", "2", "1", "3"]);
+        assert_eq!(parsed.speaker_names["2"], "Speaker 1");
+        assert!(parsed.segments[0].words.is_some());
+    }
+
+    #[test]
+    fn detect_recognises_headers() {
+        assert_eq!(detect("\u{FEFF}WEBVTT\n\n"), Some(SubtitleFormat::Vtt));
+        assert_eq!(detect("[Script Info]\n"), Some(SubtitleFormat::Ass));
+        assert_eq!(detect("1\n00:00:00,000 --> 00:00:01,000\nHi\n"), Some(SubtitleFormat::Srt));
+        assert_eq!(detect("hello"), None);
+    }
+
+    #[test]
+    fn exports_round_trip_through_the_parsers() {
+        let segments = vec![
+            seg(0.5, 1.75, "Hello & {welcome},\nfriends.", Some("1")),
+            seg(2.0, 3.0, "Bye now.", Some("2")),
+            seg(3.0, 4.0, "Narration.", None),
+        ];
+        for format in [SubtitleFormat::Srt, SubtitleFormat::Vtt, SubtitleFormat::Ass] {
+            let opts = ExportOptions::default();
+            let parsed = parse(&render(&segments, format, &opts), format).unwrap();
+            assert_eq!(parsed.segments.len(), 3, "{format:?}");
+            for (a, b) in parsed.segments.iter().zip(&segments) {
+                assert_eq!(a.text, b.text, "{format:?}");
+                assert_eq!(a.speaker_id, b.speaker_id, "{format:?}");
+                assert!((a.start - b.start).abs() < 0.01 && (a.end - b.end).abs() < 0.01);
+            }
+        }
+
+        let mut opts = ExportOptions::default();
+        opts.speaker_names.insert("1".into(), "Alice".into());
+        let parsed = parse(&render(&segments, SubtitleFormat::Ass, &opts), SubtitleFormat::Ass).unwrap();
+        let first = parsed.segments[0].speaker_id.as_deref().unwrap();
+        assert_eq!(parsed.speaker_names[first], "Alice");
+    }
+
+    #[test]
+    fn timestamps_round_to_milliseconds_and_clamp_negative() {
+        assert_eq!(hms(3723.4567), (1, 2, 3, 457));
+        assert_eq!(hms(-5.0), (0, 0, 0, 0));
+        assert_eq!(ts_clock(59.9996), "00:01:00");
+    }
+}
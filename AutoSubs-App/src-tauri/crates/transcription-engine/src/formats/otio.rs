@@ -0,0 +1,225 @@
+//! OpenTimelineIO (`.otio`) writer.
+//!
+//! The transcript becomes a timeline with two tracks:
+//!
+//! - **Subtitles**: one marker per cue over a gap spanning the transcript,
+//!   named after the cue text, with the text and speaker in its metadata.
+//! - **Speakers**: one clip per speaker turn (a run of consecutive cues with
+//!   the same `speaker_id`), separated by gaps where nobody is labelled.
+//!
+//! Times are whole frames at [`ExportOptions::sequence_rate`], relative to the
+//! timeline's `global_start_time` ([`ExportOptions::start_frame`]). Cues and
+//! turns carry their AutoSubs data under the `autosubs` metadata key.
+
+use super::{ExportOptions, FrameRate, speakers_in_order};
+use crate::types::Segment;
+use serde_json::{Value, json};
+
+/// Marker colours cycled through per speaker (OTIO's `MarkerColor` names).
+const MARKER_COLORS: &[&str] = &["RED", "BLUE", "GREEN", "ORANGE", "PURPLE", "CYAN", "YELLOW", "MAGENTA", "PINK"];
+
+fn rational_time(frames: u64, rate: FrameRate) -> Value {
+    json!({ "OTIO_SCHEMA": "RationalTime.1", "rate": rate.fps(), "value": frames as f64 })
+}
+
+fn time_range(start: u64, duration: u64, rate: FrameRate) -> Value {
+    json!({
+        "OTIO_SCHEMA": "TimeRange.1",
+        "start_time": rational_time(start, rate),
+        "duration": rational_time(duration, rate),
+    })
+}
+
+fn gap(duration: u64, rate: FrameRate) -> Value {
+    json!({
+        "OTIO_SCHEMA": "Gap.1",
+        "name": "",
+        "source_range": time_range(0, duration, rate),
+        "effects": [],
+        "markers": [],
+        "enabled": true,
+        "metadata": {},
+    })
+}
+
+fn track(name: &str, kind: &str, children: Vec<Value>, markers: Vec<Value>) -> Value {
+    json!({
+        "OTIO_SCHEMA": "Track.1",
+        "name": name,
+        "kind": kind,
+        "children": children,
+        "markers": markers,
+        "effects": [],
+        "source_range": null,
+        "enabled": true,
+        "metadata": {},
+    })
+}
+
+/// Speaker turns as `(start, end, speaker_id)` frame ranges: runs of
+/// consecutive labelled cues, each ending where the next turn starts.
+fn speaker_turns(segments: &[Segment], rate: FrameRate) -> Vec<(u64, u64, &str)> {
+    let mut turns: Vec<(u64, u64, &str)> = Vec::new();
+    let mut last: Option<&str> = None;
+    for seg in segments.iter().filter(|s| !s.text.trim().is_empty()) {
+        let (start, end) = (rate.frames(seg.start), rate.frames(seg.end.max(seg.start)));
+        match (seg.speaker_id.as_deref(), turns.last_mut()) {
+            (Some(id), Some(turn)) if last == Some(id) => turn.1 = turn.1.max(end),
+            (Some(id), _) => turns.push((start, end, id)),
+            (None, _) => {}
+        }
+        last = seg.speaker_id.as_deref();
+    }
+    for i in 1..turns.len() {
+        let next = turns[i].0;
+        turns[i - 1].1 = turns[i - 1].1.min(next);
+    }
+    turns.retain(|&(start, end, _)| end > start);
+    turns
+}
+
+/// Render an OTIO timeline with cue markers and speaker-turn clips.
+pub fn render(segments: &[Segment], opts: &ExportOptions) -> String {
+    let rate = opts.sequence_rate();
+    let speakers = speakers_in_order(segments);
+    let cues: Vec<&Segment> = segments.iter().filter(|s| !s.text.trim().is_empty()).collect();
+    let duration = cues.iter().map(|s| rate.frames(s.end.max(s.start))).max().unwrap_or(0);
+
+    let markers: Vec<Value> = cues
+        .iter()
+        .map(|seg| {
+            let text = seg.text.lines().map(str::trim).filter(|l| !l.is_empty()).collect::<Vec<_>>();
+            let (start, end) = (rate.frames(seg.start), rate.frames(seg.end.max(seg.start)));
+            let speaker = seg.speaker_id.as_deref();
+            let color = speaker
+                .and_then(|id| speakers.iter().position(|s| *s == id))
+                .map_or("WHITE", |i| MARKER_COLORS[i % MARKER_COLORS.len()]);
+            json!({
+                "OTIO_SCHEMA": "Marker.2",
+                "name": text.join(" "),
+                "comment": text.join("\n"),
+                "color": color,
+                "marked_range": time_range(start, end - start, rate),
+                "metadata": {
+                    "autosubs": {
+                        "text": text.join("\n"),
+                        "speaker_id": speaker,
+                        "speaker": speaker.map(|id| opts.speaker_name(id)),
+                    }
+                },
+            })
+        })
+        .collect();
+    let subtitle_children = if duration > 0 { vec![gap(duration, rate)] } else { Vec::new() };
+
+    let mut turn_children = Vec::new();
+    let mut cursor = 0;
+    for (start, end, id) in speaker_turns(segments, rate) {
+        if start > cursor {
+            turn_children.push(gap(start - cursor, rate));
+        }
+        let name = opts.speaker_name(id);
+        turn_children.push(json!({
+            "OTIO_SCHEMA": "Clip.2",
+            "name": name,
+            "source_range": time_range(start, end - start, rate),
+            "media_references": {
+                "DEFAULT_MEDIA": {
+                    "OTIO_SCHEMA": "MissingReference.1",
+                    "name": "",
+                    "available_range": null,
+                    "available_image_bounds": null,
+                    "metadata": {},
+                }
+            },
+            "active_media_reference_key": "DEFAULT_MEDIA",
+            "effects": [],
+            "markers": [],
+            "enabled": true,
+            "metadata": { "autosubs": { "speaker_id": id, "speaker": name } },
+        }));
+        cursor = end;
+    }
+
+    let lang = opts.language.as_deref().filter(|l| !l.is_empty() && *l != "auto");
+    let timeline = json!({
+        "OTIO_SCHEMA": "Timeline.1",
+        "name": "AutoSubs Transcript",
+        "global_start_time": rational_time(opts.start_frame, rate),
+        "metadata": { "autosubs": { "language": lang } },
+        "tracks": {
+            "OTIO_SCHEMA": "Stack.1",
+            "name": "tracks",
+            "children": [
+                track("Subtitles", "Video", subtitle_children, markers),
+                track("Speakers", "Audio", turn_children, Vec::new()),
+            ],
+            "markers": [],
+            "effects": [],
+            "source_range": null,
+            "enabled": true,
+            "metadata": {},
+        },
+    });
+    let mut out = serde_json::to_string_pretty(&timeline).unwrap_or_default();
+    out.push('\n');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::tests::seg;
+
+    fn frames(v: &Value) -> u64 {
+        v["value"].as_f64().unwrap() as u64
+    }
+
+    #[test]
+    fn cues_become_markers_and_turns_become_clips() {
+        let segments = vec![
+            seg(1.0, 2.0, "Hello\nthere.", Some("1")),
+            seg(2.0, 3.0, "Still me.", Some("1")),
+            seg(3.5, 4.0, "Music.", None),
+            seg(4.0, 5.0, "Hi!", Some("2")),
+            seg(5.0, 6.0, " ", Some("1")),
+        ];
+        let mut opts = ExportOptions { start_frame: 90_000, ..Default::default() };
+        opts.speaker_names.insert("2".into(), "Bob".into());
+        let doc: Value = serde_json::from_str(&render(&segments, &opts)).unwrap();
+
+        assert_eq!(doc["OTIO_SCHEMA"], "Timeline.1");
+        assert_eq!(frames(&doc["global_start_time"]), 90_000);
+        assert_eq!(doc["global_start_time"]["rate"], 25.0);
+        let tracks = doc["tracks"]["children"].as_array().unwrap();
+
+        let subs = &tracks[0];
+        assert_eq!(subs["children"][0]["OTIO_SCHEMA"], "Gap.1");
+        assert_eq!(frames(&subs["children"][0]["source_range"]["duration"]), 125);
+        let markers = subs["markers"].as_array().unwrap();
+        assert_eq!(markers.len(), 4, "blank cues are skipped");
+        assert_eq!(markers[0]["name"], "Hello there.");
+        assert_eq!(markers[0]["metadata"]["autosubs"]["text"], "Hello\nthere.");
+        assert_eq!(markers[0]["metadata"]["autosubs"]["speaker"], "Speaker 1");
+        assert_eq!(frames(&markers[0]["marked_range"]["start_time"]), 25);
+        assert_eq!(markers[2]["color"], "WHITE");
+        assert_eq!(markers[2]["metadata"]["autosubs"]["speaker_id"], Value::Null);
+        assert_eq!(markers[3]["metadata"]["autosubs"]["speaker"], "Bob");
+
+        // Gap to 1s, Speaker 1 for 1–3s, gap over the unlabelled cue, Bob 4–5s.
+        let turns = tracks[1]["children"].as_array().unwrap();
+        let kinds: Vec<&str> = turns.iter().map(|c| c["OTIO_SCHEMA"].as_str().unwrap()).collect();
+        assert_eq!(kinds, ["Gap.1", "Clip.2", "Gap.1", "Clip.2"]);
+        assert_eq!(turns[1]["name"], "Speaker 1");
+        assert_eq!(frames(&turns[1]["source_range"]["duration"]), 50);
+        assert_eq!(frames(&turns[2]["source_range"]["duration"]), 25);
+        assert_eq!(turns[3]["name"], "Bob");
+    }
+
+    #[test]
+    fn empty_transcripts_have_empty_tracks() {
+        let doc: Value = serde_json::from_str(&render(&[], &ExportOptions::default())).unwrap();
+        let tracks = doc["tracks"]["children"].as_array().unwrap();
+        assert!(tracks.iter().all(|t| t["children"].as_array().unwrap().is_empty()));
+    }
+}
@@ -0,0 +1,558 @@
+//! Scenarist (`.scc`) writer for CEA-608 line-21 captions.
+//!
+//! Each cue becomes a stream of 608 byte pairs on caption channel 1 (CC1),
+//! sent one pair per frame at 29.97 fps and stamped with drop-frame timecode.
+//!
+//! - **Pop-on** (default): the caption is loaded into non-displayed memory
+//!   ahead of time and swapped on screen with `EOC` exactly at the cue start.
+//! - **Roll-up** ([`ExportOptions::roll_up`] = 2–4 rows): each line is sent as
+//!   the cue plays and scrolls the window up, with `>>` marking speaker changes.
+//!
+//! Lines wider than 32 columns are re-wrapped and cues taller than the caption
+//! window are split, so the output is always within the 608 limits. Use
+//! [`PostProcessConfig::cea608`](crate::PostProcessConfig::cea608) when running
+//! `process_segments` so that rarely has to happen.
+
+use super::{ExportOptions, FrameRate, wrap_rows};
+use crate::types::Segment;
+use std::collections::BTreeMap;
+use unicode_normalization::UnicodeNormalization;
+
+/// Characters per caption row.
+pub const COLUMNS: usize = 32;
+/// Maximum rows in a pop-on caption or roll-up window.
+pub const ROWS: usize = 4;
+
+const FRAME_RATE: FrameRate = FrameRate { num: 30000, den: 1001 };
+
+// Miscellaneous control codes for CC1, before parity.
+const RCL: [u8; 2] = [0x14, 0x20];
+const EDM: [u8; 2] = [0x14, 0x2C];
+const CR: [u8; 2] = [0x14, 0x2D];
+const ENM: [u8; 2] = [0x14, 0x2E];
+const EOC: [u8; 2] = [0x14, 0x2F];
+const RU2: u8 = 0x25;
+
+/// First PAC byte and base second byte for rows 1–15 (CC1).
+const PAC_ROWS: [(u8, u8); 15] = [
+    (0x11, 0x40),
+    (0x11, 0x60),
+    (0x12, 0x40),
+    (0x12, 0x60),
+    (0x15, 0x40),
+    (0x15, 0x60),
+    (0x16, 0x40),
+    (0x16, 0x60),
+    (0x17, 0x40),
+    (0x17, 0x60),
+    (0x10, 0x40),
+    (0x13, 0x40),
+    (0x13, 0x60),
+    (0x14, 0x40),
+    (0x14, 0x60),
+];
+
+/// Set the odd-parity bit, as every byte on line 21 must carry.
+fn parity(byte: u8) -> u8 {
+    let b = byte & 0x7F;
+    if b.count_ones() & 1 == 1 { b } else { b | 0x80 }
+}
+
+/// How a character is sent.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Code {
+    /// One byte of the basic character set.
+    Basic(u8),
+    /// A two-byte special character.
+    Special(u8),
+    /// A two-byte extended character, preceded by a basic fallback that
+    /// decoders without the extended set display (others backspace over it).
+    Extended(u8, u8, u8),
+}
+
+/// 608 encoding for a character, or `None` when it has none.
+fn encode_char(c: char) -> Option<Code> {
+    use Code::*;
+    Some(match c {
+        // ASCII positions the basic set reassigns.
+        '*' => Extended(b'.', 0x12, 0x28),
+        '\\' => Extended(b'/', 0x13, 0x2B),
+        '^' => Extended(b'\'', 0x13, 0x2C),
+        '_' => Extended(b'-', 0x13, 0x2D),
+        '`' => Extended(b'\'', 0x12, 0x26),
+        '{' => Extended(b'(', 0x13, 0x29),
+        '|' => Extended(b'!', 0x13, 0x2E),
+        '}' => Extended(b')', 0x13, 0x2A),
+        '~' => Extended(b'-', 0x13, 0x2F),
+        ' '..='~' => Basic(c as u8),
+        'á' => Basic(0x2A),
+        'é' => Basic(0x5C),
+        'í' => Basic(0x5E),
+        'ó' => Basic(0x5F),
+        'ú' => Basic(0x60),
+        'ç' => Basic(0x7B),
+        '÷' => Basic(0x7C),
+        'Ñ' => Basic(0x7D),
+        'ñ' => Basic(0x7E),
+        '█' => Basic(0x7F),
+        '®' => Special(0x30),
+        '°' => Special(0x31),
+        '½' => Special(0x32),
+        '¿' => Special(0x33),
+        '™' => Special(0x34),
+        '¢' => Special(0x35),
+        '£' => Special(0x36),
+        '♪' => Special(0x37),
+        'à' => Special(0x38),
+        'è' => Special(0x3A),
+        'â' => Special(0x3B),
+        'ê' => Special(0x3C),
+        'î' => Special(0x3D),
+        'ô' => Special(0x3E),
+        'û' => Special(0x3F),
+        'Á' => Extended(b'A', 0x12, 0x20),
+        'É' => Extended(b'E', 0x12, 0x21),
+        'Ó' => Extended(b'O', 0x12, 0x22),
+        'Ú' => Extended(b'U', 0x12, 0x23),
+        'Ü' => Extended(b'U', 0x12, 0x24),
+        'ü' => Extended(b'u', 0x12, 0x25),
+        '‘' => Extended(b'\'', 0x12, 0x26),
+        '¡' => Extended(b'!', 0x12, 0x27),
+        '’' => Extended(b'\'', 0x12, 0x29),
+        '—' => Extended(b'-', 0x12, 0x2A),
+        '©' => Extended(b'c', 0x12, 0x2B),
+        '℠' => Extended(b's', 0x12, 0x2C),
+        '•' => Extended(b'.', 0x12, 0x2D),
+        '“' => Extended(b'"', 0x12, 0x2E),
+        '”' => Extended(b'"', 0x12, 0x2F),
+        'À' => Extended(b'A', 0x12, 0x30),
+        'Â' => Extended(b'A', 0x12, 0x31),
+        'Ç' => Extended(b'C', 0x12, 0x32),
+        'È' => Extended(b'E', 0x12, 0x33),
+        'Ê' => Extended(b'E', 0x12, 0x34),
+        'Ë' => Extended(b'E', 0x12, 0x35),
+        'ë' => Extended(b'e', 0x12, 0x36),
+        'Î' => Extended(b'I', 0x12, 0x37),
+        'Ï' => Extended(b'I', 0x12, 0x38),
+        'ï' => Extended(b'i', 0x12, 0x39),
+        'Ô' => Extended(b'O', 0x12, 0x3A),
+        'Ù' => Extended(b'U', 0x12, 0x3B),
+        'ù' => Extended(b'u', 0x12, 0x3C),
+        'Û' => Extended(b'U', 0x12, 0x3D),
+        '«' => Extended(b'"', 0x12, 0x3E),
+        '»' => Extended(b'"', 0x12, 0x3F),
+        'Ã' => Extended(b'A', 0x13, 0x20),
+        'ã' => Extended(b'a', 0x13, 0x21),
+        'Í' => Extended(b'I', 0x13, 0x22),
+        'Ì' => Extended(b'I', 0x13, 0x23),
+        'ì' => Extended(b'i', 0x13, 0x24),
+        'Ò' => Extended(b'O', 0x13, 0x25),
+        'ò' => Extended(b'o', 0x13, 0x26),
+        'Õ' => Extended(b'O', 0x13, 0x27),
+        'õ' => Extended(b'o', 0x13, 0x28),
+        'Ä' => Extended(b'A', 0x13, 0x30),
+        'ä' => Extended(b'a', 0x13, 0x31),
+        'Ö' => Extended(b'O', 0x13, 0x32),
+        'ö' => Extended(b'o', 0x13, 0x33),
+        'ß' => Extended(b's', 0x13, 0x34),
+        '¥' => Extended(b'Y', 0x13, 0x35),
+        '¤' => Extended(b'C', 0x13, 0x36),
+        '¦' => Extended(b'!', 0x13, 0x37),
+        'Å' => Extended(b'A', 0x13, 0x38),
+        'å' => Extended(b'a', 0x13, 0x39),
+        'Ø' => Extended(b'O', 0x13, 0x3A),
+        'ø' => Extended(b'o', 0x13, 0x3B),
+        '┌' => Extended(b'+', 0x13, 0x3C),
+        '┐' => Extended(b'+', 0x13, 0x3D),
+        '└' => Extended(b'+', 0x13, 0x3E),
+        '┘' => Extended(b'+', 0x13, 0x3F),
+        _ => return None,
+    })
+}
+
+/// Reduce text to characters 608 can show: typographic punctuation is
+/// replaced, other accented letters lose their accents, and anything left
+/// without an encoding is dropped. Line breaks are kept.
+fn to_608_text(text: &str) -> String {
+    text.chars()
+        .flat_map(|c| {
+            let replaced = match c {
+                '–' | '‐' | '‑' => Some('-'),
+                '…' => return "...".chars().collect::<Vec<_>>(),
+                '\u{00A0}' | '\t' => Some(' '),
+                '\n' => Some('\n'),
+                c if encode_char(c).is_some() => Some(c),
+                c => c.to_string().nfd().next().filter(|b| encode_char(*b).is_some()),
+            };
+            replaced.into_iter().collect()
+        })
+        .collect()
+}
+
+/// Builds the byte-pair stream for one transmission.
+#[derive(Default)]
+struct Encoder {
+    words: Vec<[u8; 2]>,
+    pending: Option<u8>,
+}
+
+impl Encoder {
+    /// A control code, sent twice for redundancy as broadcast encoders do;
+    /// decoders ignore the repeat.
+    fn control(&mut self, code: [u8; 2]) {
+        self.flush();
+        let word = [parity(code[0]), parity(code[1])];
+        self.words.push(word);
+        self.words.push(word);
+    }
+
+    fn basic(&mut self, byte: u8) {
+        match self.pending.take() {
+            Some(first) => self.words.push([parity(first), parity(byte)]),
+            None => self.pending = Some(byte),
+        }
+    }
+
+    /// Pad a dangling basic character so the next control code is aligned.
+    fn flush(&mut self) {
+        if let Some(first) = self.pending.take() {
+            self.words.push([parity(first), 0x80]);
+        }
+    }
+
+    fn text(&mut self, text: &str) {
+        for c in text.chars() {
+            match encode_char(c) {
+                Some(Code::Basic(b)) => self.basic(b),
+                Some(Code::Special(b)) => self.control([0x11, b]),
+                Some(Code::Extended(fallback, hi, lo)) => {
+                    self.basic(fallback);
+                    self.control([hi, lo]);
+                }
+                None => {}
+            }
+        }
+    }
+
+    /// Preamble address code for `row` (1–15), then tab offsets to `column`.
+    fn position(&mut self, row: usize, column: usize) {
+        let (hi, base) = PAC_ROWS[row - 1];
+        let indent = (column / 4).min(7) as u8;
+        self.control([hi, base | 0x10 | (indent << 1)]);
+        let tabs = column - indent as usize * 4;
+        if tabs > 0 {
+            self.control([0x17, 0x20 + tabs as u8]);
+        }
+    }
+
+    fn finish(mut self) -> Vec<[u8; 2]> {
+        self.flush();
+        self.words
+    }
+}
+
+/// Byte pairs by frame, one pair per frame on CC1.
+#[derive(Default)]
+struct Timeline {
+    frames: BTreeMap<u64, [u8; 2]>,
+}
+
+impl Timeline {
+    fn is_free(&self, frame: u64) -> bool {
+        !self.frames.contains_key(&frame)
+    }
+
+    /// Place `words` contiguously at the first free run starting at or after
+    /// `from`. Returns the frame after the last word.
+    fn place(&mut self, from: u64, words: &[[u8; 2]]) -> u64 {
+        let mut at = from;
+        while !(at..at + words.len() as u64).all(|f| self.is_free(f)) {
+            at += 1;
+        }
+        for (i, word) in words.iter().enumerate() {
+            self.frames.insert(at + i as u64, *word);
+        }
+        at + words.len() as u64
+    }
+
+    /// Place `words` in the latest free frames in `[floor, deadline)`, in
+    /// order, flowing around anything already placed. If there is not enough
+    /// room the remainder continues after `deadline`. Returns the frame after
+    /// the last word.
+    fn place_before(&mut self, floor: u64, deadline: u64, words: &[[u8; 2]]) -> u64 {
+        let mut slots: Vec<u64> = (floor..deadline)
+            .rev()
+            .filter(|&f| self.is_free(f))
+            .take(words.len())
+            .collect();
+        slots.reverse();
+        let mut next = deadline;
+        while slots.len() < words.len() {
+            if self.is_free(next) {
+                slots.push(next);
+            }
+            next += 1;
+        }
+        for (frame, word) in slots.iter().zip(words) {
+            self.frames.insert(*frame, *word);
+        }
+        slots.last().map_or(deadline, |f| f + 1)
+    }
+
+    /// Runs of consecutive frames, one SCC line each.
+    fn lines(&self) -> Vec<(u64, Vec<[u8; 2]>)> {
+        let mut lines: Vec<(u64, Vec<[u8; 2]>)> = Vec::new();
+        for (&frame, &word) in &self.frames {
+            match lines.last_mut() {
+                Some((start, words)) if *start + words.len() as u64 == frame => words.push(word),
+                _ => lines.push((frame, vec![word])),
+            }
+        }
+        lines
+    }
+}
+
+/// Centre a row in the 32-column grid.
+fn centred_column(row: &str) -> usize {
+    (COLUMNS - row.chars().count().min(COLUMNS)) / 2
+}
+
+/// `EDM` unless the next caption replaces this one first.
+fn clears(cues: &[(u64, u64, Vec<String>)], i: usize) -> bool {
+    cues.get(i + 1).is_none_or(|(next, _, _)| *next > cues[i].1)
+}
+
+fn erase_displayed() -> Vec<[u8; 2]> {
+    let mut enc = Encoder::default();
+    enc.control(EDM);
+    enc.finish()
+}
+
+/// One pop-on caption per cue chunk. The caption is loaded into non-displayed
+/// memory in the frames before its start (after the previous caption has been
+/// swapped in, since that swap would take it too) and shown with `EOC`.
+fn pop_on(cues: &[(u64, u64, Vec<String>)]) -> Timeline {
+    let mut timeline = Timeline::default();
+    let mut floor = 0;
+    for (i, (start, end, rows)) in cues.iter().enumerate() {
+        let mut load = Encoder::default();
+        load.control(RCL);
+        load.control(ENM);
+        let first_row = 16 - rows.len();
+        for (offset, row) in rows.iter().enumerate() {
+            load.position(first_row + offset, centred_column(row));
+            load.text(row);
+        }
+        let loaded = timeline.place_before(floor, *start, &load.finish());
+
+        let mut show = Encoder::default();
+        show.control(EOC);
+        floor = timeline.place((*start).max(loaded), &show.finish());
+
+        if clears(cues, i) {
+            timeline.place((*end).max(floor), &erase_displayed());
+        }
+    }
+    timeline
+}
+
+/// Roll-up captions: every row is sent as the cue plays, spread evenly across
+/// its duration, and rolls the `depth`-row window up from row 15.
+fn roll_up(cues: &[(u64, u64, Vec<String>)], depth: u8) -> Timeline {
+    let mut timeline = Timeline::default();
+    let mut cursor = 0;
+    for (i, (start, end, rows)) in cues.iter().enumerate() {
+        let step = (end - start) / rows.len().max(1) as u64;
+        for (j, row) in rows.iter().enumerate() {
+            let mut line = Encoder::default();
+            line.control([0x14, RU2 + depth - 2]);
+            line.control(CR);
+            line.position(15, 0);
+            line.text(row);
+            cursor = timeline.place((start + j as u64 * step).max(cursor), &line.finish());
+        }
+        if clears(cues, i) {
+            cursor = timeline.place((*end).max(cursor), &erase_displayed());
+        }
+    }
+    timeline
+}
+
+/// `Scenarist_SCC V1.0` header followed by one timecoded line per burst.
+pub fn render(segments: &[Segment], opts: &ExportOptions) -> String {
+    let depth = opts.roll_up.map(|rows| rows.clamp(2, ROWS as u8));
+    let window = depth.map_or(ROWS, usize::from);
+
+    // Cue timing in frames, with rows wrapped and chunked to the window size.
+    let mut cues: Vec<(u64, u64, Vec<String>)> = Vec::new();
+    let mut last_speaker: Option<&str> = None;
+    for seg in segments {
+        let mut text = to_608_text(seg.text.trim());
+        if depth.is_some() && seg.speaker_id.is_some() && seg.speaker_id.as_deref() != last_speaker {
+            text = format!(">> {text}");
+        }
+        last_speaker = seg.speaker_id.as_deref().or(last_speaker);
+        let rows = wrap_rows(&text, COLUMNS);
+        if rows.is_empty() {
+            continue;
+        }
+        let start = FRAME_RATE.frames(seg.start);
+        let end = FRAME_RATE.frames(seg.end).max(start + 1);
+        let chunks: Vec<&[String]> = match depth {
+            // Roll-up scrolls, so the whole cue is sent line by line.
+            Some(_) => vec![&rows[..]],
+            None => rows.chunks(window).collect(),
+        };
+        let per_chunk = (end - start) / chunks.len() as u64;
+        for (k, chunk) in chunks.iter().enumerate() {
+            let chunk_start = start + k as u64 * per_chunk;
+            let chunk_end = if k + 1 == chunks.len() { end } else { chunk_start + per_chunk };
+            cues.push((chunk_start, chunk_end, chunk.to_vec()));
+        }
+    }
+
+    let timeline = match depth {
+        Some(depth) => roll_up(&cues, depth),
+        None => pop_on(&cues),
+    };
+    let mut out = String::from("Scenarist_SCC V1.0\n\n");
+    for (frame, words) in timeline.lines() {
+        let hex: Vec<String> = words.iter().map(|[a, b]| format!("{a:02x}{b:02x}")).collect();
+        out.push_str(&format!("{}\t{}\n\n", FRAME_RATE.timecode(frame), hex.join(" ")));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::tests::seg;
+
+    /// Decode SCC back into (frame, bytes) with parity checked and stripped.
+    fn decode(scc: &str) -> Vec<(String, Vec<u8>)> {
+        let body = scc.strip_prefix("Scenarist_SCC V1.0\n\n").expect("header");
+        body.split("\n\n")
+            .filter(|l| !l.is_empty())
+            .map(|line| {
+                let (tc, data) = line.split_once('\t').expect("tab after timecode");
+                let bytes = data
+                    .split(' ')
+                    .flat_map(|w| {
+                        assert_eq!(w.len(), 4, "{w}");
+                        let v = u16::from_str_radix(w, 16).unwrap();
+                        [(v >> 8) as u8, v as u8]
+                    })
+                    .inspect(|b| assert_eq!(b.count_ones() % 2, 1, "byte {b:02x} fails odd parity"))
+                    .map(|b| b & 0x7F)
+                    .collect();
+                (tc.to_string(), bytes)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn drop_frame_timecode_skips_two_frames_per_minute() {
+        assert_eq!(FRAME_RATE.timecode(0), "00:00:00;00");
+        assert_eq!(FRAME_RATE.timecode(1799), "00:00:59;29");
+        assert_eq!(FRAME_RATE.timecode(1800), "00:01:00;02");
+        assert_eq!(FRAME_RATE.timecode(17_982), "00:10:00;00");
+        assert_eq!(FRAME_RATE.timecode(107_892), "01:00:00;00");
+    }
+
+    #[test]
+    fn characters_encode_with_parity_and_fallbacks() {
+        assert_eq!(parity(0x14), 0x94);
+        assert_eq!(parity(0x2F), 0x2F);
+        let mut enc = Encoder::default();
+        enc.text("Ñ♪É");
+        assert_eq!(
+            enc.finish(),
+            vec![[0xFD, 0x80], [0x91, 0x37], [0x91, 0x37], [0x45, 0x80], [0x92, 0xA1], [0x92, 0xA1]]
+        );
+        assert_eq!(to_608_text("Čapek – “ok”…"), "Capek - “ok”...");
+    }
+
+    #[test]
+    fn long_lines_wrap_to_32_columns() {
+        let rows = wrap_rows("This sentence is considerably longer than thirty-two columns\nok", COLUMNS);
+        assert_eq!(rows, ["This sentence is considerably", "longer than thirty-two columns", "ok"]);
+        assert!(wrap_rows(&"x".repeat(40), COLUMNS).iter().all(|r| r.chars().count() <= COLUMNS));
+    }
+
+    /// Frame count for a drop-frame timecode.
+    fn frame_of(tc: &str) -> u64 {
+        let n: Vec<u64> = tc.split([':', ';']).map(|p| p.parse().unwrap()).collect();
+        let minutes = n[0] * 60 + n[1];
+        minutes * 1800 + n[2] * 30 + n[3] - 2 * (minutes - minutes / 10)
+    }
+
+    /// Every byte pair with the frame it is sent on.
+    fn by_frame(scc: &str) -> Vec<(u64, [u8; 2])> {
+        decode(scc)
+            .into_iter()
+            .flat_map(|(tc, bytes)| {
+                let start = frame_of(&tc);
+                bytes
+                    .chunks(2)
+                    .enumerate()
+                    .map(|(i, w)| (start + i as u64, [w[0], w[1]]))
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn pop_on_displays_at_cue_start_and_clears_at_end() {
+        let out = render(&[seg(2.0, 4.0, "Hi there", None)], &ExportOptions::default());
+        let lines = decode(&out);
+        // Load (RCL ENM PAC text) running straight into EOC, then EDM at 4s.
+        assert_eq!(lines.len(), 2, "{out}");
+        let load = &lines[0].1;
+        assert_eq!(&load[..8], &[0x14, 0x20, 0x14, 0x20, 0x14, 0x2E, 0x14, 0x2E]);
+        // Row 15, centred: column 12 = indent 12 (0x76).
+        assert_eq!(&load[8..12], &[0x14, 0x76, 0x14, 0x76]);
+        assert_eq!(&load[12..20], b"Hi there");
+        assert_eq!(&load[20..], &[0x14, 0x2F, 0x14, 0x2F]);
+        // EOC lands exactly on the cue start (frame 60).
+        assert_eq!(frame_of(&lines[0].0) + 10, 60);
+        assert_eq!(lines[1], (FRAME_RATE.timecode(FRAME_RATE.frames(4.0)), vec![0x14, 0x2C, 0x14, 0x2C]));
+    }
+
+    #[test]
+    fn back_to_back_pop_ons_swap_without_clearing() {
+        let segments = vec![seg(1.0, 2.0, "One", None), seg(2.0, 3.0, "Two", None)];
+        let out = render(&segments, &ExportOptions::default());
+        assert_eq!(out.matches("942c 942c").count(), 1, "{out}");
+    }
+
+    #[test]
+    fn loads_flow_around_a_clear() {
+        // The second caption's load overlaps the first caption's EDM.
+        let long = "abcdefghijklmnopqrstuvwxyz abcde\nabcdefghijklmnopqrstuvwxyz abcde";
+        let segments = vec![seg(1.0, 2.0, "One", None), seg(2.5, 4.0, long, None)];
+        let frames = by_frame(&render(&segments, &ExportOptions::default()));
+        let at = |word: [u8; 2]| frames.iter().filter(|(_, w)| *w == word).map(|(f, _)| *f).collect::<Vec<_>>();
+        assert_eq!(at([0x14, 0x2C])[..2], [60, 61]);
+        let eoc = at([0x14, 0x2F]);
+        assert_eq!(eoc, [30, 31, 75, 76]);
+        // The second load starts only after the first caption is swapped in.
+        let rcl = at([0x14, 0x20]);
+        assert!(rcl[2] > 31 && rcl[3] < 60, "{rcl:?}");
+    }
+
+    #[test]
+    fn roll_up_sends_each_row_with_speaker_markers() {
+        let segments = vec![seg(0.0, 2.0, "Hello\nworld", Some("1")), seg(2.0, 3.0, "Hi", Some("2"))];
+        let opts = ExportOptions { roll_up: Some(3), ..Default::default() };
+        let lines = decode(&render(&segments, &opts));
+        assert_eq!(lines.len(), 4);
+        for (_, bytes) in &lines[..3] {
+            assert_eq!(&bytes[..12], &[0x14, 0x26, 0x14, 0x26, 0x14, 0x2D, 0x14, 0x2D, 0x14, 0x70, 0x14, 0x70]);
+        }
+        assert_eq!(&lines[0].1[12..], b">> Hello");
+        assert_eq!(&lines[1].1[12..], b"world\0");
+        assert_eq!(lines[1].0, "00:00:01;00");
+        assert_eq!(&lines[2].1[12..], b">> Hi\0");
+        assert_eq!(lines[3].1, [0x14, 0x2C, 0x14, 0x2C]);
+    }
+}
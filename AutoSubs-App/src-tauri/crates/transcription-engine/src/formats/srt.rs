@@ -0,0 +1,112 @@
+//! SubRip (`.srt`) writer and parser.
+
+use super::{
+    CueBuilder, ExportOptions, ParsedSubtitles, hms, parse_timing_line, split_speaker_prefix,
+    strip_tags,
+};
+use crate::types::Segment;
+use eyre::{Result, bail};
+
+/// `HH:MM:SS,mmm` timestamp.
+pub fn timestamp(seconds: f64) -> String {
+    let (h, m, s, ms) = hms(seconds);
+    format!("{h:02}:{m:02}:{s:02},{ms:03}")
+}
+
+/// One numbered cue per segment, speaker-prefixed when diarized.
+pub fn render(segments: &[Segment], opts: &ExportOptions) -> String {
+    let mut out = String::new();
+    for (i, seg) in segments.iter().enumerate() {
+        let end = seg.end.max(seg.start);
+        out.push_str(&format!(
+            "{}\n{} --> {}\n{}{}\n\n",
+            i + 1,
+            timestamp(seg.start),
+            timestamp(end),
+            opts.speaker_prefix(seg.speaker_id.as_deref()),
+            seg.text.trim()
+        ));
+    }
+    out
+}
+
+/// Parse SubRip cues. Cue numbers are optional and ignored; formatting tags
+/// and `{\an8}`-style position overrides are stripped, and a leading
+/// `Speaker N: ` becomes the cue's speaker.
+pub fn parse(content: &str) -> Result<ParsedSubtitles> {
+    let mut cues = CueBuilder::default();
+    let mut lines = content.lines().peekable();
+    let mut found = false;
+    while let Some(line) = lines.next() {
+        let Some((start, end)) = parse_timing_line(line) else {
+            if !line.trim().is_empty() && !line.trim().bytes().all(|b| b.is_ascii_digit()) {
+                tracing::warn!("skipping unexpected SRT line: {line}");
+            }
+            continue;
+        };
+        found = true;
+        let mut text = Vec::new();
+        while let Some(l) = lines.next_if(|l| !l.trim().is_empty()) {
+            text.push(strip_overrides(&strip_tags(l)));
+        }
+        let text = text.join("\n");
+        let (speaker, text) = split_speaker_prefix(&text);
+        cues.push(start, end, text, speaker);
+    }
+    if !found {
+        bail!("no SRT cues found");
+    }
+    Ok(cues.finish())
+}
+
+/// Remove `{\...}` override blocks some SRT writers borrow from ASS.
+fn strip_overrides(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut rest = line;
+    while let Some(open) = rest.find("{\\") {
+        out.push_str(&rest[..open]);
+        match rest[open..].find('}') {
+            Some(close) => rest = &rest[open + close + 1..],
+            None => {
+                rest = &rest[open..];
+                break;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::tests::seg;
+
+    #[test]
+    fn renders_numbered_cues_with_speaker_prefix() {
+        let segments = vec![
+            seg(0.0, 1.5, "Hello there.", Some("1")),
+            seg(1.5, 1.2, " General Kenobi.\nYou are bold. ", None),
+        ];
+        let out = render(&segments, &ExportOptions::default());
+        assert_eq!(
+            out,
+            "1\n00:00:00,000 --> 00:00:01,500\nSpeaker 1: Hello there.\n\n\
+             2\n00:00:01,500 --> 00:00:01,500\nGeneral Kenobi.\nYou are bold.\n\n"
+        );
+    }
+
+    #[test]
+    fn parses_cues_and_strips_markup() {
+        let content = "1\n00:00:01,000 --> 00:00:02,500\n<i>Speaker 2: Hello</i>\nthere\n\n\
+                       2\n00:00:03,000 --> 00:00:04,000 X1:10 X2:20\n{\\an8}Top &amp; tail\n";
+        let parsed = parse(content).unwrap();
+        assert_eq!(parsed.segments.len(), 2);
+        assert_eq!(parsed.segments[0].text, "Hello\nthere");
+        assert_eq!(parsed.segments[0].speaker_id.as_deref(), Some("2"));
+        assert_eq!((parsed.segments[0].start, parsed.segments[0].end), (1.0, 2.5));
+        assert_eq!(parsed.segments[1].text, "Top & tail");
+        assert_eq!(parsed.segments[1].speaker_id, None);
+        assert!(parse("no cues here").is_err());
+    }
+}
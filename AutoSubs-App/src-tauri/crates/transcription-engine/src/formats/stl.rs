@@ -0,0 +1,558 @@
+//! EBU STL (Tech 3264) binary subtitle writer and reader.
+//!
+//! A file is a 1024-byte General Subtitle Information (GSI) block followed by
+//! one 128-byte Text and Timing Information (TTI) block per subtitle, with
+//! extension blocks for text longer than one block's 112-byte text field.
+//!
+//! Subtitles are written for Level-1 teletext: every row is double height in
+//! a boxed area, centred, with the bottom row ending on row 23. Each speaker
+//! gets a teletext colour (white, yellow, cyan, green, in order of
+//! appearance), which costs one of the 40 columns, so rows are wrapped to
+//! [`ROW_CHARS`]. Use
+//! [`PostProcessConfig::teletext`](crate::PostProcessConfig::teletext) when
+//! running `process_segments` so that rarely has to happen.
+//!
+//! Text is encoded in the [`CodePage`] for the transcript language (or, for
+//! `auto`, the dominant alphabet of the text). Timecodes count whole frames at
+//! 25 fps (`STL25.01`), or 30 fps (`STL30.01`) when [`ExportOptions::frame_rate`]
+//! is a 30 or 60 fps family rate; NTSC rates get real-time timecode. Cue
+//! timecodes start at [`ExportOptions::start_frame`], which is also written as
+//! the start of programme.
+
+use super::{CueBuilder, ExportOptions, FrameRate, ParsedSubtitles, speakers_in_order, track_cues, wrap_rows};
+use crate::formatting::{ScriptProfile, profile_for_lang};
+use crate::types::Segment;
+use eyre::{Result, bail};
+use unicode_normalization::UnicodeNormalization;
+
+/// Characters per teletext row: 40 columns less the double-height, colour
+/// and two start-box codes in front of the text.
+pub const ROW_CHARS: usize = 36;
+
+const GSI_LEN: usize = 1024;
+const TTI_LEN: usize = 128;
+const TEXT_LEN: usize = 112;
+
+// Teletext control codes in the text field.
+const DOUBLE_HEIGHT: u8 = 0x0D;
+const START_BOX: u8 = 0x0B;
+const CR_LF: u8 = 0x8A;
+const UNUSED: u8 = 0x8F;
+/// Extension block number of a subtitle's last (or only) block.
+const LAST_BLOCK: u8 = 0xFF;
+
+/// Speaker colours in order of appearance: white, yellow, cyan, green.
+const SPEAKER_COLORS: [u8; 4] = [0x07, 0x03, 0x06, 0x02];
+
+/// EBU language codes for ISO 639-1 languages (Tech 3264 appendix 3).
+const LANGUAGE_CODES: &[(&str, u8)] = &[
+    ("sq", 0x01), ("br", 0x02), ("ca", 0x03), ("hr", 0x04), ("cy", 0x05),
+    ("cs", 0x06), ("da", 0x07), ("de", 0x08), ("en", 0x09), ("es", 0x0A),
+    ("eo", 0x0B), ("et", 0x0C), ("eu", 0x0D), ("fo", 0x0E), ("fr", 0x0F),
+    ("fy", 0x10), ("ga", 0x11), ("gd", 0x12), ("gl", 0x13), ("is", 0x14),
+    ("it", 0x15), ("se", 0x16), ("la", 0x17), ("lv", 0x18), ("lb", 0x19),
+    ("lt", 0x1A), ("hu", 0x1B), ("mt", 0x1C), ("nl", 0x1D), ("no", 0x1E),
+    ("nb", 0x1E), ("nn", 0x1E), ("oc", 0x1F), ("pl", 0x20), ("pt", 0x21),
+    ("ro", 0x22), ("rm", 0x23), ("sr", 0x24), ("sk", 0x25), ("sl", 0x26),
+    ("fi", 0x27), ("sv", 0x28), ("tr", 0x29), ("zu", 0x45), ("vi", 0x46),
+    ("uz", 0x47), ("ur", 0x48), ("uk", 0x49), ("th", 0x4A), ("te", 0x4B),
+    ("tt", 0x4C), ("ta", 0x4D), ("tg", 0x4E), ("sw", 0x4F), ("so", 0x51),
+    ("si", 0x52), ("sn", 0x53), ("ru", 0x56), ("qu", 0x57), ("ps", 0x58),
+    ("pa", 0x59), ("fa", 0x5A), ("or", 0x5C), ("ne", 0x5D), ("mr", 0x5F),
+    ("ms", 0x61), ("mg", 0x62), ("mk", 0x63), ("lo", 0x64), ("ko", 0x65),
+    ("km", 0x66), ("kk", 0x67), ("kn", 0x68), ("ja", 0x69), ("id", 0x6A),
+    ("hi", 0x6B), ("he", 0x6C), ("ha", 0x6D), ("gu", 0x6F), ("el", 0x70),
+    ("ka", 0x71), ("zh", 0x75), ("my", 0x76), ("bg", 0x77), ("bn", 0x78),
+    ("be", 0x79), ("az", 0x7B), ("as", 0x7C), ("hy", 0x7D), ("ar", 0x7E),
+    ("am", 0x7F),
+];
+
+/// Character code table of the text fields (GSI `CCT`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CodePage {
+    /// ISO 6937, the default Latin table.
+    Latin,
+    /// ISO 8859-5.
+    Cyrillic,
+    /// ISO 8859-6.
+    Arabic,
+    /// ISO 8859-7.
+    Greek,
+    /// ISO 8859-8.
+    Hebrew,
+}
+
+impl CodePage {
+    /// Table for a language code: by alphabet for languages written in
+    /// Cyrillic, Greek or Hebrew, then by [`profile_for_lang`] (right-to-left
+    /// scripts other than Hebrew use Arabic).
+    pub fn for_language(lang: &str) -> Self {
+        let primary = lang.split(['-', '_']).next().unwrap_or(lang).to_ascii_lowercase();
+        match primary.as_str() {
+            "ru" | "uk" | "be" | "bg" | "sr" | "mk" | "kk" | "ky" | "mn" | "tg" | "tt" | "ba" => Self::Cyrillic,
+            "el" => Self::Greek,
+            "he" | "iw" | "yi" => Self::Hebrew,
+            _ if profile_for_lang(&primary) == ScriptProfile::RTL => Self::Arabic,
+            _ => Self::Latin,
+        }
+    }
+
+    /// Table for the alphabet most letters in `text` are written in.
+    pub fn for_text(text: &str) -> Self {
+        let mut counts = [0usize; 5];
+        for c in text.chars().filter(|c| c.is_alphabetic()) {
+            let page = match c {
+                '\u{0400}'..='\u{04FF}' => Self::Cyrillic,
+                '\u{0600}'..='\u{06FF}' => Self::Arabic,
+                '\u{0370}'..='\u{03FF}' => Self::Greek,
+                '\u{0590}'..='\u{05FF}' => Self::Hebrew,
+                _ => Self::Latin,
+            };
+            counts[page as usize] += 1;
+        }
+        let best = (0..counts.len()).max_by_key(|&i| (counts[i], i == 0)).unwrap_or(0);
+        [Self::Latin, Self::Cyrillic, Self::Arabic, Self::Greek, Self::Hebrew][best]
+    }
+
+    fn cct(self) -> &'static [u8; 2] {
+        match self {
+            Self::Latin => b"00",
+            Self::Cyrillic => b"01",
+            Self::Arabic => b"02",
+            Self::Greek => b"03",
+            Self::Hebrew => b"04",
+        }
+    }
+
+    fn from_cct(cct: &[u8]) -> Option<Self> {
+        [Self::Latin, Self::Cyrillic, Self::Arabic, Self::Greek, Self::Hebrew]
+            .into_iter()
+            .find(|page| page.cct() == cct)
+    }
+
+    /// Bytes for one character, or `None` when the table has no encoding.
+    fn encode(self, c: char) -> Option<Vec<u8>> {
+        if matches!(c, ' '..='~') && !(self == Self::Latin && c == '$') {
+            return Some(vec![c as u8]);
+        }
+        let cp = c as u32;
+        let byte = match self {
+            Self::Latin => return encode_6937(c),
+            Self::Cyrillic => match cp {
+                0x0401..=0x045F if !matches!(cp, 0x040D | 0x0450 | 0x045D) => cp - 0x0360,
+                0x2116 => 0xF0,
+                _ => return None,
+            },
+            Self::Arabic => match cp {
+                0x060C | 0x061B | 0x061F | 0x0621..=0x063A | 0x0640..=0x0652 => cp - 0x0560,
+                0x0660..=0x0669 => cp - 0x0660 + u32::from(b'0'),
+                _ => return None,
+            },
+            Self::Greek => match cp {
+                0x0384..=0x03CE if !matches!(cp, 0x038B | 0x038D | 0x03A2) => cp - 0x02D0,
+                0x2018 => 0xA1,
+                0x2019 => 0xA2,
+                _ => return None,
+            },
+            Self::Hebrew => match cp {
+                0x05D0..=0x05EA => cp - 0x04F0,
+                0x00D7 => 0xAA,
+                0x00F7 => 0xBA,
+                _ => return None,
+            },
+        };
+        Some(vec![byte as u8])
+    }
+
+    /// Character for a single byte above ASCII (diacritics are handled by
+    /// the caller for the Latin table).
+    fn decode(self, byte: u8) -> Option<char> {
+        let b = u32::from(byte);
+        let cp = match self {
+            Self::Latin => return LATIN_SPECIALS.iter().find(|(_, v)| *v == byte).map(|(c, _)| *c),
+            Self::Cyrillic => match byte {
+                0xF0 => 0x2116,
+                0xA1..=0xFF if byte != 0xAD => b + 0x0360,
+                _ => return None,
+            },
+            Self::Arabic => b + 0x0560,
+            Self::Greek => match byte {
+                0xA1 => 0x2018,
+                0xA2 => 0x2019,
+                _ => b + 0x02D0,
+            },
+            Self::Hebrew => match byte {
+                0xAA => 0x00D7,
+                0xBA => 0x00F7,
+                _ => b + 0x04F0,
+            },
+        };
+        char::from_u32(cp).filter(|&c| self.encode(c).as_deref() == Some(&[byte][..]))
+    }
+}
+
+/// ISO 6937 characters without a diacritic prefix.
+const LATIN_SPECIALS: &[(char, u8)] = &[
+    ('¡', 0xA1), ('¢', 0xA2), ('£', 0xA3), ('$', 0xA4), ('¥', 0xA5), ('#', 0xA6),
+    ('§', 0xA7), ('¤', 0xA8), ('‘', 0xA9), ('“', 0xAA), ('«', 0xAB), ('°', 0xB0),
+    ('±', 0xB1), ('²', 0xB2), ('³', 0xB3), ('×', 0xB4), ('µ', 0xB5), ('¶', 0xB6),
+    ('·', 0xB7), ('÷', 0xB8), ('’', 0xB9), ('”', 0xBA), ('»', 0xBB), ('¼', 0xBC),
+    ('½', 0xBD), ('¾', 0xBE), ('¿', 0xBF), ('―', 0xD0), ('¹', 0xD1), ('®', 0xD2),
+    ('©', 0xD3), ('™', 0xD4), ('♪', 0xD5), ('Ω', 0xE0), ('Æ', 0xE1), ('Đ', 0xE2),
+    ('ª', 0xE3), ('Ħ', 0xE4), ('Ĳ', 0xE6), ('Ŀ', 0xE7), ('Ł', 0xE8), ('Ø', 0xE9),
+    ('Œ', 0xEA), ('º', 0xEB), ('Þ', 0xEC), ('Ŧ', 0xED), ('Ŋ', 0xEE), ('ŉ', 0xEF),
+    ('ĸ', 0xF0), ('æ', 0xF1), ('đ', 0xF2), ('ð', 0xF3), ('ħ', 0xF4), ('ı', 0xF5),
+    ('ĳ', 0xF6), ('ŀ', 0xF7), ('ł', 0xF8), ('ø', 0xF9), ('œ', 0xFA), ('ß', 0xFB),
+    ('þ', 0xFC), ('ŧ', 0xFD), ('ŋ', 0xFE),
+];
+
+/// ISO 6937 non-spacing diacritics, sent before the letter they modify.
+const LATIN_DIACRITICS: &[(char, u8)] = &[
+    ('\u{0300}', 0xC1), ('\u{0301}', 0xC2), ('\u{0302}', 0xC3), ('\u{0303}', 0xC4),
+    ('\u{0304}', 0xC5), ('\u{0306}', 0xC6), ('\u{0307}', 0xC7), ('\u{0308}', 0xC8),
+    ('\u{030A}', 0xCA), ('\u{0327}', 0xCB), ('\u{030B}', 0xCD), ('\u{0328}', 0xCE),
+    ('\u{030C}', 0xCF),
+];
+
+fn encode_6937(c: char) -> Option<Vec<u8>> {
+    if let Some(&(_, byte)) = LATIN_SPECIALS.iter().find(|(s, _)| *s == c) {
+        return Some(vec![byte]);
+    }
+    // Accented letters: one diacritic byte, then the base letter.
+    let parts: Vec<char> = c.to_string().nfd().collect();
+    match parts[..] {
+        [base @ ('A'..='Z' | 'a'..='z'), mark] => LATIN_DIACRITICS
+            .iter()
+            .find(|(m, _)| *m == mark)
+            .map(|&(_, byte)| vec![byte, base as u8]),
+        _ => None,
+    }
+}
+
+/// Encode a row, one entry per character: typographic punctuation is
+/// simplified, letters the table cannot hold lose their accents, and anything
+/// left over is dropped.
+fn encode_text(text: &str, page: CodePage) -> Vec<Vec<u8>> {
+    let mut out = Vec::new();
+    for c in text.chars() {
+        let c = match c {
+            '–' | '‐' | '‑' => '-',
+            '—' if page != CodePage::Latin => '-',
+            '—' => '―',
+            '…' => {
+                out.extend([vec![b'.'], vec![b'.'], vec![b'.']]);
+                continue;
+            }
+            '\u{00A0}' | '\t' => ' ',
+            c => c,
+        };
+        if let Some(bytes) = page.encode(c).or_else(|| c.to_string().nfd().next().and_then(|b| page.encode(b))) {
+            out.push(bytes);
+        }
+    }
+    out
+}
+
+/// Decode a text field into lines.
+fn decode_text(field: &[u8], page: CodePage) -> String {
+    let mut lines = vec![String::new()];
+    let mut i = 0;
+    while i < field.len() {
+        let byte = field[i];
+        i += 1;
+        match byte {
+            UNUSED => break,
+            CR_LF => {
+                if !lines.last().is_some_and(|l| l.trim().is_empty()) {
+                    lines.push(String::new());
+                }
+            }
+            // Teletext and open-subtitle control codes.
+            0x00..=0x1F | 0x7F..=0x9F => {}
+            0x20..=0x7E => lines.last_mut().unwrap().push(byte as char),
+            0xC1..=0xCF if page == CodePage::Latin => {
+                let mark = LATIN_DIACRITICS.iter().find(|(_, b)| *b == byte).map(|(m, _)| *m);
+                if let (Some(mark), Some(&base)) = (mark, field.get(i)) {
+                    i += 1;
+                    lines.last_mut().unwrap().extend([base as char, mark].into_iter().nfc());
+                }
+            }
+            _ => lines.last_mut().unwrap().extend(page.decode(byte)),
+        }
+    }
+    lines.iter().map(|l| l.trim()).filter(|l| !l.is_empty()).collect::<Vec<_>>().join("\n")
+}
+
+/// STL timecodes count whole frames at 25 or 30 fps.
+fn stl_rate(opts: &ExportOptions) -> FrameRate {
+    match opts.frame_rate.map(|r| r.nominal()) {
+        Some(30 | 60) => FrameRate { num: 30, den: 1 },
+        _ => FrameRate { num: 25, den: 1 },
+    }
+}
+
+/// `[HH, MM, SS, FF]` for a frame count at a whole-number `fps`.
+fn timecode(frame: u64, fps: u64) -> [u8; 4] {
+    let secs = frame / fps;
+    [(secs / 3600 % 24) as u8, (secs / 60 % 60) as u8, (secs % 60) as u8, (frame % fps) as u8]
+}
+
+/// `HHMMSSFF` as written in the GSI block.
+fn timecode_digits(frame: u64, fps: u64) -> String {
+    timecode(frame, fps).iter().map(|v| format!("{v:02}")).collect()
+}
+
+/// Frame count for a timecode, in either form.
+fn timecode_frames(tc: [u64; 4], fps: u64) -> u64 {
+    ((tc[0] * 60 + tc[1]) * 60 + tc[2]) * fps + tc[3]
+}
+
+/// Today's date as `YYMMDD` (UTC).
+fn today() -> String {
+    let secs = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    yymmdd(secs)
+}
+
+/// `YYMMDD` for a Unix timestamp, from the days-to-civil-date algorithm.
+fn yymmdd(unix_secs: u64) -> String {
+    let z = (unix_secs / 86_400) as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{:02}{month:02}{day:02}", year % 100)
+}
+
+/// Write `value` into a space-padded GSI field.
+fn put(gsi: &mut [u8], offset: usize, value: &str) {
+    let bytes = value.as_bytes();
+    gsi[offset..offset + bytes.len()].copy_from_slice(bytes);
+}
+
+/// Render an STL file.
+pub fn render(segments: &[Segment], opts: &ExportOptions) -> Vec<u8> {
+    let rate = stl_rate(opts);
+    let fps = u64::from(rate.num);
+    let lang = opts.language.as_deref().filter(|l| !l.is_empty() && *l != "auto");
+    let page = match lang {
+        Some(lang) => CodePage::for_language(lang),
+        None => CodePage::for_text(&segments.iter().map(|s| s.text.as_str()).collect::<Vec<_>>().join(" ")),
+    };
+    let start = rate.frames(opts.start_frame as f64 / opts.sequence_rate().fps());
+    let speakers = speakers_in_order(segments);
+
+    let mut blocks: Vec<[u8; TTI_LEN]> = Vec::new();
+    let cues = track_cues(segments, rate);
+    for (n, &(cue_start, cue_end, seg)) in cues.iter().enumerate() {
+        let rows = wrap_rows(seg.text.trim(), ROW_CHARS);
+        let color = seg
+            .speaker_id
+            .as_deref()
+            .and_then(|id| speakers.iter().position(|s| *s == id))
+            .map_or(SPEAKER_COLORS[0], |i| SPEAKER_COLORS[i % SPEAKER_COLORS.len()]);
+
+        // Characters and each row's control codes are indivisible, so
+        // extension blocks never split them.
+        let mut units: Vec<Vec<u8>> = Vec::new();
+        for (i, row) in rows.iter().enumerate() {
+            if i > 0 {
+                units.push(vec![CR_LF, CR_LF]);
+            }
+            units.push(vec![DOUBLE_HEIGHT, color, START_BOX, START_BOX]);
+            units.extend(encode_text(row, page));
+        }
+        let mut fields: Vec<Vec<u8>> = vec![Vec::new()];
+        for unit in units {
+            if fields.last().unwrap().len() + unit.len() > TEXT_LEN {
+                fields.push(Vec::new());
+            }
+            fields.last_mut().unwrap().extend(unit);
+        }
+
+        // Double-height rows take two teletext rows each, ending on row 23.
+        let vertical = 24usize.saturating_sub(2 * rows.len()).max(1) as u8;
+        let sn = ((n + 1) as u16).to_le_bytes();
+        for (k, field) in fields.iter().enumerate() {
+            let mut tti = [UNUSED; TTI_LEN];
+            tti[0] = 0; // subtitle group
+            tti[1..3].copy_from_slice(&sn);
+            tti[3] = if k + 1 == fields.len() { LAST_BLOCK } else { k as u8 };
+            tti[4] = 0; // cumulative status: not cumulative
+            tti[5..9].copy_from_slice(&timecode(start + cue_start, fps));
+            tti[9..13].copy_from_slice(&timecode(start + cue_end, fps));
+            tti[13] = vertical;
+            tti[14] = 2; // centred
+            tti[15] = 0; // not a comment
+            tti[16..16 + field.len()].copy_from_slice(field);
+            blocks.push(tti);
+        }
+    }
+
+    let mut gsi = [b' '; GSI_LEN];
+    put(&mut gsi, 0, "850");
+    put(&mut gsi, 3, if fps == 30 { "STL30.01" } else { "STL25.01" });
+    put(&mut gsi, 11, "1"); // Level-1 teletext
+    gsi[12..14].copy_from_slice(page.cct());
+    let lc = lang
+        .map(|l| l.split(['-', '_']).next().unwrap_or(l).to_ascii_lowercase())
+        .and_then(|l| LANGUAGE_CODES.iter().find(|(code, _)| *code == l))
+        .map_or(0, |&(_, lc)| lc);
+    put(&mut gsi, 14, &format!("{lc:02X}"));
+    let date = today();
+    put(&mut gsi, 224, &date); // creation
+    put(&mut gsi, 230, &date); // revision
+    put(&mut gsi, 236, "00");
+    put(&mut gsi, 238, &format!("{:05}", blocks.len().min(99_999)));
+    put(&mut gsi, 243, &format!("{:05}", cues.len().min(99_999)));
+    put(&mut gsi, 248, "001");
+    put(&mut gsi, 251, "40");
+    put(&mut gsi, 253, "23");
+    put(&mut gsi, 255, "1"); // timecodes are intended for use
+    put(&mut gsi, 256, &timecode_digits(start, fps));
+    let first = cues.first().map_or(0, |&(s, _, _)| s);
+    put(&mut gsi, 264, &timecode_digits(start + first, fps));
+    put(&mut gsi, 272, "11"); // disk 1 of 1
+
+    let mut out = gsi.to_vec();
+    for block in &blocks {
+        out.extend_from_slice(block);
+    }
+    out
+}
+
+/// Parse an STL file. Cue times are relative to the start of programme.
+/// Comment and user-data blocks are skipped; speakers are not recovered.
+pub fn parse(bytes: &[u8]) -> Result<ParsedSubtitles> {
+    if bytes.len() < GSI_LEN || !(bytes.len() - GSI_LEN).is_multiple_of(TTI_LEN) {
+        bail!("not an EBU STL file: {} bytes is not a GSI block plus whole TTI blocks", bytes.len());
+    }
+    let fps: u64 = match &bytes[3..11] {
+        b"STL25.01" => 25,
+        b"STL30.01" => 30,
+        dfc => bail!("unsupported STL disk format '{}'", String::from_utf8_lossy(dfc)),
+    };
+    let page = CodePage::from_cct(&bytes[12..14]).unwrap_or(CodePage::Latin);
+    let digits = |field: &[u8]| -> Option<u64> { std::str::from_utf8(field).ok()?.trim().parse().ok() };
+    let start = (0..4)
+        .map(|i| digits(&bytes[256 + 2 * i..258 + 2 * i]))
+        .collect::<Option<Vec<u64>>>()
+        .map_or(0, |tc| timecode_frames([tc[0], tc[1], tc[2], tc[3]], fps));
+    let seconds = |tc: &[u8]| {
+        let frames = timecode_frames([tc[0], tc[1], tc[2], tc[3]].map(u64::from), fps);
+        frames.saturating_sub(start) as f64 / fps as f64
+    };
+
+    let mut cues = CueBuilder::default();
+    let mut text: Vec<u8> = Vec::new();
+    for tti in bytes[GSI_LEN..].chunks_exact(TTI_LEN) {
+        // Comments and user-data extension blocks carry no subtitle text.
+        if tti[15] == 1 || tti[3] == 0xFE {
+            continue;
+        }
+        let field = &tti[16..];
+        let used = field.iter().rposition(|&b| b != UNUSED).map_or(0, |i| i + 1);
+        text.extend_from_slice(&field[..used]);
+        if tti[3] == LAST_BLOCK {
+            cues.push(seconds(&tti[5..9]), seconds(&tti[9..13]), &decode_text(&text, page), None);
+            text.clear();
+        }
+    }
+    Ok(cues.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::tests::seg;
+
+    fn gsi_field(stl: &[u8], offset: usize, len: usize) -> &str {
+        std::str::from_utf8(&stl[offset..offset + len]).unwrap()
+    }
+
+    #[test]
+    fn round_trips_through_the_binary_format() {
+        let segments = vec![
+            seg(1.0, 2.48, "Ça va, Øyvind?\nNão, £5 — 50% off!", Some("1")),
+            seg(3.04, 4.0, "Straße…", Some("2")),
+        ];
+        let opts = ExportOptions { language: Some("fr".into()), ..Default::default() };
+        let stl = render(&segments, &opts);
+        assert_eq!(stl.len(), GSI_LEN + 2 * TTI_LEN);
+        assert_eq!(gsi_field(&stl, 3, 8), "STL25.01");
+        assert_eq!(gsi_field(&stl, 12, 4), "000F");
+        assert_eq!(gsi_field(&stl, 238, 10), "0000200002");
+        assert_eq!(gsi_field(&stl, 256, 16), "0000000000000100");
+
+        let first = &stl[GSI_LEN..GSI_LEN + TTI_LEN];
+        assert_eq!(&first[1..4], &[1, 0, LAST_BLOCK]);
+        assert_eq!(&first[5..13], &[0, 0, 1, 0, 0, 0, 2, 12]);
+        assert_eq!(first[13], 20, "two double-height rows end on row 23");
+        assert_eq!(&first[16..21], &[DOUBLE_HEIGHT, 0x07, START_BOX, START_BOX, 0xCB]);
+        let second = &stl[GSI_LEN + TTI_LEN..];
+        assert_eq!(second[17], 0x03, "second speaker is yellow");
+        assert_eq!(&second[5..9], &[0, 0, 3, 1]);
+
+        let parsed = parse(&stl).unwrap();
+        let texts: Vec<&str> = parsed.segments.iter().map(|s| s.text.as_str()).collect();
+        assert_eq!(texts, ["Ça va, Øyvind?\nNão, £5 ― 50% off!", "Straße..."]);
+        assert_eq!((parsed.segments[0].start, parsed.segments[0].end), (1.0, 2.48));
+        assert_eq!(parsed.segments[1].start, 3.04);
+    }
+
+    #[test]
+    fn code_page_follows_the_language() {
+        for (lang, text, cct) in [
+            ("ru", "Привет, мир", "01"),
+            ("ar", "مرحبا بالعالم", "02"),
+            ("el", "Καλημέρα κόσμε", "03"),
+            ("he", "שלום עולם", "04"),
+            ("auto", "Привет, мир", "01"),
+        ] {
+            let opts = ExportOptions { language: Some(lang.into()), ..Default::default() };
+            let stl = render(&[seg(0.0, 1.0, text, None)], &opts);
+            assert_eq!(gsi_field(&stl, 12, 2), cct, "{lang}");
+            assert_eq!(parse(&stl).unwrap().segments[0].text, text, "{lang}");
+        }
+    }
+
+    #[test]
+    fn long_cues_use_extension_blocks_at_30_fps() {
+        let text = "word ".repeat(30);
+        let rate = FrameRate::parse("29.97").unwrap();
+        let opts = ExportOptions {
+            frame_rate: Some(rate),
+            start_frame: rate.parse_timecode("01:00:00;00").unwrap(),
+            ..Default::default()
+        };
+        let stl = render(&[seg(0.5, 2.0, &text, None)], &opts);
+        assert_eq!(gsi_field(&stl, 3, 8), "STL30.01");
+        assert_eq!(gsi_field(&stl, 256, 8), "01000000");
+        let blocks: Vec<&[u8]> = stl[GSI_LEN..].chunks(TTI_LEN).collect();
+        assert_eq!(blocks.len(), 2);
+        assert_eq!((blocks[0][3], blocks[1][3]), (0, LAST_BLOCK));
+        assert_eq!(&blocks[0][5..9], &[1, 0, 0, 15]);
+
+        let parsed = parse(&stl).unwrap();
+        assert_eq!(parsed.segments.len(), 1);
+        assert_eq!(parsed.segments[0].start, 0.5);
+        assert_eq!(parsed.segments[0].text.split_whitespace().count(), 30);
+        assert!(parsed.segments[0].text.lines().all(|l| l.chars().count() <= ROW_CHARS));
+    }
+
+    #[test]
+    fn creation_dates_are_yymmdd() {
+        assert_eq!(yymmdd(0), "700101");
+        assert_eq!(yymmdd(1_700_000_000), "231114");
+    }
+
+    #[test]
+    fn input_shorter_than_a_gsi_block_is_rejected() {
+        assert!(parse(&[b' '; 100]).is_err());
+    }
+}
@@ -0,0 +1,95 @@
+//! Plain-text transcript writer, and the paragraph builder it shares with the
+//! Markdown and HTML documents.
+
+use super::{ExportOptions, ts_clock};
+use crate::types::Segment;
+
+/// Silence gap that starts a new paragraph (see [`paragraphs`]).
+const GAP_BREAK_SECS: f64 = 2.0;
+
+/// Consecutive cues merged into prose, as read by the document formats.
+pub(crate) struct Paragraph {
+    pub start: f64,
+    pub end: f64,
+    pub speaker_id: Option<String>,
+    pub text: String,
+}
+
+/// Merge subtitle cues into paragraphs. The engine's segments are short cues
+/// (wrapped for on-screen display), which read poorly as prose, so consecutive
+/// cues are joined while the speaker stays the same. A silence gap longer than
+/// `GAP_BREAK_SECS` also starts a new paragraph when there are no speaker
+/// labels, or within a speaker's turn when `split_turns` is set, so long
+/// monologues still break at natural pauses.
+pub(crate) fn paragraphs(segments: &[Segment], split_turns: bool) -> Vec<Paragraph> {
+    let mut out: Vec<Paragraph> = Vec::new();
+    for seg in segments {
+        let line = seg.text.trim();
+        if line.is_empty() {
+            continue;
+        }
+        match out.last_mut() {
+            Some(p)
+                if p.speaker_id == seg.speaker_id
+                    && ((seg.speaker_id.is_some() && !split_turns) || seg.start - p.end <= GAP_BREAK_SECS) =>
+            {
+                if !p.text.ends_with(' ') {
+                    p.text.push(' ');
+                }
+                p.text.push_str(line);
+                p.end = seg.end.max(p.end);
+            }
+            _ => out.push(Paragraph {
+                start: seg.start,
+                end: seg.end,
+                speaker_id: seg.speaker_id.clone(),
+                text: line.to_string(),
+            }),
+        }
+    }
+    out
+}
+
+/// `[HH:MM:SS] Speaker N: text` per *speaker turn* — the default, human-readable
+/// transcript. Without diarization, paragraphs break at natural pauses instead.
+pub fn render(segments: &[Segment], opts: &ExportOptions) -> String {
+    paragraphs(segments, false)
+        .iter()
+        .map(|p| text_line(p.start, p.speaker_id.as_deref(), &p.text, opts))
+        .collect()
+}
+
+fn text_line(start: f64, speaker: Option<&str>, text: &str, opts: &ExportOptions) -> String {
+    format!("[{}] {}{text}\n", ts_clock(start), opts.speaker_prefix(speaker))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::tests::seg;
+
+    #[test]
+    fn merges_cues_into_speaker_turns() {
+        let segments = vec![
+            seg(0.0, 1.0, "Hello", Some("1")),
+            seg(5.0, 6.0, "again.", Some("1")),
+            seg(6.0, 7.0, "Hi!", Some("2")),
+        ];
+        let out = render(&segments, &ExportOptions::default());
+        assert_eq!(
+            out,
+            "[00:00:00] Speaker 1: Hello again.\n[00:00:06] Speaker 2: Hi!\n"
+        );
+    }
+
+    #[test]
+    fn long_pauses_break_paragraphs_without_speakers() {
+        let segments = vec![
+            seg(0.0, 1.0, "One.", None),
+            seg(2.5, 3.0, "Two.", None),
+            seg(6.0, 7.0, "Three.", None),
+        ];
+        let out = render(&segments, &ExportOptions::default());
+        assert_eq!(out, "[00:00:00] One. Two.\n[00:00:06] Three.\n");
+    }
+}
@@ -0,0 +1,344 @@
+//! TTML writers for broadcast and streaming delivery: the W3C IMSC1 Text
+//! Profile and EBU-TT-D (EBU Tech 3380).
+//!
+//! Both emit one `<p>` per cue inside a single bottom-aligned region, with the
+//! cue's lines (as wrapped by `process_segments`) separated by `<br/>`. Speakers
+//! get their own style carrying their [`SpeakerColors`](super::SpeakerColors);
+//! right-to-left scripts set the region's writing mode and text direction.
+//!
+//! Timing is always in the media time base. IMSC1 writes frame-based clock
+//! times (`HH:MM:SS:FF`) with `ttp:frameRate` when
+//! [`ExportOptions::frame_rate`] is set. EBU-TT-D only allows
+//! `HH:MM:SS.mmm`, so there the frame rate snaps cue times to frame boundaries.
+
+use super::{ExportOptions, FrameRate, parse_hex_color, script_profile, speakers_in_order, xml_escape};
+use crate::formatting::ScriptProfile;
+use crate::types::Segment;
+
+/// Which TTML profile to write.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Profile {
+    Imsc1Text,
+    EbuTtD,
+}
+
+const IMSC1_TEXT_PROFILE: &str = "http://www.w3.org/ns/ttml/profile/imsc1/text";
+const EBU_TT_D_STANDARD: &str = "urn:ebu:tt:distribution:2014-01";
+
+/// Media-time clock value: `HH:MM:SS:FF` when `frames` is set (IMSC1 only),
+/// otherwise `HH:MM:SS.mmm`.
+fn clock_time(seconds: f64, frames: Option<FrameRate>) -> String {
+    let seconds = seconds.max(0.0);
+    match frames {
+        Some(rate) => {
+            // Frame clock times count whole seconds of media time plus
+            // frames at the effective rate within the second.
+            let mut whole = seconds.floor() as u64;
+            let mut frame = ((seconds - whole as f64) * rate.fps()).round() as u64;
+            if frame >= rate.nominal() as u64 {
+                whole += 1;
+                frame = 0;
+            }
+            format!(
+                "{:02}:{:02}:{:02}:{:02}",
+                whole / 3600,
+                (whole / 60) % 60,
+                whole % 60,
+                frame
+            )
+        }
+        None => {
+            let (h, m, s, ms) = super::hms(seconds);
+            format!("{h:02}:{m:02}:{s:02}.{ms:03}")
+        }
+    }
+}
+
+/// `#RRGGBB[AA]` to TTML's `#rrggbbaa`.
+fn ttml_color(hex: &str) -> Option<String> {
+    let (r, g, b, a) = parse_hex_color(hex)?;
+    Some(format!("#{r:02x}{g:02x}{b:02x}{a:02x}"))
+}
+
+/// Render a TTML document in `profile`.
+pub fn render(segments: &[Segment], profile: Profile, opts: &ExportOptions) -> String {
+    let rtl = script_profile(segments, opts) == ScriptProfile::RTL;
+    let lang = opts
+        .language
+        .as_deref()
+        .filter(|l| !l.is_empty() && *l != "auto")
+        .unwrap_or("");
+    let frame_clock = match profile {
+        Profile::Imsc1Text => opts.frame_rate,
+        Profile::EbuTtD => None,
+    };
+    let snap = |t: f64| match (profile, opts.frame_rate) {
+        (Profile::EbuTtD, Some(rate)) => rate.snap(t),
+        _ => t,
+    };
+
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str("<tt xmlns=\"http://www.w3.org/ns/ttml\"");
+    out.push_str(" xmlns:ttp=\"http://www.w3.org/ns/ttml#parameter\"");
+    out.push_str(" xmlns:tts=\"http://www.w3.org/ns/ttml#styling\"");
+    out.push_str(" xmlns:ttm=\"http://www.w3.org/ns/ttml#metadata\"");
+    if profile == Profile::EbuTtD {
+        out.push_str(" xmlns:ebuttm=\"urn:ebu:tt:metadata\"");
+    }
+    out.push_str(&format!(" xml:lang=\"{}\" ttp:timeBase=\"media\"", xml_escape(lang)));
+    if profile == Profile::Imsc1Text {
+        out.push_str(&format!(" ttp:profile=\"{IMSC1_TEXT_PROFILE}\""));
+        if let Some(rate) = frame_clock {
+            out.push_str(&format!(" ttp:frameRate=\"{}\"", rate.nominal()));
+            // Effective rate = frameRate * multiplier, e.g. 30 * 1000/1001.
+            let (num, den) = (rate.num, rate.nominal() * rate.den);
+            if num != den {
+                let g = super::gcd(num as u64, den as u64) as u32;
+                out.push_str(&format!(" ttp:frameRateMultiplier=\"{} {}\"", num / g, den / g));
+            }
+        }
+    }
+    out.push_str(" ttp:cellResolution=\"32 15\">\n");
+
+    out.push_str("  <head>\n");
+    if profile == Profile::EbuTtD {
+        out.push_str("    <metadata>\n      <ebuttm:documentMetadata>\n");
+        out.push_str(&format!(
+            "        <ebuttm:conformsToStandard>{EBU_TT_D_STANDARD}</ebuttm:conformsToStandard>\n"
+        ));
+        out.push_str("      </ebuttm:documentMetadata>\n    </metadata>\n");
+    }
+
+    out.push_str("    <styling>\n");
+    out.push_str(&format!(
+        "      <style xml:id=\"paragraph\" tts:textAlign=\"center\" tts:fontFamily=\"proportionalSansSerif\" tts:fontSize=\"100%\" tts:lineHeight=\"normal\"{}/>\n",
+        if rtl { " tts:direction=\"rtl\" tts:unicodeBidi=\"embed\"" } else { "" }
+    ));
+    out.push_str("      <style xml:id=\"text\" tts:color=\"#ffffffff\" tts:backgroundColor=\"#000000c2\"/>\n");
+    let speakers = speakers_in_order(segments);
+    for (i, id) in speakers.iter().enumerate() {
+        let colors = opts.speaker_colors.get(*id);
+        let fill = colors.and_then(|c| c.fill.as_deref()).and_then(ttml_color);
+        let back = colors.and_then(|c| c.border.as_deref()).and_then(ttml_color);
+        let outline = match profile {
+            // EBU-TT-D has no text outline.
+            Profile::Imsc1Text => colors.and_then(|c| c.outline.as_deref()).and_then(ttml_color),
+            Profile::EbuTtD => None,
+        };
+        out.push_str(&format!(
+            "      <style xml:id=\"speaker{}\" tts:color=\"{}\" tts:backgroundColor=\"{}\"{}/>\n",
+            i + 1,
+            fill.as_deref().unwrap_or("#ffffffff"),
+            back.as_deref().unwrap_or("#000000c2"),
+            outline.map(|c| format!(" tts:textOutline=\"{c} 5%\"")).unwrap_or_default()
+        ));
+    }
+    out.push_str("    </styling>\n");
+
+    out.push_str("    <layout>\n");
+    out.push_str(&format!(
+        "      <region xml:id=\"bottom\" tts:origin=\"10% 10%\" tts:extent=\"80% 80%\" tts:displayAlign=\"after\" tts:writingMode=\"{}\"/>\n",
+        if rtl { "rltb" } else { "lrtb" }
+    ));
+    out.push_str("    </layout>\n");
+    out.push_str("  </head>\n");
+
+    out.push_str("  <body>\n    <div>\n");
+    let mut cue_no = 0;
+    for seg in segments {
+        let lines: Vec<&str> = seg.text.lines().map(str::trim).filter(|l| !l.is_empty()).collect();
+        if lines.is_empty() {
+            continue;
+        }
+        cue_no += 1;
+        let begin = snap(seg.start);
+        let end = snap(seg.end.max(seg.start)).max(begin);
+        let span_style = seg
+            .speaker_id
+            .as_deref()
+            .and_then(|id| speakers.iter().position(|s| *s == id))
+            .map(|i| format!("speaker{}", i + 1))
+            .unwrap_or_else(|| "text".to_string());
+        let body = lines
+            .iter()
+            .map(|line| format!("<span style=\"{span_style}\">{}</span>", xml_escape(line)))
+            .collect::<Vec<_>>()
+            .join("<br/>");
+        out.push_str(&format!(
+            "      <p xml:id=\"sub{cue_no}\" begin=\"{}\" end=\"{}\" region=\"bottom\" style=\"paragraph\">{body}</p>\n",
+            clock_time(begin, frame_clock),
+            clock_time(end, frame_clock),
+        ));
+    }
+    out.push_str("    </div>\n  </body>\n</tt>\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::SpeakerColors;
+    use crate::formats::tests::{Element, parse_xml, seg};
+    use std::collections::HashSet;
+
+    /// Structural checks from the profile specifications: allowed elements, the
+    /// required `ttp:` parameters, style/region references that resolve to the
+    /// right kind of element, media time expressions and non-decreasing cue
+    /// times. This is not a validation against the profiles' XML schemas.
+    fn validate(doc: &str, profile: Profile) -> Element {
+        let root = parse_xml(doc);
+        assert_eq!(root.name, "tt");
+        assert_eq!(root.attr("xmlns"), Some("http://www.w3.org/ns/ttml"));
+        assert_eq!(root.attr("ttp:timeBase"), Some("media"));
+        assert!(root.attr("xml:lang").is_some(), "xml:lang is mandatory");
+        let cells: Vec<u32> = root
+            .attr("ttp:cellResolution")
+            .expect("ttp:cellResolution")
+            .split(' ')
+            .map(|n| n.parse().unwrap())
+            .collect();
+        assert!(cells.len() == 2 && cells.iter().all(|&n| n > 0), "bad cellResolution {cells:?}");
+
+        let mut all = Vec::new();
+        root.walk(&mut all);
+        let allowed: &[&str] = match profile {
+            Profile::EbuTtD => &[
+                "tt", "head", "metadata", "ebuttm:documentMetadata", "ebuttm:conformsToStandard",
+                "styling", "style", "layout", "region", "body", "div", "p", "span", "br",
+            ],
+            Profile::Imsc1Text => &[
+                "tt", "head", "styling", "style", "layout", "region", "body", "div", "p", "span", "br",
+            ],
+        };
+        for el in &all {
+            assert!(allowed.contains(&el.name.as_str()), "<{}> not allowed", el.name);
+        }
+        if profile == Profile::EbuTtD {
+            assert!(root.attr("ttp:frameRate").is_none(), "EBU-TT-D has no frame-based times");
+            assert!(all.iter().any(|e| e.name == "ebuttm:conformsToStandard"), "conformsToStandard is mandatory");
+        }
+
+        let mut ids = HashSet::new();
+        for id in all.iter().filter_map(|e| e.attr("xml:id")) {
+            assert!(ids.insert(id), "duplicate xml:id {id}");
+        }
+        let ids_of = |name: &str| -> HashSet<&str> {
+            all.iter().filter(|e| e.name == name).filter_map(|e| e.attr("xml:id")).collect()
+        };
+        let (styles, regions) = (ids_of("style"), ids_of("region"));
+        for el in &all {
+            for r in el.attr("style").into_iter().flat_map(str::split_whitespace) {
+                assert!(styles.contains(r), "style reference {r} is not a <style>");
+            }
+            if let Some(r) = el.attr("region") {
+                assert!(regions.contains(r), "region reference {r} is not a <region>");
+            }
+        }
+
+        let frame_rate = root.attr("ttp:frameRate").map(|r| r.parse::<u64>().unwrap());
+        let time = |t: &str| -> f64 {
+            let parts: Vec<&str> = t.split([':', '.']).collect();
+            assert!(parts.len() == 4 && parts.iter().all(|p| p.bytes().all(|b| b.is_ascii_digit())), "{t}");
+            let n = |i: usize| parts[i].parse::<u64>().unwrap();
+            assert!(parts[0].len() >= 2 && parts[1].len() == 2 && parts[2].len() == 2, "{t}");
+            assert!(n(1) < 60 && n(2) < 60, "{t}");
+            let clock = (n(0) * 3600 + n(1) * 60 + n(2)) as f64;
+            match frame_rate {
+                Some(fr) if !t.contains('.') => {
+                    assert!(n(3) < fr, "frame out of range in {t}");
+                    clock + n(3) as f64 / fr as f64
+                }
+                _ => {
+                    // EBU-TT-D allows only this form: hh:mm:ss.fff.
+                    assert!(t.as_bytes()[8] == b'.' && parts[3].len() == 3, "{t}");
+                    clock + n(3) as f64 / 1000.0
+                }
+            }
+        };
+        let mut last = 0.0;
+        for p in all.iter().filter(|e| e.name == "p") {
+            let (begin, end) = (time(p.attr("begin").unwrap()), time(p.attr("end").unwrap()));
+            assert!(begin >= last && end >= begin, "cue times out of order");
+            last = begin;
+            if profile == Profile::EbuTtD {
+                // EBU-TT-D: text content only inside spans.
+                assert!(p.children.iter().all(|c| c.name == "span" || c.name == "br"));
+            }
+        }
+        root
+    }
+
+    fn cues() -> Vec<Segment> {
+        vec![
+            seg(0.5, 2.0, "Fish & chips <3\nfor \"two\"", Some("1")),
+            seg(2.0, 3.25, "Thanks.", Some("2")),
+            seg(4.0, 5.0, "Narration.", None),
+        ]
+    }
+
+    #[test]
+    fn imsc1_document_is_valid() {
+        let mut opts = ExportOptions { language: Some("en".into()), ..Default::default() };
+        opts.speaker_colors.insert(
+            "1".into(),
+            SpeakerColors { fill: Some("#ffff00".into()), outline: Some("#000".into()), border: None },
+        );
+        let doc = render(&cues(), Profile::Imsc1Text, &opts);
+        let root = validate(&doc, Profile::Imsc1Text);
+        assert_eq!(root.attr("ttp:profile"), Some(IMSC1_TEXT_PROFILE));
+        assert!(doc.contains("<style xml:id=\"speaker1\" tts:color=\"#ffff00ff\" tts:backgroundColor=\"#000000c2\" tts:textOutline=\"#000000ff 5%\"/>"));
+        assert!(doc.contains(
+            "<p xml:id=\"sub1\" begin=\"00:00:00.500\" end=\"00:00:02.000\" region=\"bottom\" style=\"paragraph\">\
+             <span style=\"speaker1\">Fish &amp; chips &lt;3</span><br/><span style=\"speaker1\">for &quot;two&quot;</span></p>"
+        ));
+        assert!(doc.contains("<span style=\"text\">Narration.</span>"));
+    }
+
+    #[test]
+    fn imsc1_uses_frame_clock_times_with_a_frame_rate() {
+        let opts = ExportOptions { frame_rate: FrameRate::parse("29.97"), ..Default::default() };
+        let doc = render(&cues(), Profile::Imsc1Text, &opts);
+        let root = validate(&doc, Profile::Imsc1Text);
+        assert_eq!(root.attr("ttp:frameRate"), Some("30"));
+        assert_eq!(root.attr("ttp:frameRateMultiplier"), Some("1000 1001"));
+        assert!(doc.contains("begin=\"00:00:00:15\" end=\"00:00:02:00\""));
+    }
+
+    #[test]
+    fn ebu_tt_d_document_is_valid_and_frame_snapped() {
+        let opts = ExportOptions { frame_rate: FrameRate::parse("25"), ..Default::default() };
+        let doc = render(&[seg(1.01, 2.03, "Hi", None)], Profile::EbuTtD, &opts);
+        let root = validate(&doc, Profile::EbuTtD);
+        assert!(root.attr("ttp:frameRate").is_none());
+        assert!(doc.contains(&format!("<ebuttm:conformsToStandard>{EBU_TT_D_STANDARD}</ebuttm:conformsToStandard>")));
+        assert!(doc.contains("begin=\"00:00:01.000\" end=\"00:00:02.040\""));
+        assert!(!doc.contains("textOutline"));
+    }
+
+    #[test]
+    #[should_panic(expected = "is not a <region>")]
+    fn validation_rejects_references_to_the_wrong_element() {
+        let doc = render(&cues(), Profile::Imsc1Text, &ExportOptions::default());
+        validate(&doc.replace("region=\"bottom\"", "region=\"paragraph\""), Profile::Imsc1Text);
+    }
+
+    #[test]
+    #[should_panic(expected = "00:00:01:00")]
+    fn validation_rejects_frame_times_in_ebu_tt_d() {
+        let doc = render(&[seg(1.0, 2.0, "Hi", None)], Profile::EbuTtD, &ExportOptions::default());
+        validate(&doc.replace("00:00:01.000", "00:00:01:00"), Profile::EbuTtD);
+    }
+
+    #[test]
+    fn rtl_languages_set_direction_and_writing_mode() {
+        let opts = ExportOptions { language: Some("ar".into()), ..Default::default() };
+        let doc = render(&[seg(0.0, 1.0, "مرحبا", None)], Profile::EbuTtD, &opts);
+        validate(&doc, Profile::EbuTtD);
+        assert!(doc.contains("tts:writingMode=\"rltb\""));
+        assert!(doc.contains("tts:direction=\"rtl\""));
+
+        // Without a declared language the script is detected from the text.
+        let doc = render(&[seg(0.0, 1.0, "שלום עולם", None)], Profile::Imsc1Text, &ExportOptions::default());
+        assert!(doc.contains("tts:writingMode=\"rltb\""));
+    }
+}
@@ -0,0 +1,274 @@
+//! WebVTT (`.vtt`) writer and parser.
+//!
+//! Diarized cues are wrapped in `<v Name>` voice spans, and speakers with
+//! [`SpeakerColors`](super::SpeakerColors) get a `::cue(v[voice=...])` rule in
+//! a `STYLE` block so web players colour them. With [`ExportOptions::karaoke`]
+//! each word after the first carries an inline `<HH:MM:SS.mmm>` timestamp for
+//! word highlighting.
+//!
+//! Cues sit in a bottom-anchored `REGION`; a cue that starts while the previous
+//! one is still showing is positioned at the top of the frame instead, so
+//! overlapping speech stays readable.
+
+use super::{
+    CueBuilder, ExportOptions, ParsedSubtitles, hms, parse_hex_color, parse_timing_line,
+    speakers_in_order, split_speaker_prefix, strip_tags, words_by_line,
+};
+use crate::types::{Segment, WordTimestamp};
+use eyre::{Result, bail};
+
+/// `HH:MM:SS.mmm` timestamp.
+pub fn timestamp(seconds: f64) -> String {
+    let (h, m, s, ms) = hms(seconds);
+    format!("{h:02}:{m:02}:{s:02}.{ms:03}")
+}
+
+/// Escape cue text: `&`, `<` and `>` (which also keeps `-->` out of cue
+/// payloads).
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+/// `#RRGGBB[AA]` to a CSS `rgba()` colour.
+fn css_color(hex: &str) -> Option<String> {
+    let (r, g, b, a) = parse_hex_color(hex)?;
+    let alpha = (a as f64 / 255.0 * 100.0).round() / 100.0;
+    Some(format!("rgba({r}, {g}, {b}, {alpha})"))
+}
+
+/// `STYLE` block with one `::cue(v[voice])` rule per coloured speaker, or
+/// `None` when no speaker has colours.
+fn style_block(segments: &[Segment], opts: &ExportOptions) -> Option<String> {
+    let mut rules = String::new();
+    for id in speakers_in_order(segments) {
+        let Some(colors) = opts.speaker_colors.get(id) else {
+            continue;
+        };
+        let mut decls = Vec::new();
+        if let Some(c) = colors.fill.as_deref().and_then(css_color) {
+            decls.push(format!("color: {c};"));
+        }
+        if let Some(c) = colors.border.as_deref().and_then(css_color) {
+            decls.push(format!("background-color: {c};"));
+        }
+        if let Some(c) = colors.outline.as_deref().and_then(css_color) {
+            decls.push(format!("text-shadow: 0 0 2px {c}, 0 0 2px {c};"));
+        }
+        if decls.is_empty() {
+            continue;
+        }
+        let voice = opts.speaker_name(id).replace('\\', "\\\\").replace('"', "\\\"");
+        rules.push_str(&format!("::cue(v[voice=\"{voice}\"]) {{ {} }}\n", decls.join(" ")));
+    }
+    (!rules.is_empty()).then(|| format!("STYLE\n{rules}"))
+}
+
+/// Cue payload with an inline timestamp before every word that starts after
+/// the previous one, inside the cue.
+fn karaoke_text(seg: &Segment, lines: &[Vec<&WordTimestamp>]) -> String {
+    let end = seg.end.max(seg.start);
+    let mut last = timestamp(seg.start);
+    let mut out = Vec::new();
+    for line in lines {
+        let mut text = String::new();
+        for (i, word) in line.iter().enumerate() {
+            let raw = if i == 0 { word.text.trim_start() } else { word.text.as_str() };
+            let trimmed = raw.trim_start();
+            text.push_str(&raw[..raw.len() - trimmed.len()]);
+            let stamp = timestamp(word.start);
+            if word.start > seg.start && word.start < end && stamp > last {
+                text.push_str(&format!("<{stamp}>"));
+                last = stamp;
+            }
+            text.push_str(&escape(trimmed));
+        }
+        out.push(text);
+    }
+    out.join("\n")
+}
+
+/// `WEBVTT` header, optional `STYLE` block, the caption `REGION`, then one cue
+/// per segment with its speaker in a voice span.
+pub fn render(segments: &[Segment], opts: &ExportOptions) -> String {
+    let mut out = String::from("WEBVTT\n\n");
+    if let Some(style) = style_block(segments, opts) {
+        out.push_str(&style);
+        out.push('\n');
+    }
+    let lines = segments
+        .iter()
+        .map(|s| s.text.lines().filter(|l| !l.trim().is_empty()).count())
+        .max()
+        .unwrap_or(0)
+        .max(2);
+    out.push_str(&format!(
+        "REGION\nid:bottom width:80% lines:{lines} regionanchor:50%,100% viewportanchor:50%,90%\n\n"
+    ));
+
+    let mut shown_until = f64::NEG_INFINITY;
+    for seg in segments {
+        let end = seg.end.max(seg.start);
+        let text = seg.text.lines().map(str::trim).filter(|l| !l.is_empty()).collect::<Vec<_>>().join("\n");
+        if text.is_empty() {
+            continue;
+        }
+        let settings = if seg.start < shown_until {
+            "line:10% position:50% align:center"
+        } else {
+            "region:bottom align:center"
+        };
+        shown_until = shown_until.max(end);
+        let payload = match opts.karaoke.then(|| words_by_line(seg)).flatten() {
+            Some(lines) => karaoke_text(seg, &lines),
+            None => escape(&text),
+        };
+        let payload = match seg.speaker_id.as_deref() {
+            Some(id) => format!("<v {}>{payload}</v>", escape(&opts.speaker_name(id))),
+            None => payload,
+        };
+        out.push_str(&format!(
+            "{} --> {} {settings}\n{payload}\n\n",
+            timestamp(seg.start),
+            timestamp(end),
+        ));
+    }
+    out
+}
+
+/// Parse WebVTT cues. `NOTE`, `STYLE` and `REGION` blocks, cue identifiers
+/// and cue settings are skipped. The first `<v Name>` voice span names the
+/// speaker (falling back to a `Speaker N: ` prefix); all other markup and
+/// inline timestamps are stripped.
+pub fn parse(content: &str) -> Result<ParsedSubtitles> {
+    if !content.trim_start().starts_with("WEBVTT") {
+        bail!("missing WEBVTT header");
+    }
+    let mut cues = CueBuilder::default();
+    // Blocks are separated by blank lines; the first is the header.
+    for block in content.split("\n\n").skip(1) {
+        let mut lines = block.lines().filter(|l| !l.trim().is_empty()).peekable();
+        let Some(&first) = lines.peek() else {
+            continue;
+        };
+        if ["NOTE", "STYLE", "REGION"].iter().any(|kw| first.starts_with(kw)) {
+            continue;
+        }
+        if !first.contains("-->") {
+            // Cue identifier.
+            lines.next();
+        }
+        let Some((start, end)) = lines.next().and_then(parse_timing_line) else {
+            tracing::warn!("skipping malformed VTT block: {block}");
+            continue;
+        };
+        let raw: Vec<&str> = lines.collect();
+        let voice = raw.iter().find_map(|l| voice_name(l));
+        let text = raw.iter().map(|l| strip_tags(l)).collect::<Vec<_>>().join("\n");
+        match voice {
+            Some(name) => cues.push(start, end, &text, Some(&name)),
+            None => {
+                let (speaker, text) = split_speaker_prefix(&text);
+                cues.push(start, end, text, speaker);
+            }
+        }
+    }
+    Ok(cues.finish())
+}
+
+/// Speaker from the first `<v Name>` or `<v.class Name>` span in a line.
+fn voice_name(line: &str) -> Option<String> {
+    let start = line.find("<v")?;
+    let tag = &line[start + 2..];
+    let tag = &tag[..tag.find('>')?];
+    if !tag.starts_with([' ', '\t', '.']) {
+        return None;
+    }
+    let (_, name) = tag.split_once([' ', '\t'])?;
+    let name = strip_tags(name.trim());
+    (!name.is_empty()).then_some(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::SpeakerColors;
+    use crate::formats::tests::{seg, word};
+
+    #[test]
+    fn renders_header_and_cues() {
+        let mut opts = ExportOptions::default();
+        opts.speaker_names.insert("2".into(), "Bob".into());
+        let out = render(&[seg(61.25, 62.0, "Hi.", Some("2")), seg(63.0, 64.0, "Narration.", None)], &opts);
+        assert_eq!(
+            out,
+            "WEBVTT\n\n\
+             REGION\nid:bottom width:80% lines:2 regionanchor:50%,100% viewportanchor:50%,90%\n\n\
+             00:01:01.250 --> 00:01:02.000 region:bottom align:center\n<v Bob>Hi.</v>\n\n\
+             00:01:03.000 --> 00:01:04.000 region:bottom align:center\nNarration.\n\n"
+        );
+    }
+
+    #[test]
+    fn escapes_markup_and_arrows() {
+        let mut opts = ExportOptions::default();
+        opts.speaker_names.insert("1".into(), "A<B>".into());
+        let out = render(&[seg(0.0, 1.0, "x --> y & <3", Some("1"))], &opts);
+        assert!(out.contains("\n<v A&lt;B&gt;>x --&gt; y &amp; &lt;3</v>\n"));
+        let parsed = parse(&out).unwrap();
+        assert_eq!(parsed.segments[0].text, "x --> y & <3");
+        assert_eq!(parsed.speaker_names["1"], "A<B>");
+    }
+
+    #[test]
+    fn karaoke_adds_inline_word_timestamps() {
+        let mut cue = seg(1.0, 3.0, "Hello there,\ngeneral Kenobi.", Some("1"));
+        cue.words = Some(vec![
+            word("Hello", 1.0, 1.4),
+            word(" there,", 1.25, 1.8),
+            word(" general", 2.0, 2.4),
+            word(" Kenobi.", 2.5, 3.5),
+        ]);
+        let opts = ExportOptions { karaoke: true, ..Default::default() };
+        let out = render(&[cue.clone()], &opts);
+        assert!(out.contains(
+            "<v Speaker 1>Hello <00:00:01.250>there,\n<00:00:02.000>general <00:00:02.500>Kenobi.</v>"
+        ));
+        let parsed = parse(&out).unwrap();
+        assert_eq!(parsed.segments[0].text, cue.text);
+    }
+
+    #[test]
+    fn speaker_colours_and_overlaps_use_style_and_settings() {
+        let mut opts = ExportOptions::default();
+        opts.speaker_names.insert("1".into(), "Ana \"A\"".into());
+        opts.speaker_colors.insert(
+            "1".into(),
+            SpeakerColors { fill: Some("#FF0000".into()), border: Some("#00000080".into()), outline: None },
+        );
+        let out = render(
+            &[seg(0.0, 2.0, "One\ntwo\nthree", Some("1")), seg(1.0, 2.5, "Overlap.", Some("2"))],
+            &opts,
+        );
+        assert!(out.starts_with(
+            "WEBVTT\n\nSTYLE\n::cue(v[voice=\"Ana \\\"A\\\"\"]) { color: rgba(255, 0, 0, 1); background-color: rgba(0, 0, 0, 0.5); }\n\nREGION\n"
+        ));
+        assert!(out.contains("lines:3 "));
+        assert!(out.contains("00:00:01.000 --> 00:00:02.500 line:10% position:50% align:center\n"));
+    }
+
+    #[test]
+    fn parses_voice_spans_and_skips_metadata_blocks() {
+        let content = "WEBVTT - demo\n\nNOTE written by hand\n\nSTYLE\n::cue { color: red }\n\n\
+                       intro\n00:01.000 --> 00:02.000 line:0\n<v.loud Ana María>Hola <00:01.500><c>mundo</c></v>\n\n\
+                       00:00:03.000 --> 00:00:04.000\nSpeaker 4: fish &amp; chips\n";
+        let parsed = parse(content).unwrap();
+        assert_eq!(parsed.segments.len(), 2);
+        assert_eq!(parsed.segments[0].text, "Hola mundo");
+        assert_eq!((parsed.segments[0].start, parsed.segments[0].end), (1.0, 2.0));
+        let ana = parsed.segments[0].speaker_id.as_deref().unwrap();
+        assert_eq!(parsed.speaker_names[ana], "Ana María");
+        assert_eq!(parsed.segments[1].text, "fish & chips");
+        assert_eq!(parsed.segments[1].speaker_id.as_deref(), Some("4"));
+        assert!(parse("1\n00:00:00,000 --> 00:00:01,000\nHi").is_err());
+    }
+}
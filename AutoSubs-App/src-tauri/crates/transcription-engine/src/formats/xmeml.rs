@@ -0,0 +1,147 @@
+//! Final Cut Pro 7 XML interchange (`xmeml`) writer, for Premiere Pro.
+//!
+//! Produces a single sequence with one video track holding every cue as a
+//! Text generator clip, named after the cue's speaker and coloured with their
+//! [`SpeakerColors`](super::SpeakerColors) fill. Premiere imports these as
+//! title clips on their own track, no AutoSubs extension required.
+//!
+//! Clip positions are frames from the sequence start at
+//! [`ExportOptions::sequence_rate`]; the sequence timecode starts at
+//! [`ExportOptions::start_frame`].
+
+use super::{ExportOptions, FrameRate, parse_hex_color, track_cues, xml_escape};
+use crate::types::Segment;
+
+/// `<rate>` element for `rate`, indented by `indent`.
+fn rate_xml(rate: FrameRate, indent: &str) -> String {
+    format!(
+        "{indent}<rate>\n{indent}  <timebase>{}</timebase>\n{indent}  <ntsc>{}</ntsc>\n{indent}</rate>\n",
+        rate.nominal(),
+        if rate.is_ntsc() { "TRUE" } else { "FALSE" }
+    )
+}
+
+/// Render an xmeml document with one Text generator per cue.
+pub fn render(segments: &[Segment], opts: &ExportOptions) -> String {
+    let rate = opts.sequence_rate();
+    let cues = track_cues(segments, rate);
+    let duration = cues.last().map_or(0, |&(_, end, _)| end);
+
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<!DOCTYPE xmeml>\n");
+    out.push_str("<xmeml version=\"4\">\n");
+    out.push_str("  <sequence id=\"sequence-1\">\n");
+    out.push_str("    <name>AutoSubs Captions</name>\n");
+    out.push_str(&format!("    <duration>{duration}</duration>\n"));
+    out.push_str(&rate_xml(rate, "    "));
+    out.push_str("    <timecode>\n");
+    out.push_str(&rate_xml(rate, "      "));
+    out.push_str(&format!("      <string>{}</string>\n", rate.timecode(opts.start_frame)));
+    out.push_str(&format!("      <frame>{}</frame>\n", opts.start_frame));
+    out.push_str(&format!(
+        "      <displayformat>{}</displayformat>\n",
+        if rate.is_drop_frame() { "DF" } else { "NDF" }
+    ));
+    out.push_str("    </timecode>\n");
+    out.push_str("    <media>\n      <video>\n");
+    out.push_str("        <format>\n          <samplecharacteristics>\n");
+    out.push_str(&rate_xml(rate, "            "));
+    out.push_str("            <width>1920</width>\n            <height>1080</height>\n");
+    out.push_str("            <pixelaspectratio>square</pixelaspectratio>\n");
+    out.push_str("          </samplecharacteristics>\n        </format>\n");
+    out.push_str("        <track>\n");
+    for (i, (start, end, seg)) in cues.into_iter().enumerate() {
+        let length = end - start;
+        let text = seg.text.lines().map(str::trim).filter(|l| !l.is_empty()).collect::<Vec<_>>();
+        let name = match seg.speaker_id.as_deref() {
+            Some(id) => opts.speaker_name(id),
+            None => text.join(" "),
+        };
+        let (red, green, blue, alpha) = seg
+            .speaker_id
+            .as_deref()
+            .and_then(|id| opts.speaker_colors.get(id))
+            .and_then(|c| c.fill.as_deref())
+            .and_then(parse_hex_color)
+            .unwrap_or((255, 255, 255, 255));
+
+        out.push_str(&format!("          <generatoritem id=\"caption-{}\">\n", i + 1));
+        out.push_str(&format!("            <name>{}</name>\n", xml_escape(&name)));
+        out.push_str("            <enabled>TRUE</enabled>\n");
+        out.push_str(&format!("            <duration>{length}</duration>\n"));
+        out.push_str(&rate_xml(rate, "            "));
+        out.push_str(&format!(
+            "            <start>{start}</start>\n            <end>{end}</end>\n            <in>0</in>\n            <out>{length}</out>\n"
+        ));
+        out.push_str("            <effect>\n");
+        out.push_str("              <name>Text</name>\n              <effectid>Text</effectid>\n");
+        out.push_str("              <effectcategory>Text</effectcategory>\n");
+        out.push_str("              <effecttype>generator</effecttype>\n              <mediatype>video</mediatype>\n");
+        out.push_str(&format!(
+            "              <parameter>\n                <parameterid>str</parameterid>\n                <name>Text</name>\n                <value>{}</value>\n              </parameter>\n",
+            xml_escape(&text.join("\n"))
+        ));
+        out.push_str("              <parameter>\n                <parameterid>fontsize</parameterid>\n                <name>Size</name>\n                <value>36</value>\n              </parameter>\n");
+        out.push_str("              <parameter>\n                <parameterid>fontalign</parameterid>\n                <name>Alignment</name>\n                <value>2</value>\n              </parameter>\n");
+        out.push_str(&format!(
+            "              <parameter>\n                <parameterid>fontcolor</parameterid>\n                <name>Font Color</name>\n                <value>\n                  <alpha>{alpha}</alpha>\n                  <red>{red}</red>\n                  <green>{green}</green>\n                  <blue>{blue}</blue>\n                </value>\n              </parameter>\n"
+        ));
+        out.push_str("              <parameter>\n                <parameterid>origin</parameterid>\n                <name>Origin</name>\n                <value>\n                  <horiz>0</horiz>\n                  <vert>0.4</vert>\n                </value>\n              </parameter>\n");
+        out.push_str("            </effect>\n");
+        out.push_str("          </generatoritem>\n");
+    }
+    out.push_str("        </track>\n");
+    out.push_str("      </video>\n    </media>\n  </sequence>\n</xmeml>\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::SpeakerColors;
+    use crate::formats::tests::{Element, parse_xml, seg};
+
+    fn child<'a>(el: &'a Element, name: &str) -> &'a Element {
+        el.children.iter().find(|c| c.name == name).unwrap_or_else(|| panic!("no <{name}>"))
+    }
+
+    #[test]
+    fn clips_are_placed_in_sequence_frames() {
+        let segments = vec![
+            seg(1.0, 2.5, "Fish & chips\nfor two", Some("1")),
+            seg(2.0, 3.0, "Overlapping.", None),
+        ];
+        let mut opts = ExportOptions { frame_rate: FrameRate::parse("29.97"), ..Default::default() };
+        opts.start_frame = 107_892;
+        opts.speaker_names.insert("1".into(), "Alice".into());
+        opts.speaker_colors.insert(
+            "1".into(),
+            SpeakerColors { fill: Some("#FF000080".into()), ..Default::default() },
+        );
+        let doc = render(&segments, &opts);
+        let root = parse_xml(&doc);
+        assert_eq!(root.name, "xmeml");
+
+        let sequence = child(&root, "sequence");
+        let track = child(child(child(sequence, "media"), "video"), "track");
+        assert_eq!(track.children.len(), 2);
+        assert!(track.children.iter().all(|c| c.name == "generatoritem"));
+        assert!(doc.contains("<string>01:00:00;00</string>\n      <frame>107892</frame>\n      <displayformat>DF</displayformat>"));
+        assert!(doc.contains("<timebase>30</timebase>\n      <ntsc>TRUE</ntsc>"));
+        // 1.0s..2.0s at 29.97, trimmed to the next clip's start.
+        assert!(doc.contains("<name>Alice</name>"));
+        assert!(doc.contains("<start>30</start>\n            <end>60</end>"));
+        assert!(doc.contains("<value>Fish &amp; chips\nfor two</value>"));
+        assert!(doc.contains("<alpha>128</alpha>\n                  <red>255</red>"));
+        assert!(doc.contains("<name>Overlapping.</name>"));
+    }
+
+    #[test]
+    fn default_sequence_is_25_fps_non_drop() {
+        let doc = render(&[seg(0.0, 1.0, "Hi", None)], &ExportOptions::default());
+        parse_xml(&doc);
+        assert!(doc.contains("<timebase>25</timebase>"));
+        assert!(doc.contains("<string>00:00:00:00</string>"));
+        assert!(doc.contains("<displayformat>NDF</displayformat>"));
+        assert!(doc.contains("<end>25</end>"));
+    }
+}
@@ -176,6 +176,50 @@ impl PostProcessConfig {
     pub fn se_asian_no_space() -> Self { Self::with_profile(ScriptProfile::SEAsianNoSpace) }
     pub fn rtl() -> Self { Self::with_profile(ScriptProfile::RTL) }
     pub fn indic() -> Self { Self::with_profile(ScriptProfile::Indic) }
+
+    /// CEA-608 (SCC) captions: 32 columns, two rows per pop-on caption (the
+    /// format allows four, but two is the broadcast norm for readability).
+    pub fn cea608() -> Self {
+        Self {
+            max_chars_per_line: crate::formats::scc::COLUMNS,
+            max_lines: 2,
+            ..Self::latin()
+        }
+    }
+
+    /// Lyrics (LRC): one sung line per cue. Lines break at pauses and segment
+    /// ends, with the line length cap relaxed so it only splits runs too long
+    /// for a lyrics display; cues keep their sung timing instead of being
+    /// stretched to a minimum duration.
+    pub fn apply_lyrics_layout(&mut self) {
+        self.max_chars_per_line *= 2;
+        self.max_lines = 1;
+        self.min_sub_dur = 0.0;
+        self.max_sub_dur = 12.0;
+        self.single_word = false;
+    }
+
+    /// Latin lyrics preset; see [`Self::apply_lyrics_layout`].
+    pub fn lyrics() -> Self {
+        let mut cfg = Self::latin();
+        cfg.apply_lyrics_layout();
+        cfg
+    }
+
+    /// EBU STL teletext: two double-height rows that fit the 40-column
+    /// teletext row after its control codes. Narrower script profiles keep
+    /// their own line length.
+    pub fn apply_teletext_layout(&mut self) {
+        self.max_chars_per_line = self.max_chars_per_line.min(crate::formats::stl::ROW_CHARS);
+        self.max_lines = 2;
+    }
+
+    /// Latin teletext preset; see [`Self::apply_teletext_layout`].
+    pub fn teletext() -> Self {
+        let mut cfg = Self::latin();
+        cfg.apply_teletext_layout();
+        cfg
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -922,6 +966,7 @@ fn segment_from_lines(lines: &[Vec<Tok>], cfg: &PostProcessConfig) -> Segment {
         text,
         words: Some(words),
         speaker_id,
+        stats: None,
     }
 }
 
@@ -965,6 +1010,7 @@ mod tests {
                 WordTimestamp { text: " to".into(), start: 0.95, end: 1.05, probability: None },
                 WordTimestamp { text: ".".into(), start: 1.05, end: 1.10, probability: None },
             ]),
+            stats: None,
         };
         let cues = process_segments(&[seg], &cfg);
         assert!(!cues.is_empty());
@@ -1039,6 +1085,7 @@ mod tests {
                 WordTimestamp { text: " survival".into(),     start: 13.6, end: 14.2, probability: None },
                 WordTimestamp { text: ",".into(),             start: 14.2, end: 14.2, probability: None },
             ]),
+            stats: None,
         };
 
         let cues = process_segments(&[seg], &cfg);
@@ -1085,6 +1132,7 @@ mod tests {
                 WordTimestamp { text: "Hello".into(),  start: 0.0, end: 0.5, probability: None },
                 WordTimestamp { text: " world".into(), start: 0.5, end: 1.0, probability: None },
             ]),
+            stats: None,
         };
         let seg2 = Segment {
             start: 1.5,
@@ -1095,6 +1143,7 @@ mod tests {
                 WordTimestamp { text: "Good".into(),    start: 1.5, end: 2.0, probability: None },
                 WordTimestamp { text: " morning".into(),start: 2.0, end: 2.5, probability: None },
             ]),
+            stats: None,
         };
 
         let cues = process_segments(&[seg, seg2], &cfg);
@@ -1129,6 +1178,7 @@ mod tests {
                 WordTimestamp { text: "ものです".into(),         start: 6.3, end: 7.0, probability: None },
                 WordTimestamp { text: "。".into(),              start: 7.0, end: 7.1, probability: None },
             ]),
+            stats: None,
         };
 
         let cues = process_segments(&[seg], &cfg);
@@ -1168,6 +1218,7 @@ mod tests {
                 WordTimestamp { text: " Goodbye".into(),start: 0.5, end: 1.0, probability: None },
                 WordTimestamp { text: ".".into(),       start: 1.0, end: 1.1, probability: None },
             ]),
+            stats: None,
         };
 
         let cues = process_segments(&[seg], &cfg);
@@ -1175,6 +1226,56 @@ mod tests {
             cues.len(), cues.iter().map(|c| &c.text).collect::<Vec<_>>());
     }
 
+    #[test]
+    fn cea608_preset_fits_the_caption_grid() {
+        let text = "Closed captions on line twenty one have thirty two columns and the preset keeps every cue inside them";
+        let words: Vec<WordTimestamp> = text
+            .split(' ')
+            .enumerate()
+            .map(|(i, w)| WordTimestamp {
+                text: if i == 0 { w.to_string() } else { format!(" {w}") },
+                start: i as f64 * 0.3,
+                end: i as f64 * 0.3 + 0.25,
+                probability: None,
+            })
+            .collect();
+        let seg = Segment { start: 0.0, end: 6.0, text: text.into(), speaker_id: None, words: Some(words), stats: None };
+
+        let cues = process_segments(&[seg], &PostProcessConfig::cea608());
+        assert!(cues.len() > 1);
+        for cue in &cues {
+            let lines: Vec<&str> = cue.text.lines().collect();
+            assert!(lines.len() <= 2, "{:?}", cue.text);
+            assert!(lines.iter().all(|l| l.chars().count() <= 32), "{:?}", cue.text);
+        }
+    }
+
+    #[test]
+    fn lyrics_preset_breaks_at_sung_pauses_not_line_length() {
+        // Two sung lines separated by a breath; the first is longer than a
+        // subtitle line but still one lyric line.
+        let lines = [
+            "And the vision that was planted in my brain still remains",
+            "within the sound of silence",
+        ];
+        let mut words = Vec::new();
+        let mut t = 0.0;
+        for line in lines {
+            for (i, w) in line.split(' ').enumerate() {
+                let text = if i == 0 && words.is_empty() { w.to_string() } else { format!(" {w}") };
+                words.push(WordTimestamp { text, start: t, end: t + 0.3, probability: None });
+                t += 0.35;
+            }
+            t += 1.0;
+        }
+        let seg = Segment { start: 0.0, end: t, text: lines.join(" "), speaker_id: None, words: Some(words), stats: None };
+
+        let cues = process_segments(&[seg], &PostProcessConfig::lyrics());
+        let texts: Vec<&str> = cues.iter().map(|c| c.text.as_str()).collect();
+        assert_eq!(texts, lines);
+        assert!((cues[0].end - 3.8).abs() < 1e-9, "cue end stretched to {}", cues[0].end);
+    }
+
     #[test]
     fn min_sub_dur_extends_short_cues() {
         let mut cfg = PostProcessConfig::default();
@@ -1192,6 +1293,7 @@ mod tests {
                 WordTimestamp { text: " Welcome".into(), start: 2.0, end: 2.5, probability: None },
                 WordTimestamp { text: " back".into(),  start: 2.5, end: 3.0, probability: None },
             ]),
+            stats: None,
         };
 
         let cues = process_segments(&[seg], &cfg);
@@ -1238,6 +1340,7 @@ mod tests {
                 WordTimestamp { text: " multiple".into(), start: 5.5, end: 6.5, probability: None },
                 WordTimestamp { text: " parts".into(),    start: 6.5, end: 7.5, probability: None },
             ]),
+            stats: None,
         };
 
         let cues = process_segments(&[seg], &cfg);
@@ -1280,6 +1383,7 @@ mod tests {
                 WordTimestamp { text: " de".into(),             start: 4.2, end: 4.4, probability: None },
                 WordTimestamp { text: " l'humanité".into(),     start: 4.4, end: 5.5, probability: None },
             ]),
+            stats: None,
         };
 
         let cues = process_segments(&[seg], &cfg);
@@ -1336,6 +1440,7 @@ mod tests {
                 WordTimestamp { text: " world".into(), start: 1.2, end: 1.8, probability: None },
                 WordTimestamp { text: "!".into(),      start: 1.8, end: 1.9, probability: None },
             ]),
+            stats: None,
         }
     }
 
@@ -1450,6 +1555,7 @@ mod tests {
                 WordTimestamp { text: "،".into(),     start: 0.7, end: 0.8, probability: None },
                 WordTimestamp { text: " مرحبا".into(), start: 1.0, end: 1.5, probability: None },
             ]),
+            stats: None,
         };
 
         let cues = process_segments(&[seg], &cfg);
@@ -1479,6 +1585,7 @@ mod tests {
                 WordTimestamp { text: "World".into(), start: 0.45, end: 0.9, probability: None },
                 WordTimestamp { text: ".".into(),    start: 0.9, end: 1.0, probability: None },
             ]),
+            stats: None,
         };
 
         let cues = process_segments(&[seg], &cfg);
@@ -1502,6 +1609,7 @@ mod tests {
             words: Some(vec![WordTimestamp {
                 text: "Hello".into(), start: 0.0, end: 0.5, probability: None,
             }]),
+            stats: None,
         };
         let second = Segment {
             start: 0.5,
@@ -1513,6 +1621,7 @@ mod tests {
             words: Some(vec![WordTimestamp {
                 text: "World".into(), start: 0.5, end: 1.0, probability: None,
             }]),
+            stats: None,
         };
 
         let cues = process_segments(&[first, second], &cfg);
@@ -1536,6 +1645,7 @@ mod tests {
                 WordTimestamp { text: "Hello".into(), start: 0.0, end: 0.3, probability: None },
                 WordTimestamp { text: " again".into(), start: 2.0, end: 2.5, probability: None },
             ]),
+            stats: None,
         };
 
         let cues = process_segments(&[segment], &cfg);
@@ -1568,6 +1678,7 @@ mod tests {
                 WordTimestamp { text: "مرحبا؟".into(), start: 0.0, end: 0.7, probability: None },
                 WordTimestamp { text: " أهلا".into(), start: 0.8, end: 1.4, probability: None },
             ]),
+            stats: None,
         };
 
         let cues = process_segments(&[segment], &cfg);
@@ -1591,6 +1702,7 @@ mod tests {
                 WordTimestamp { text: " two".into(), start: 2.0, end: 4.0, probability: None },
                 WordTimestamp { text: " three".into(), start: 4.0, end: 6.0, probability: None },
             ]),
+            stats: None,
         };
 
         let cues = process_segments(&[segment], &cfg);
@@ -1610,6 +1722,7 @@ mod tests {
             text: "Fallback text still wraps correctly".into(),
             speaker_id: None,
             words: None,
+            stats: None,
         };
 
         let cues = process_segments(&[segment], &cfg);
@@ -1634,6 +1747,7 @@ mod tests {
             text: "Fallback timing".into(),
             words: None,
             speaker_id: None,
+            stats: None,
         };
 
         let cues = process_segments(&[segment], &cfg);
@@ -1661,6 +1775,7 @@ mod tests {
                 text: text.into(), start: 0.0, end: 2.0, probability: None,
             }]),
             speaker_id: None,
+            stats: None,
         };
 
         let cues = process_segments(&[segment], &cfg);
@@ -1685,6 +1800,7 @@ mod tests {
             text: text.into(),
             words: None,
             speaker_id: None,
+            stats: None,
         };
 
         let cues = process_segments(&[segment], &cfg);
@@ -1715,6 +1831,7 @@ mod tests {
                     text: " ends".into(), start: 1.0, end: 2.0, probability: None,
                 },
             ]),
+            stats: None,
         };
         let second = Segment {
             start: 1.5,
@@ -1724,6 +1841,7 @@ mod tests {
             words: Some(vec![WordTimestamp {
                 text: "Second".into(), start: 1.5, end: 3.0, probability: None,
             }]),
+            stats: None,
         };
 
         let cues = process_segments(&[first, second], &cfg);
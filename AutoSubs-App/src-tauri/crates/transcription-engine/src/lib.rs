@@ -2,9 +2,11 @@ pub mod align;
 pub mod audio;
 pub mod engine;
 pub mod engines;
+pub mod formats;
 pub mod manifest;
 pub mod model_manager;
 pub mod vad;
+pub mod stream;
 pub mod types;
 pub mod translate;
 pub mod translation_pipeline;
@@ -13,11 +15,15 @@ pub mod formatting;
 
 // Re-exports (crate users only need these)
 pub use engine::{Engine, EngineConfig, ContentFormatting};
+pub use audio::Samples;
+pub use engines::backend::{AsrBackend, AsrModel, Capabilities, ChunkSegment, NativeTranslation, RunOptions};
 pub use vad::get_segments;
-pub use types::{Callbacks, LabeledProgressFn, NewSegmentFn, SegmentStage, SpeakersIdentifiedFn, TranscribeOptions, Segment, WordTimestamp, ProgressType};
+pub use stream::{LiveSession, StreamOptions};
+pub use types::{Callbacks, LabeledProgressFn, NewSegmentFn, SegmentStage, SpeakersIdentifiedFn, TranscribeOptions, Segment, SegmentStats, WordTimestamp, ProgressType};
 pub use model_manager::ModelManager;
-pub use utils::{get_translate_languages, get_whisper_languages};
+pub use utils::{compression_ratio, get_translate_languages, get_whisper_languages};
 pub use formatting::{PostProcessConfig, process_segments, TextCase, TextDensity};
+pub use formats::{ExportOptions, FrameRate, ParsedSubtitles, SpeakerColors, SubtitleFormat};
 
 /// Install whisper.cpp logging hooks so output is routed through Rust's tracing system
 /// instead of raw stderr, allowing filters to suppress chatty internal logs.
@@ -14,7 +14,7 @@
 //! steps, so adding this module is a no-op at runtime.
 
 use once_cell::sync::Lazy;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 /// Raw manifest JSON, embedded at compile time.
 ///
@@ -74,7 +74,7 @@ pub struct AlignerModel {
     pub ui: Ui,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct ModelLicense {
     pub spdx: String,
     pub url: String,
@@ -100,6 +100,10 @@ pub struct ModelEntry {
     /// Display metadata consumed by the frontend. Optional for backend-only use.
     #[serde(default)]
     pub ui: Option<Ui>,
+    /// Licence of the model weights, shown by `autosubs models info`. Absent
+    /// means not yet recorded in the manifest, not unlicensed.
+    #[serde(default)]
+    pub license: Option<ModelLicense>,
 }
 
 impl ModelEntry {
@@ -112,7 +116,7 @@ impl ModelEntry {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
 #[serde(rename_all = "snake_case")]
 pub enum Engine {
     Whisper,
@@ -125,6 +129,23 @@ pub enum Engine {
     OmniAsr,
 }
 
+impl Engine {
+    /// The engine's name in `models.json`, which is also the key its backend
+    /// is registered under (see [`crate::engines::backend`]).
+    pub fn name(self) -> &'static str {
+        match self {
+            Engine::Whisper => "whisper",
+            Engine::Parakeet => "parakeet",
+            Engine::Moonshine => "moonshine",
+            Engine::Canary => "canary",
+            Engine::Cohere => "cohere",
+            Engine::Gigaam => "gigaam",
+            Engine::SenseVoice => "sense_voice",
+            Engine::OmniAsr => "omni_asr",
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum Quant {
@@ -243,7 +264,7 @@ pub struct Ui {
     pub language_support: LanguageSupport,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(tag = "kind", rename_all = "snake_case")]
 pub enum LanguageSupport {
     Multilingual,
@@ -277,6 +298,16 @@ pub fn get(id: &str) -> Option<&'static ModelEntry> {
     MANIFEST.get(id)
 }
 
+/// The source languages the manifest restricts `id` to, or `None` if it takes
+/// any language (or isn't in the manifest).
+pub fn languages(id: &str) -> Option<Vec<String>> {
+    match &MANIFEST.get(id)?.ui.as_ref()?.language_support {
+        LanguageSupport::Multilingual => None,
+        LanguageSupport::SingleLanguage { language } => Some(vec![language.clone()]),
+        LanguageSupport::Restricted { languages } => Some(languages.clone()),
+    }
+}
+
 /// The engine that loads a given model id, if known.
 pub fn engine_for(id: &str) -> Option<Engine> {
     MANIFEST.get(id).map(|m| m.engine)
@@ -441,30 +472,51 @@ mod tests {
     }
 
     #[test]
-    fn every_model_engine_has_a_wrapper() {
-        // Engines wired up in engine.rs. Adding a manifest row for an engine
-        // without a transcribe wrapper would fail transcription at runtime, so
-        // guard it here. Update this set when a new engine wrapper lands.
+    fn engine_names_match_the_manifest_spelling() {
+        for e in &MANIFEST.models {
+            assert_eq!(
+                serde_json::to_value(e.engine).unwrap(),
+                e.engine.name(),
+                "model '{}': Engine::name disagrees with models.json",
+                e.id
+            );
+        }
+    }
+
+    #[test]
+    fn every_model_engine_has_a_backend() {
+        // Adding a manifest row for an engine without a registered backend
+        // would fail transcription at runtime, so guard it here.
         for e in &MANIFEST.models {
             assert!(
-                matches!(
-                    e.engine,
-                    Engine::Whisper
-                        | Engine::Parakeet
-                        | Engine::Moonshine
-                        | Engine::SenseVoice
-                        | Engine::Canary
-                        | Engine::Cohere
-                        | Engine::Gigaam
-                        | Engine::OmniAsr
-                ),
-                "model '{}' uses engine {:?} which has no wrapper yet",
+                crate::engines::backend::get(e.engine.name()).is_some(),
+                "model '{}' uses engine {:?} which has no backend yet",
                 e.id,
                 e.engine
             );
         }
     }
 
+    #[test]
+    fn language_limits_follow_language_support() {
+        assert_eq!(languages("large-v3"), None);
+        assert_eq!(languages("moonshine-tiny-ar"), Some(vec!["ar".to_string()]));
+        assert_eq!(languages("gigaam-v3"), Some(vec!["ru".to_string()]));
+        assert_eq!(languages("not-a-model"), None);
+    }
+
+    #[test]
+    fn language_checked_backends_match_the_manifest() {
+        // The driver rejects other source languages up front for these, so
+        // their lists must agree with what the frontend offers.
+        for e in &MANIFEST.models {
+            if matches!(e.engine, Engine::Canary | Engine::Cohere | Engine::Gigaam | Engine::Moonshine) {
+                let backend = crate::engines::backend::get(e.engine.name()).unwrap();
+                assert_eq!(backend.capabilities(&e.id).languages, languages(&e.id), "{}", e.id);
+            }
+        }
+    }
+
     #[test]
     fn moonshine_entries_have_a_variant() {
         for e in &MANIFEST.models {
@@ -278,13 +278,7 @@ impl ModelManager {
                 let variant = entry.moonshine_variant.clone().unwrap_or_else(|| entry.id.clone());
                 Some(("moonshine", variant))
             }
-            MEngine::Canary => Some(("canary", entry.id.clone())),
-            MEngine::Gigaam => Some(("gigaam", entry.id.clone())),
-            MEngine::Cohere => Some(("cohere", entry.id.clone())),
-            MEngine::SenseVoice => Some(("sense_voice", entry.id.clone())),
-            MEngine::Parakeet => Some(("parakeet", entry.id.clone())),
-            MEngine::OmniAsr => Some(("omni_asr", entry.id.clone())),
-            MEngine::Whisper => Some(("whisper", entry.id.clone())),
+            engine => Some((engine.name(), entry.id.clone())),
         }
     }
 
@@ -0,0 +1,693 @@
+//! Live transcription of audio that arrives over time: PCM pushed from a
+//! microphone or network stream, or a WAV file that is still being written.
+//!
+//! Pending audio is re-checked with the VAD as it arrives. A speech region is
+//! closed once enough silence follows it (or once it grows past
+//! [`StreamOptions::max_region_sec`]); closed regions are transcribed with the
+//! selected engine and reported through [`NewSegmentFn`] as final
+//! [`SegmentStage::Transcribe`] segments, numbered in order from 0. While a
+//! region is still open it is re-transcribed every so often and reported as one
+//! [`SegmentStage::Partial`] segment, so captions appear before the speaker
+//! pauses.
+//!
+//! Diarization, forced alignment and post-translation need the whole recording
+//! and are not available live. Native translation (Whisper to English, Canary)
+//! is.
+//!
+//! [`NewSegmentFn`]: crate::types::NewSegmentFn
+
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use eyre::{Result, bail};
+
+use crate::engine::{Engine, ensure_asr_model, resolve_native_target};
+use crate::engines::backend::{self, AsrBackend};
+use crate::types::{Callbacks, Segment, SegmentStage, SpeechSegment, TranscribeOptions};
+
+const SAMPLE_RATE: f64 = 16_000.0;
+/// How far before the cut a region that grew too long looks for a quiet split.
+const SPLIT_SEARCH_SECONDS: f64 = 0.5;
+/// How often [`LiveSession::tail_wav`] checks the file for new audio.
+const TAIL_POLL: Duration = Duration::from_millis(200);
+
+type DetectSpeechFn = fn(&str, &[i16]) -> Result<Vec<(f64, f64)>>;
+
+/// Tuning for a live session.
+#[derive(Clone, Debug)]
+pub struct StreamOptions {
+    /// Silence after speech that closes a region, in seconds. At least the
+    /// VAD's own 0.5 s minimum break.
+    pub min_silence_sec: f64,
+    /// Longest a region may grow before it is cut at a quiet point and
+    /// transcribed anyway, in seconds. Whisper reads 30 s at a time.
+    pub max_region_sec: f64,
+    /// Seconds of new audio between provisional transcriptions of the open
+    /// region; `None` reports final segments only.
+    pub partial_interval_sec: Option<f64>,
+    /// Seconds of new audio to collect before running the VAD again.
+    pub vad_step_sec: f64,
+}
+
+impl Default for StreamOptions {
+    fn default() -> Self {
+        Self {
+            min_silence_sec: 0.5,
+            max_region_sec: 30.0,
+            partial_interval_sec: Some(2.0),
+            vad_step_sec: 0.5,
+        }
+    }
+}
+
+/// A live transcription in progress, from [`Engine::start_stream`]. Feed it
+/// audio with [`Self::push`] or [`Self::tail_wav`] and end it with
+/// [`Self::finish`].
+pub struct LiveSession<'a> {
+    engine: &'a mut Engine,
+    options: TranscribeOptions,
+    stream: StreamOptions,
+    cb: Callbacks,
+    backend: Arc<dyn AsrBackend>,
+    model_path: PathBuf,
+    vad_model: String,
+    /// Speech regions (in seconds) of the pending audio: the VAD outside tests.
+    detect_speech: DetectSpeechFn,
+    native_target: Option<String>,
+    /// Audio not yet transcribed for good, starting `buffer_start` samples
+    /// into the stream.
+    buffer: Vec<i16>,
+    buffer_start: usize,
+    /// Samples received since the VAD last ran.
+    since_vad: usize,
+    /// Stream position (in samples) of the last provisional transcription.
+    last_partial: usize,
+    /// Index the shown partial was sent at, until a final segment replaces it.
+    partial_index: Option<usize>,
+    segments: Vec<Segment>,
+    language: Option<String>,
+}
+
+impl Engine {
+    /// Start transcribing live audio with `options`, downloading the ASR and
+    /// VAD models first if needed. The session borrows the engine, so the
+    /// model stays loaded for the next session or file.
+    pub async fn start_stream(
+        &mut self,
+        options: TranscribeOptions,
+        stream: StreamOptions,
+        cb: Option<Callbacks>,
+    ) -> Result<LiveSession<'_>> {
+        if options.enable_diarize.unwrap_or(false) {
+            bail!("speaker diarization is not available for live transcription");
+        }
+        if options.enable_forced_alignment.unwrap_or(false) {
+            bail!("forced alignment is not available for live transcription");
+        }
+        let cb = cb.unwrap_or_default();
+        let backend = backend::for_model(&options.model)?;
+
+        let from_lang = options.lang.clone().unwrap_or_else(|| "auto".to_string());
+        let native_target = resolve_native_target(
+            &backend.capabilities(&options.model),
+            &from_lang,
+            options.translate_target.as_deref(),
+            options.use_native_translation.unwrap_or(false),
+        );
+        let untranslatable = options
+            .translate_target
+            .as_deref()
+            .filter(|target| native_target.is_none() && from_lang != *target);
+        if let Some(target) = untranslatable {
+            bail!(
+                "{} can't translate to '{target}' itself, and live transcription has no translation pass",
+                options.model
+            );
+        }
+
+        let model_path = ensure_asr_model(&self.models, &self.cfg, &options.model, &cb).await?;
+        let vad_model = match &self.cfg.vad_model_path {
+            Some(p) => p.clone(),
+            None => self
+                .models
+                .ensure_vad_model(cb.progress.as_deref(), cb.is_cancelled.as_deref())
+                .await?
+                .to_string_lossy()
+                .to_string(),
+        };
+
+        Ok(LiveSession {
+            engine: self,
+            options,
+            stream,
+            cb,
+            backend,
+            model_path,
+            vad_model,
+            detect_speech: crate::vad::detect_speech,
+            native_target,
+            buffer: Vec::new(),
+            buffer_start: 0,
+            since_vad: 0,
+            last_partial: 0,
+            partial_index: None,
+            segments: Vec::new(),
+            language: None,
+        })
+    }
+}
+
+impl LiveSession<'_> {
+    /// Add audio (mono i16 at 16 kHz) to the stream, transcribing any speech
+    /// that it closes.
+    pub async fn push(&mut self, samples: &[i16]) -> Result<()> {
+        self.buffer.extend_from_slice(samples);
+        self.since_vad += samples.len();
+        if (self.since_vad as f64) < self.stream.vad_step_sec * SAMPLE_RATE {
+            return Ok(());
+        }
+        self.process(false).await
+    }
+
+    /// Follow a 16 kHz mono 16-bit WAV that is still being written (by a
+    /// recorder, say), pushing audio as it is appended. Returns once the file
+    /// has not grown for `idle`.
+    pub async fn tail_wav(&mut self, path: &Path, idle: Duration) -> Result<()> {
+        let mut last_growth = Instant::now();
+        let data_start = loop {
+            if let Some(header) = read_wav_header(path)? {
+                break header.data_start;
+            }
+            if last_growth.elapsed() >= idle {
+                bail!("'{}' never got a WAV header", path.display());
+            }
+            tokio::time::sleep(TAIL_POLL).await;
+        };
+
+        let mut file = std::fs::File::open(path)?;
+        let mut pos = data_start;
+        // A sample split across two reads waits here for its second byte.
+        let mut odd_byte = None;
+        loop {
+            if self.cancelled() {
+                bail!("Transcription cancelled");
+            }
+            // A finished file states its data length; before that it is a
+            // placeholder and everything after the header is audio.
+            let len = file.metadata()?.len();
+            let end = match read_wav_header(path)? {
+                Some(WavHeader { data_start, data_len: Some(data_len) }) => (data_start + data_len).min(len),
+                _ => len,
+            };
+            if end > pos {
+                let mut bytes = Vec::with_capacity((end - pos) as usize + 1);
+                bytes.extend(odd_byte.take());
+                file.seek(SeekFrom::Start(pos))?;
+                (&mut file).take(end - pos).read_to_end(&mut bytes)?;
+                pos = end;
+                if !bytes.len().is_multiple_of(2) {
+                    odd_byte = bytes.pop();
+                }
+                let samples: Vec<i16> = bytes.chunks_exact(2).map(|b| i16::from_le_bytes([b[0], b[1]])).collect();
+                self.push(&samples).await?;
+                last_growth = Instant::now();
+            } else if last_growth.elapsed() >= idle {
+                return Ok(());
+            }
+            tokio::time::sleep(TAIL_POLL).await;
+        }
+    }
+
+    /// Final segments so far.
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+
+    /// Transcribe whatever speech is still open and end the session, returning
+    /// every final segment and the detected (or requested) language.
+    pub async fn finish(mut self) -> Result<(Vec<Segment>, String)> {
+        self.process(true).await?;
+        let language = match (&self.native_target, self.language.take()) {
+            (Some(target), _) => target.clone(),
+            (None, Some(detected)) => detected,
+            (None, None) => self.options.lang.clone().unwrap_or_else(|| "auto".to_string()),
+        };
+        Ok((self.segments, language))
+    }
+
+    fn cancelled(&self) -> bool {
+        self.cb.is_cancelled.as_ref().is_some_and(|cancelled| cancelled())
+    }
+
+    /// Run the VAD over the pending audio, transcribe the regions it closes and
+    /// drop the audio before the one still open. With `flush`, every region
+    /// counts as closed.
+    async fn process(&mut self, flush: bool) -> Result<()> {
+        self.since_vad = 0;
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        if self.cancelled() {
+            bail!("Transcription cancelled");
+        }
+        let total = self.buffer.len() as f64 / SAMPLE_RATE;
+        let raw = (self.detect_speech)(&self.vad_model, &self.buffer)?;
+        let regions = crate::vad::pad_boundaries(&raw, total);
+        let closed = closed_regions(&raw, total, self.stream.min_silence_sec, flush);
+
+        for &(start, end) in &regions[..closed] {
+            let segments = self.transcribe(to_sample(start), to_sample(end)).await?;
+            self.emit_final(segments);
+        }
+
+        // Keep the open region, or a little trailing silence as lead-in for
+        // the next one.
+        let last_closed_end = regions[..closed].last().map_or(0, |&(_, end)| to_sample(end));
+        let keep_from = match regions.get(closed) {
+            Some(&(start, _)) => to_sample(start),
+            None if flush => self.buffer.len(),
+            None => self.buffer.len().saturating_sub(to_sample(crate::vad::SPEECH_PAD_MS / 1000.0)),
+        };
+        self.drain(keep_from.max(last_closed_end).min(self.buffer.len()));
+
+        let open = closed < regions.len();
+        if let Some(index) = self.partial_index {
+            if self.segments.len() > index {
+                // Replaced by a final segment at the same index.
+                self.partial_index = None;
+            } else if !open {
+                // The region closed without producing any text.
+                self.emit_partial(index, Segment {
+                    start: self.buffer_start as f64 / SAMPLE_RATE,
+                    end: self.buffer_start as f64 / SAMPLE_RATE,
+                    text: String::new(),
+                    words: None,
+                    speaker_id: None,
+                    stats: None,
+                });
+                self.partial_index = None;
+            }
+        }
+        if !open {
+            return Ok(());
+        }
+
+        let max_region = to_sample(self.stream.max_region_sec);
+        if self.buffer.len() > max_region {
+            let split = crate::utils::find_low_energy_split(
+                &self.buffer,
+                0,
+                max_region,
+                to_sample(SPLIT_SEARCH_SECONDS),
+            );
+            let segments = self.transcribe(0, split).await?;
+            self.emit_final(segments);
+            self.drain(split);
+            return Ok(());
+        }
+
+        let received = self.buffer_start + self.buffer.len();
+        let partial_due = self
+            .stream
+            .partial_interval_sec
+            .is_some_and(|interval| (received - self.last_partial) as f64 >= interval * SAMPLE_RATE);
+        if partial_due {
+            self.last_partial = received;
+            let segments = self.transcribe(0, self.buffer.len()).await?;
+            let text = segments.iter().map(|s| s.text.trim()).filter(|t| !t.is_empty()).collect::<Vec<_>>().join(" ");
+            if !text.is_empty() {
+                let index = self.segments.len();
+                self.emit_partial(index, Segment {
+                    start: segments.first().map_or(0.0, |s| s.start),
+                    end: segments.last().map_or(0.0, |s| s.end),
+                    text,
+                    words: None,
+                    speaker_id: None,
+                    stats: None,
+                });
+                self.partial_index = Some(index);
+            }
+        }
+        Ok(())
+    }
+
+    /// Transcribe `buffer[from..to]` as one speech region.
+    async fn transcribe(&mut self, from: usize, to: usize) -> Result<Vec<Segment>> {
+        if to <= from {
+            return Ok(Vec::new());
+        }
+        let speech = SpeechSegment {
+            start: (self.buffer_start + from) as f64 / SAMPLE_RATE,
+            end: (self.buffer_start + to) as f64 / SAMPLE_RATE,
+            audio: Arc::new(self.buffer[from..to].to_vec()),
+            range: 0..to - from,
+            speaker_id: None,
+        };
+        let (segments, detected) = crate::engines::run_engine(
+            &*self.backend,
+            &self.model_path,
+            vec![speech],
+            &self.options,
+            self.native_target.as_deref(),
+            &self.engine.cfg,
+            &mut self.engine.loaded,
+            None,
+            None,
+            self.cb.is_cancelled.clone(),
+        )
+        .await?;
+        if self.language.is_none() {
+            self.language = detected;
+        }
+        Ok(segments)
+    }
+
+    fn emit_final(&mut self, segments: Vec<Segment>) {
+        for segment in segments.into_iter().filter(|s| !s.text.trim().is_empty()) {
+            if let Some(cb) = &self.cb.new_segment_callback {
+                cb(self.segments.len(), &segment, SegmentStage::Transcribe);
+            }
+            self.segments.push(segment);
+        }
+    }
+
+    fn emit_partial(&self, index: usize, segment: Segment) {
+        if let Some(cb) = &self.cb.new_segment_callback {
+            cb(index, &segment, SegmentStage::Partial);
+        }
+    }
+
+    /// Forget the first `n` pending samples.
+    fn drain(&mut self, n: usize) {
+        self.buffer.drain(..n);
+        self.buffer_start += n;
+    }
+}
+
+fn to_sample(seconds: f64) -> usize {
+    (seconds * SAMPLE_RATE).round().max(0.0) as usize
+}
+
+/// How many of the VAD's `raw` regions (seconds into `total` seconds of audio)
+/// are over: any followed by another region, and the last once
+/// `min_silence_sec` of audio has passed since it ended. With `flush`, all.
+fn closed_regions(raw: &[(f64, f64)], total: f64, min_silence_sec: f64, flush: bool) -> usize {
+    match raw.last() {
+        Some(&(_, end)) if !flush && end + min_silence_sec > total => raw.len() - 1,
+        _ => raw.len(),
+    }
+}
+
+struct WavHeader {
+    /// Byte offset of the first sample.
+    data_start: u64,
+    /// Length of the sample data, once the writer has filled it in.
+    data_len: Option<u64>,
+}
+
+/// Parse the header of a WAV file that may still be being written. `None`
+/// until the `data` chunk has started; an error if it isn't 16 kHz mono
+/// 16-bit PCM.
+fn read_wav_header(path: &Path) -> Result<Option<WavHeader>> {
+    let mut head = Vec::new();
+    std::fs::File::open(path)?.take(4096).read_to_end(&mut head)?;
+    parse_wav_header(&head)
+}
+
+fn parse_wav_header(head: &[u8]) -> Result<Option<WavHeader>> {
+    if head.len() < 12 {
+        return Ok(None);
+    }
+    if &head[0..4] != b"RIFF" || &head[8..12] != b"WAVE" {
+        bail!("not a WAV file");
+    }
+    let u16_at = |i: usize| u16::from_le_bytes([head[i], head[i + 1]]);
+    let u32_at = |i: usize| u32::from_le_bytes([head[i], head[i + 1], head[i + 2], head[i + 3]]);
+    let mut pos = 12;
+    let mut format_checked = false;
+    while pos + 8 <= head.len() {
+        let id = &head[pos..pos + 4];
+        let size = u32_at(pos + 4);
+        let body = pos + 8;
+        if id == b"data" {
+            if !format_checked {
+                bail!("WAV file has no format chunk before its data");
+            }
+            // Streaming writers leave 0 or 0xFFFFFFFF here until they finish.
+            let data_len = (size != 0 && size != u32::MAX).then_some(size as u64);
+            return Ok(Some(WavHeader { data_start: body as u64, data_len }));
+        }
+        if id == b"fmt " {
+            if body + 16 > head.len() {
+                return Ok(None);
+            }
+            let (format, channels, rate, bits) = (u16_at(body), u16_at(body + 2), u32_at(body + 4), u16_at(body + 14));
+            // 0xFFFE is WAVE_FORMAT_EXTENSIBLE, which ffmpeg writes for PCM too.
+            if !matches!(format, 1 | 0xFFFE) || channels != 1 || rate != 16_000 || bits != 16 {
+                bail!("expected 16 kHz mono 16-bit PCM, found format {format}, {channels} channel(s), {rate} Hz, {bits} bits");
+            }
+            format_checked = true;
+        }
+        // Chunks are padded to an even length.
+        pos = body + size as usize + (size as usize & 1);
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::EngineConfig;
+    use crate::engines::backend::{AsrModel, Capabilities, ChunkSegment};
+    use std::io::Write;
+    use std::sync::Mutex;
+
+    /// Registered in place of a real model: one segment per chunk, naming the
+    /// chunk's length ("1.2s").
+    struct Lengths;
+
+    impl AsrModel for Lengths {
+        fn transcribe_chunk(&mut self, samples: &[f32]) -> Result<Vec<ChunkSegment>> {
+            let seconds = samples.len() as f64 / SAMPLE_RATE;
+            Ok(vec![ChunkSegment { end: seconds, text: format!("{seconds:.1}s"), ..Default::default() }])
+        }
+
+        fn detected_lang(&self) -> Option<String> {
+            None
+        }
+    }
+
+    impl AsrBackend for Lengths {
+        fn capabilities(&self, _: &str) -> Capabilities {
+            Capabilities::default()
+        }
+
+        fn load(&self, _: &Path, _: &TranscribeOptions, _: &EngineConfig) -> Result<Box<dyn AsrModel>> {
+            Ok(Box::new(Lengths))
+        }
+    }
+
+    /// Stands in for the VAD: speech is every 10 ms frame with a sample louder
+    /// than 100.
+    fn loud_frames(_: &str, samples: &[i16]) -> Result<Vec<(f64, f64)>> {
+        let mut regions: Vec<(usize, usize)> = Vec::new();
+        for (i, frame) in samples.chunks(160).enumerate() {
+            if !frame.iter().any(|s| s.unsigned_abs() > 100) {
+                continue;
+            }
+            let (start, end) = (i * 160, i * 160 + frame.len());
+            match regions.last_mut() {
+                Some(last) if last.1 == start => last.1 = end,
+                _ => regions.push((start, end)),
+            }
+        }
+        Ok(regions.into_iter().map(|(start, end)| (start as f64 / SAMPLE_RATE, end as f64 / SAMPLE_RATE)).collect())
+    }
+
+    type Events = Arc<Mutex<Vec<(usize, String, SegmentStage)>>>;
+
+    /// A session on the [`Lengths`] backend and [`loud_frames`], recording
+    /// every segment event.
+    fn session(engine: &mut Engine, stream: StreamOptions) -> (LiveSession<'_>, Events) {
+        backend::register("stream-test", Lengths);
+        backend::register_model("stream-test-model", "stream-test");
+        let events = Events::default();
+        let sink = events.clone();
+        let cb = Callbacks {
+            new_segment_callback: Some(Arc::new(move |index, segment: &Segment, stage| {
+                sink.lock().unwrap().push((index, segment.text.clone(), stage));
+            })),
+            ..Default::default()
+        };
+        let options = TranscribeOptions {
+            model: "stream-test-model".into(),
+            lang: Some("en".into()),
+            ..Default::default()
+        };
+        let session = LiveSession {
+            engine,
+            backend: backend::for_model(&options.model).unwrap(),
+            options,
+            stream,
+            cb,
+            model_path: PathBuf::from("stream-test"),
+            vad_model: String::new(),
+            detect_speech: loud_frames,
+            native_target: None,
+            buffer: Vec::new(),
+            buffer_start: 0,
+            since_vad: 0,
+            last_partial: 0,
+            partial_index: None,
+            segments: Vec::new(),
+            language: None,
+        };
+        (session, events)
+    }
+
+    fn speech(seconds: f64) -> Vec<i16> {
+        vec![1000; to_sample(seconds)]
+    }
+
+    fn silence(seconds: f64) -> Vec<i16> {
+        vec![0; to_sample(seconds)]
+    }
+
+    /// Segment bounds in samples, with the text.
+    fn spans(segments: &[Segment]) -> Vec<(usize, usize, &str)> {
+        segments.iter().map(|s| (to_sample(s.start), to_sample(s.end), s.text.as_str())).collect()
+    }
+
+    #[tokio::test]
+    async fn partials_are_replaced_by_finals_at_the_same_index() {
+        let mut engine = Engine::new(EngineConfig::default());
+        let stream = StreamOptions { partial_interval_sec: Some(1.0), ..Default::default() };
+        let (mut session, events) = session(&mut engine, stream);
+
+        session.push(&speech(1.0)).await.unwrap();
+        assert_eq!(session.partial_index, Some(0));
+
+        // Half a second of silence closes the region; it is transcribed with
+        // its 0.2 s pad, and the pad's worth of silence after it is kept.
+        session.push(&silence(1.0)).await.unwrap();
+        assert_eq!(session.partial_index, None);
+        assert_eq!(session.buffer_start, to_sample(1.8));
+        assert_eq!(session.buffer.len(), to_sample(0.2));
+
+        session.push(&speech(1.0)).await.unwrap();
+        assert_eq!(session.partial_index, Some(1));
+
+        let (segments, language) = session.finish().await.unwrap();
+        assert_eq!(spans(&segments), [(0, to_sample(1.2), "1.2s"), (to_sample(1.8), to_sample(3.0), "1.2s")]);
+        assert_eq!(language, "en");
+        let events = events.lock().unwrap();
+        let events: Vec<(usize, &str, SegmentStage)> =
+            events.iter().map(|(i, text, stage)| (*i, text.as_str(), *stage)).collect();
+        assert_eq!(
+            events,
+            [
+                (0, "1.0s", SegmentStage::Partial),
+                (0, "1.2s", SegmentStage::Transcribe),
+                (1, "1.2s", SegmentStage::Partial),
+                (1, "1.2s", SegmentStage::Transcribe),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn long_regions_are_cut_at_a_quiet_point() {
+        let mut engine = Engine::new(EngineConfig::default());
+        let stream = StreamOptions { max_region_sec: 2.0, partial_interval_sec: None, ..Default::default() };
+        let (mut session, events) = session(&mut engine, stream);
+
+        // Quieter, but still speech to the VAD: the cut lands where it starts.
+        let mut audio = speech(3.0);
+        audio[to_sample(1.7)..to_sample(1.8)].fill(200);
+        for second in audio.chunks(to_sample(1.0)) {
+            session.push(second).await.unwrap();
+        }
+        assert_eq!(session.buffer_start, to_sample(1.7));
+        assert_eq!(session.buffer.len(), to_sample(1.3));
+        assert_eq!(events.lock().unwrap().len(), 1);
+
+        let (segments, _) = session.finish().await.unwrap();
+        assert_eq!(spans(&segments), [(0, to_sample(1.7), "1.7s"), (to_sample(1.7), to_sample(3.0), "1.3s")]);
+    }
+
+    #[tokio::test]
+    async fn tailing_keeps_samples_split_across_reads() {
+        let mut engine = Engine::new(EngineConfig::default());
+        // A VAD step longer than the audio: everything pushed stays buffered.
+        let stream = StreamOptions { vad_step_sec: 1000.0, ..Default::default() };
+        let (mut session, _) = session(&mut engine, stream);
+
+        let samples: Vec<i16> = (0..4000).map(|i| (i * 37 % 20_000 - 10_000) as i16).collect();
+        let bytes: Vec<u8> = samples.iter().flat_map(|s| s.to_le_bytes()).collect();
+        let path = std::env::temp_dir().join(format!("autosubs-tail-{}.wav", std::process::id()));
+        let mut file = std::fs::File::create(&path).unwrap();
+        file.write_all(&wav_header(1, 16_000, 0)).unwrap();
+        // The first read ends halfway through a sample.
+        file.write_all(&bytes[..3001]).unwrap();
+        let rest = bytes[3001..].to_vec();
+        let writer = std::thread::spawn(move || {
+            std::thread::sleep(TAIL_POLL * 2);
+            file.write_all(&rest).unwrap();
+        });
+
+        let tailed = session.tail_wav(&path, TAIL_POLL * 5).await;
+        writer.join().unwrap();
+        std::fs::remove_file(&path).unwrap();
+        tailed.unwrap();
+        assert_eq!(session.buffer, samples);
+    }
+
+    fn wav_header(channels: u16, rate: u32, data_len: u32) -> Vec<u8> {
+        let mut h = Vec::new();
+        h.extend_from_slice(b"RIFF");
+        h.extend_from_slice(&0u32.to_le_bytes());
+        h.extend_from_slice(b"WAVE");
+        h.extend_from_slice(b"fmt ");
+        h.extend_from_slice(&16u32.to_le_bytes());
+        h.extend_from_slice(&1u16.to_le_bytes());
+        h.extend_from_slice(&channels.to_le_bytes());
+        h.extend_from_slice(&rate.to_le_bytes());
+        h.extend_from_slice(&(rate * 2 * channels as u32).to_le_bytes());
+        h.extend_from_slice(&(2 * channels).to_le_bytes());
+        h.extend_from_slice(&16u16.to_le_bytes());
+        h.extend_from_slice(b"LIST");
+        h.extend_from_slice(&3u32.to_le_bytes());
+        h.extend_from_slice(b"abc\0");
+        h.extend_from_slice(b"data");
+        h.extend_from_slice(&data_len.to_le_bytes());
+        h
+    }
+
+    #[test]
+    fn finds_the_data_chunk_past_other_chunks() {
+        let header = parse_wav_header(&wav_header(1, 16_000, 0)).unwrap().unwrap();
+        assert_eq!(header.data_start, 56);
+        assert_eq!(header.data_len, None);
+
+        let header = parse_wav_header(&wav_header(1, 16_000, 3200)).unwrap().unwrap();
+        assert_eq!(header.data_len, Some(3200));
+    }
+
+    #[test]
+    fn waits_for_a_partial_header_and_rejects_other_formats() {
+        assert!(parse_wav_header(&wav_header(1, 16_000, 0)[..30]).unwrap().is_none());
+        assert!(parse_wav_header(&wav_header(2, 16_000, 0)).is_err());
+        assert!(parse_wav_header(&wav_header(1, 44_100, 0)).is_err());
+        assert!(parse_wav_header(b"OggS\0\0\0\0\0\0\0\0").is_err());
+    }
+
+    #[test]
+    fn last_region_stays_open_until_enough_silence_follows() {
+        let raw = [(0.5, 2.0), (3.0, 4.0)];
+        assert_eq!(closed_regions(&raw, 4.2, 0.5, false), 1);
+        assert_eq!(closed_regions(&raw, 4.5, 0.5, false), 2);
+        assert_eq!(closed_regions(&raw, 4.2, 0.5, true), 2);
+        assert_eq!(closed_regions(&[], 4.2, 0.5, false), 0);
+    }
+}
@@ -23,6 +23,11 @@ pub enum SegmentStage {
     Transcribe,
     Translate,
     Align,
+    /// Provisional text for speech that is still going on, from a live
+    /// session (see [`crate::stream`]). It is sent at the index the next final
+    /// segment will take and is replaced by that `Transcribe` segment; an
+    /// empty partial withdraws the previous one.
+    Partial,
 }
 
 // Shared callback types
@@ -59,6 +64,7 @@ pub struct AdvancedTranscribe {
     pub max_text_ctx: Option<i32>, // The maximum number of tokens to keep in the text context. Defaults to 16000.
     pub init_prompt: Option<String>, // Initial prompt for the model.
     pub diarize_threshold: Option<f32>, // Threshold for diarization
+    pub workers: Option<usize>, // Speech chunks decoded at once, each on its own ONNX session or Whisper state (sharing one context). Defaults to 1; above that, Whisper no longer carries context from one chunk to the next.
 }
 
 // TranscribeOptions references AdvancedTranscribe optionally
@@ -113,6 +119,22 @@ pub struct WordTimestamp {
     pub probability: Option<f32>,
 }
 
+/// Decoder statistics for one ASR segment, matching the fields of OpenAI
+/// Whisper's `verbose_json` segments. Only the Whisper engine reports them.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct SegmentStats {
+    /// Start of the decoded audio window, in 10 ms frames.
+    pub seek: usize,
+    /// Token ids of the segment, including timestamp tokens.
+    pub tokens: Vec<i32>,
+    /// Sampling temperature the segment was decoded at.
+    pub temperature: f32,
+    /// Mean token log-probability.
+    pub avg_logprob: f64,
+    /// Probability that the window contains no speech.
+    pub no_speech_prob: f64,
+}
+
 // Transcribe function will return a list of segments
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Segment {
@@ -123,6 +145,10 @@ pub struct Segment {
     pub words: Option<Vec<WordTimestamp>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub speaker_id: Option<String>,
+    /// Decoder statistics, when the engine reports them. Dropped once
+    /// `process_segments` re-cuts the text into subtitle cues.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stats: Option<SegmentStats>,
 }
 
-pub use diarize::SpeechSegment;
+pub use diarize::{SharedAudio, SpeechSegment};
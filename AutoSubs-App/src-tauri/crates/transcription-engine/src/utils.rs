@@ -139,6 +139,7 @@ pub fn find_low_energy_split(
 /// Split a speech segment into chunks no longer than `max_seconds` (at 16 kHz),
 /// preserving absolute timing and speaker id. Used by ONNX backends that process
 /// a whole clip per call (Moonshine, SenseVoice, Canary, Cohere) to bound memory.
+/// The chunks are ranges of the segment's shared audio, not copies.
 ///
 /// Boundaries are nudged back onto the quietest frame within the last ~1 s of
 /// each chunk so a chunk edge does not cut through a word, which makes models
@@ -150,25 +151,27 @@ pub fn split_speech_segment(
     const SAMPLE_RATE: usize = 16000;
     const SEARCH_WINDOW_SAMPLES: usize = SAMPLE_RATE; // look back up to 1 s for silence
     let max_samples = (max_seconds * SAMPLE_RATE as f64) as usize;
-    if max_samples == 0 || seg.samples.len() <= max_samples {
+    let samples = seg.samples();
+    if max_samples == 0 || samples.len() <= max_samples {
         return vec![seg.clone()];
     }
 
     let mut out = Vec::new();
     let mut idx = 0usize;
-    while idx < seg.samples.len() {
-        let target = (idx + max_samples).min(seg.samples.len());
-        let end_idx = if target == seg.samples.len() {
+    while idx < samples.len() {
+        let target = (idx + max_samples).min(samples.len());
+        let end_idx = if target == samples.len() {
             target
         } else {
-            find_low_energy_split(&seg.samples, idx, target, SEARCH_WINDOW_SAMPLES)
+            find_low_energy_split(samples, idx, target, SEARCH_WINDOW_SAMPLES)
         };
         let start_s = idx as f64 / SAMPLE_RATE as f64;
         let end_s = end_idx as f64 / SAMPLE_RATE as f64;
         out.push(crate::types::SpeechSegment {
             start: seg.start + start_s,
             end: seg.start + end_s,
-            samples: seg.samples[idx..end_idx].to_vec(),
+            audio: seg.audio.clone(),
+            range: seg.range.start + idx..seg.range.start + end_idx,
             speaker_id: seg.speaker_id.clone(),
         });
         idx = end_idx;
@@ -213,6 +216,21 @@ pub fn interpolate_word_timestamps(line: &str, start: f64, end: f64) -> Vec<crat
     out
 }
 
+/// Ratio of a text's UTF-8 size to its zlib-compressed size, as OpenAI Whisper
+/// reports per segment. Repetitive (often hallucinated) text scores high;
+/// Whisper treats anything above 2.4 as a failed decode.
+pub fn compression_ratio(text: &str) -> f64 {
+    use std::io::Write;
+    let bytes = text.as_bytes();
+    if bytes.is_empty() { return 0.0; }
+    let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+    let compressed = encoder.write_all(bytes).and_then(|_| encoder.finish());
+    match compressed {
+        Ok(c) if !c.is_empty() => bytes.len() as f64 / c.len() as f64,
+        _ => 0.0,
+    }
+}
+
 /// Clamp the previous segment (and its last word) so it doesn't overlap the
 /// new segment's start, then push. Mirrors the logic duplicated across engines.
 pub fn push_segment_clamped(
@@ -262,6 +280,7 @@ pub fn get_whisper_languages() -> Vec<&'static str> {
 mod tests {
     use super::*;
     use crate::types::SpeechSegment;
+    use std::sync::Arc;
 
     /// 16 kHz buffer of loud samples with a silent 100 ms window centred on `quiet_at_seconds`.
     fn samples_with_silence_at(duration_seconds: f64, quiet_at_seconds: f64) -> Vec<i16> {
@@ -284,14 +303,22 @@ mod tests {
 
     #[test]
     fn split_lands_on_silence_instead_of_cutting_a_word() {
+        // The segment starts 10 s into the recording it shares.
+        let mut audio = vec![0; 160_000];
+        audio.extend(samples_with_silence_at(45.0, 29.5));
         let seg = SpeechSegment {
             start: 10.0,
             end: 55.0,
-            samples: samples_with_silence_at(45.0, 29.5),
+            range: 160_000..audio.len(),
+            audio: Arc::new(audio),
             speaker_id: None,
         };
         let chunks = split_speech_segment(&seg, 30.0);
         assert_eq!(chunks.len(), 2);
+        assert!(chunks.iter().all(|chunk| Arc::ptr_eq(&chunk.audio, &seg.audio)));
+        assert_eq!(chunks[0].range.start, 160_000);
+        assert_eq!(chunks[0].range.end, chunks[1].range.start);
+        assert_eq!(chunks[1].range.end, seg.range.end);
         // Boundary moved back into the silent window rather than the hard 30 s mark.
         assert!(
             (chunks[0].end - (10.0 + 29.5)).abs() < 0.05,
@@ -304,14 +331,25 @@ mod tests {
 
     #[test]
     fn split_falls_back_to_the_hard_boundary_without_silence() {
+        let audio = samples_with_silence_at(45.0, 44.0);
         let seg = SpeechSegment {
             start: 0.0,
             end: 45.0,
-            samples: samples_with_silence_at(45.0, 44.0),
+            range: 0..audio.len(),
+            audio: Arc::new(audio),
             speaker_id: None,
         };
         let chunks = split_speech_segment(&seg, 30.0);
         assert_eq!(chunks.len(), 2);
         assert!((chunks[0].end - 30.0).abs() < 1e-9, "unexpected boundary: {}", chunks[0].end);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn compression_ratio_flags_repetition() {
+        assert_eq!(compression_ratio(""), 0.0);
+        let plain = compression_ratio("The quick brown fox jumps over the lazy dog.");
+        let looped = compression_ratio(&"Thank you. ".repeat(40));
+        assert!(plain < 2.4, "plain text scored {plain}");
+        assert!(looped > 2.4, "repeated text scored {looped}");
+    }
+}
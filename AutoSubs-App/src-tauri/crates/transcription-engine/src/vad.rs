@@ -1,73 +1,111 @@
 use whisper_rs::{WhisperVadContext, WhisperVadContextParams, WhisperVadParams};
-use crate::types::SpeechSegment;
+use crate::types::{SharedAudio, SpeechSegment};
 use eyre::Result;
 
 /// Silero reports tight boundaries and can clip a word's onset/offset, so the
 /// ASR sees a partial word at a chunk edge (half words, or stray symbols with
 /// normalizing models). Boundaries are grown by this much, taking audio only
 /// from the surrounding silence.
-const SPEECH_PAD_MS: f64 = 200.0;
+pub(crate) const SPEECH_PAD_MS: f64 = 200.0;
 
-/// Detect speech segments with Silero VAD via whisper-rs. Input `int_samples` must be mono i16 at 16_000 Hz.
+/// Audio is converted to f32 and run through the VAD this many seconds at a
+/// time, so a multi-hour recording never needs a float copy of the whole file.
+const VAD_BLOCK_SECONDS: usize = 600;
+
+/// Silences shorter than this are not treated as breaks. 500 ms aligns with
+/// common VAD defaults (Silero/pyannote). 200 ms was aggressive and chopped
+/// natural mid-phrase pauses, particularly in languages with more inter-word
+/// pausing (e.g. Russian).
+const MIN_SILENCE_MS: i32 = 500;
+
+/// Detect speech segments with Silero VAD via whisper-rs. `audio` must be mono
+/// i16 at 16_000 Hz; the segments are ranges of it rather than copies.
 pub fn get_segments(
     vad_model: &str,
-    int_samples: &[i16],
+    audio: &SharedAudio,
 ) -> Result<Vec<SpeechSegment>> {
-    // Convert entire integer buffer to f32 for VAD processing
-    let mut samples = vec![0.0f32; int_samples.len()];
-    whisper_rs::convert_integer_to_float_audio(int_samples, &mut samples)?;
-
-    // The VAD graph is tiny (a single-window Silero LSTM). Running it with
-    // multiple threads hits a known ggml threadpool race/deadlock on tiny
-    // graphs, especially on Windows, which can cause VAD to hang
-    // indefinitely on longer audio. Use a single thread to avoid the threadpool.
-    let mut ctx_params = WhisperVadContextParams::new();
-    ctx_params.set_n_threads(1);
-    let mut vad = WhisperVadContext::new(vad_model, ctx_params)?;
-
-    let mut vadp = WhisperVadParams::new();
-    // 500 ms aligns with common VAD defaults (Silero/pyannote). 200 ms was
-    // aggressive and chopped natural mid-phrase pauses, particularly in
-    // languages with more inter-word pausing (e.g. Russian).
-    vadp.set_min_silence_duration(500); // ms — silences shorter than this are not treated as breaks
-
-    let segs = vad.segments_from_samples(vadp, &samples)?;
+    let raw = detect_speech(vad_model, audio)?;
 
-    // Convert VAD centiseconds to seconds and slice samples from the original buffer
-    let n = int_samples.len();
+    // Map the padded regions onto sample ranges of the original buffer
+    let n = audio.len();
     const SR: f32 = 16_000.0;
     let n_f32 = n as f32;
 
-    let raw: Vec<(f64, f64)> = segs
-        .map(|s| ((s.start as f64) / 100.0, (s.end as f64) / 100.0))
-        .filter(|(st, en)| en > st)
-        .collect();
-
     let segments: Vec<SpeechSegment> = pad_boundaries(&raw, n as f64 / SR as f64)
         .into_iter()
         .map(|(start_sec, end_sec)| {
             let start_idx = ((start_sec as f32 * SR).round()).clamp(0.0, n_f32) as usize;
             let end_idx = ((end_sec as f32 * SR).round()).clamp(0.0, n_f32) as usize;
 
-            let seg_samples: Vec<i16> = if end_idx > start_idx {
-                int_samples[start_idx..end_idx].to_vec()
-            } else {
-                Vec::new()
-            };
-
-            SpeechSegment { start: start_sec, end: end_sec, samples: seg_samples, speaker_id: None }
+            SpeechSegment {
+                start: start_sec,
+                end: end_sec,
+                audio: audio.clone(),
+                range: start_idx..end_idx.max(start_idx),
+                speaker_id: None,
+            }
         })
-        .filter(|seg| seg.end > seg.start && !seg.samples.is_empty())
+        .filter(|seg| seg.end > seg.start && !seg.range.is_empty())
         .collect();
 
     Ok(segments)
 }
 
+/// Speech regions as `(start, end)` seconds, exactly as Silero reports them
+/// (unpadded). Input `int_samples` must be mono i16 at 16_000 Hz.
+pub(crate) fn detect_speech(vad_model: &str, int_samples: &[i16]) -> Result<Vec<(f64, f64)>> {
+    // The VAD graph is tiny (a single-window Silero LSTM). Running it with
+    // multiple threads hits a known ggml threadpool race/deadlock on tiny
+    // graphs, especially on Windows, which can cause VAD to hang
+    // indefinitely on longer audio. Use a single thread to avoid the threadpool.
+    let mut ctx_params = WhisperVadContextParams::new();
+    ctx_params.set_n_threads(1);
+    let mut vad = WhisperVadContext::new(vad_model, ctx_params)?;
+
+    // Even blocks, so the last one is never a sliver too short to analyse.
+    let block_len = VAD_BLOCK_SECONDS * 16_000;
+    let blocks = int_samples.len().div_ceil(block_len).max(1);
+    let block_len = int_samples.len().div_ceil(blocks).max(1);
+    let mut samples = vec![0.0f32; block_len.min(int_samples.len())];
+
+    let mut speech = Vec::new();
+    for (i, block) in int_samples.chunks(block_len).enumerate() {
+        let samples = &mut samples[..block.len()];
+        whisper_rs::convert_integer_to_float_audio(block, samples)?;
+
+        let mut vadp = WhisperVadParams::new();
+        vadp.set_min_silence_duration(MIN_SILENCE_MS);
+        let offset = (i * block_len) as f64 / 16_000.0;
+
+        // VAD reports centiseconds
+        let segs = vad
+            .segments_from_samples(vadp, samples)?
+            .map(|s| (offset + (s.start as f64) / 100.0, offset + (s.end as f64) / 100.0))
+            .filter(|(st, en)| en > st);
+        append_block(&mut speech, segs);
+    }
+    Ok(speech)
+}
+
+/// Add one block's speech regions, joining a region cut by the block boundary
+/// back together: the VAD would not have split it at less than
+/// [`MIN_SILENCE_MS`] of silence.
+fn append_block(speech: &mut Vec<(f64, f64)>, block: impl IntoIterator<Item = (f64, f64)>) {
+    let mut block = block.into_iter();
+    if let Some((start, end)) = block.next() {
+        match speech.last_mut() {
+            Some(last) if start - last.1 < MIN_SILENCE_MS as f64 / 1000.0 => last.1 = last.1.max(end),
+            _ => speech.push((start, end)),
+        }
+    }
+    speech.extend(block);
+}
+
 /// Grow each boundary by up to [`SPEECH_PAD_MS`], but never past the audio ends
 /// and never more than halfway into the gap to the neighbouring segment, so
 /// segments stay disjoint and no speech is transcribed twice. Segments that the
 /// VAD already reported as overlapping are trimmed back to the gap midpoint.
-fn pad_boundaries(raw: &[(f64, f64)], total_sec: f64) -> Vec<(f64, f64)> {
+pub(crate) fn pad_boundaries(raw: &[(f64, f64)], total_sec: f64) -> Vec<(f64, f64)> {
     let pad = SPEECH_PAD_MS / 1000.0;
     raw.iter()
         .enumerate()
@@ -116,6 +154,17 @@ mod tests {
         assert_close(padded[1].1, 2.05);
     }
 
+    #[test]
+    fn regions_split_by_a_block_boundary_are_joined() {
+        let mut speech = vec![(1.0, 2.0), (598.0, 600.0)];
+        append_block(&mut speech, [(600.1, 603.0), (610.0, 611.0)]);
+        assert_eq!(speech, [(1.0, 2.0), (598.0, 603.0), (610.0, 611.0)]);
+
+        // A real pause at the boundary still separates the regions.
+        append_block(&mut speech, [(1200.0, 1201.0)]);
+        assert_eq!(speech.len(), 4);
+    }
+
     #[test]
     fn overlapping_input_segments_are_trimmed_to_the_midpoint() {
         let raw = vec![(0.0, 1.2), (1.0, 2.0)];
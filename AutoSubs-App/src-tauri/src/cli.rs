@@ -1,9 +1,13 @@
 //! Headless command-line interface.
 //!
-//! AutoSubs is a Tauri desktop app, but when it is launched with a subcommand
-//! (currently just `transcribe`) we run without showing the window, do the work,
-//! print the result, and exit. This lets AI agents and terminal users drive the
-//! transcription engine directly.
+//! AutoSubs is a Tauri desktop app, but when it is launched with arguments we run
+//! without showing the window, do the work, print the result, and exit. This lets
+//! AI agents and terminal users drive the transcription engine directly. A bare
+//! `autosubs <file>` transcribes; several files, directories or globs transcribe
+//! as a batch with one output file per input and a summary at the end.
+//! `autosubs convert <file>` converts an existing subtitle file between formats,
+//! `autosubs reformat <transcript.json>` re-wraps a saved transcript's cues, and
+//! `autosubs models` lists, downloads and deletes the cached models.
 //!
 //! The heavy lifting is reused verbatim from the GUI path:
 //! [`crate::transcription_api::transcribe_audio`] already normalizes audio via the
@@ -16,49 +20,22 @@ use std::sync::Arc;
 
 use serde::{Deserialize, Serialize};
 use serde_json::json;
-use tauri::{AppHandle, Listener, Runtime};
+use tauri::{AppHandle, Listener, Manager, Runtime};
 use tauri_plugin_cli::{CliExt, Matches};
 
 #[allow(unused_imports)]
 use std::process::Command;
 
-use crate::transcript_types::{Segment, Transcript};
-use crate::transcription_api::{FrontendTranscribeOptions, transcribe_audio};
-use transcription_engine::TextDensity;
-
-/// Transcription model identifiers accepted by `--model`, grouped by family and
-/// ordered best-first within each group. Hardcoded here because the model catalog
-/// currently lives only in the frontend (`AutoSubs-App/src/lib/models.ts`); when
-/// that catalog moves to the backend this should be sourced from it instead.
-const MODELS: &[&str] = &[
-    // Whisper
-    "tiny",
-    "tiny.en",
-    "base",
-    "base.en",
-    "small",
-    "small.en",
-    "medium",
-    "medium.en",
-    "large-v3-turbo",
-    "large-v3",
-    // Parakeet
-    "parakeet",
-    // GigaAM
-    "gigaam-v3",
-    "gigaam-multilingual",
-    // Omni-ASR
-    "omni-asr-1b-ctc",
-    // Moonshine
-    "moonshine-tiny",
-    "moonshine-base",
-    "moonshine-tiny-ar",
-    "moonshine-tiny-zh",
-    "moonshine-tiny-ja",
-    "moonshine-tiny-ko",
-    "moonshine-tiny-uk",
-    "moonshine-tiny-vi",
-];
+use crate::transcription_api::{
+    FrontendFormattingOptions, FrontendTranscribeOptions, export_options, format_for_export, import_transcript,
+    parse_text_case, reformat_segments, render_export, transcribe_audio, transcribe_audio_with,
+};
+use crate::transcript_types::Transcript;
+use transcription_engine::manifest::{self, LanguageSupport, ModelLicense, Ui};
+use transcription_engine::{
+    ContentFormatting, ExportOptions, FrameRate, LabeledProgressFn, ModelManager, ProgressType, SubtitleFormat,
+    TextDensity,
+};
 
 /// True when the app was launched with CLI arguments, meaning "run headless,
 /// don't open a window". Checked against raw `argv` *before* the Tauri app is
@@ -112,6 +89,25 @@ pub async fn run<R: Runtime>(app: AppHandle<R>) -> ! {
         flush_and_exit(0);
     }
 
+    if let Some(sub) = matches.subcommand {
+        let sub = *sub;
+        if let Some(text) = help_text(&sub.matches) {
+            println!("{text}");
+            flush_and_exit(0);
+        }
+        match sub.name.as_str() {
+            "convert" => run_convert(sub.matches),
+            "reformat" => run_reformat(sub.matches),
+            "models" => run_models(app, sub.matches).await,
+            "serve" => run_serve(app, sub.matches).await,
+            "watch" => run_watch(app, sub.matches).await,
+            other => {
+                eprintln!("autosubs: unknown subcommand '{other}'");
+                flush_and_exit(2);
+            }
+        }
+    }
+
     // `--help`: the plugin puts the rendered help text in the "help" arg's value.
     if let Some(text) = help_text(&matches) {
         println!("{text}");
@@ -120,8 +116,8 @@ pub async fn run<R: Runtime>(app: AppHandle<R>) -> ! {
 
     // `--list-models`: print the known model identifiers (one per line) and exit.
     if arg_flag(&matches, "list-models") {
-        for model in MODELS {
-            println!("{model}");
+        for model in &manifest::MANIFEST.models {
+            println!("{}", model.id);
         }
         flush_and_exit(0);
     }
@@ -130,328 +126,1445 @@ pub async fn run<R: Runtime>(app: AppHandle<R>) -> ! {
 }
 
 async fn run_transcribe<R: Runtime>(app: AppHandle<R>, m: Matches) -> ! {
+    let inputs = arg_list(&m, "input");
+    // Explicit flags win over the `--profile` values, which win over the defaults.
+    let (config_path, profile) = load_profile(&app, &m);
+
+    let mut options = transcribe_options(&m, &profile);
+    options.audio_path = inputs.first().cloned().unwrap_or_default();
+    let translate = options.translate.unwrap_or(false);
+    let forced_alignment = options.enable_forced_alignment.unwrap_or(false);
+
+    let output = arg_str(&m, "output");
+    // Resolve the output format up front so a bad value fails before doing work.
+    let format = match resolve_format(arg_str(&m, "format").as_deref(), output.as_deref(), profile.format.as_deref()) {
+        Ok(f) => f,
+        Err(e) => {
+            eprintln!("autosubs: {e}");
+            flush_and_exit(2);
+        }
+    };
+
+    // `--print-config`: show the merged settings (after the profile and the
+    // explicit flags) and exit, so a profile can be checked without running it.
+    if arg_flag(&m, "print-config") {
+        let config = config_report(config_path.as_deref(), arg_str(&m, "profile"), format, &options);
+        println!("{}", serde_json::to_string_pretty(&config).unwrap_or_default());
+        flush_and_exit(0);
+    }
+
     // `input` is optional in the CLI schema so flags like `--list-models` can run
     // without a file; enforce it here as a usage error (exit 2) when transcribing.
-    let input = match arg_str(&m, "input") {
-        Some(p) => p,
-        None => {
-            eprintln!("autosubs: missing required <input> file path");
-            flush_and_exit(2);
+    if inputs.is_empty() {
+        eprintln!("autosubs: missing required <input> file path");
+        flush_and_exit(2);
+    }
+    if translate && forced_alignment {
+        eprintln!("autosubs: --forced-alignment cannot be used with --translate");
+        flush_and_exit(2);
+    }
+    if forced_alignment {
+        eprintln!(
+            "autosubs: MMS forced-alignment weights may be downloaded (317MB); CC BY-NC 4.0, noncommercial use"
+        );
+    }
+
+    let export = resolve_export_settings(&m);
+
+    // Several inputs, a directory, a glob or `--output-dir` make a batch: one
+    // output file per input instead of a single result on stdout or `-o`.
+    let output_dir = arg_str(&m, "output-dir");
+    let batch = inputs.len() > 1
+        || output_dir.is_some()
+        || inputs.iter().any(|p| is_glob(p) || std::path::Path::new(p).is_dir());
+    if batch && output.is_some() {
+        eprintln!("autosubs: -o/--output writes a single file; use --output-dir for several inputs");
+        flush_and_exit(2);
+    }
+
+    let finish = format_preset(&formatting_options(&options), format);
+
+    // Progress reporting on stderr. `transcribe_audio` already emits
+    // `labeled-progress` events ({progress, type, label}); we render them as a live
+    // bar on a TTY, or as one line per high-level stage when stderr is piped (so an
+    // agent capturing stderr gets clean, non-spammy output). stdout stays JSON-only.
+    // `--progress json` replaces both with an NDJSON event stream, and
+    // `--progress none` silences them.
+    let progress = resolve_progress(&m);
+    if progress != ProgressMode::Json && arg_str(&m, "progress-fd").is_some() {
+        eprintln!("autosubs: --progress-fd needs --progress json");
+        flush_and_exit(2);
+    }
+    let stream = match progress {
+        ProgressMode::Json => Some(Arc::new(EventStream::open(resolve_progress_fd(&m)))),
+        _ => None,
+    };
+    QUIET.store(progress != ProgressMode::Auto, std::sync::atomic::Ordering::Relaxed);
+    let is_tty = progress == ProgressMode::Auto && std::io::stderr().is_terminal();
+    note(format_args!("autosubs: starting (model={})", options.model));
+
+    let events = app.clone();
+    let last_stage: Arc<std::sync::Mutex<Option<String>>> = Arc::new(std::sync::Mutex::new(None));
+    let last_stage_cb = last_stage.clone();
+    let progress_stream = stream.clone();
+    let mut listeners = vec![events.listen("labeled-progress", move |event| {
+        let Ok(ev) = serde_json::from_str::<ProgressEvent>(event.payload()) else {
+            return;
+        };
+        let stage = ev.stage.as_deref().unwrap_or("");
+        // Ignore the initial null-stage tick (before the engine sets a real stage).
+        if stage.is_empty() {
+            return;
+        }
+        if let Some(stream) = &progress_stream {
+            stream.emit(
+                "progress",
+                json!({ "stage": stage.to_lowercase(), "percent": ev.progress, "label": ev.label }),
+            );
+            return;
+        }
+        if progress == ProgressMode::Quiet {
+            return;
+        }
+        if is_tty {
+            let mut err = std::io::stderr();
+            let _ = write!(err, "{}", render_bar(stage, ev.progress));
+            let _ = err.flush();
+        } else if let Ok(mut last) = last_stage_cb.lock() {
+            if last.as_deref() != Some(stage) {
+                eprintln!("autosubs: {}...", stage_label(stage).to_lowercase());
+                *last = Some(stage.to_string());
+            }
+        }
+    })];
+    // The live segment and diarization events only go to the JSON stream.
+    if let Some(stream) = &stream {
+        for (name, event_name) in [("segment-updated", "segment"), ("speakers-identified", "speakers")] {
+            let stream = stream.clone();
+            listeners.push(events.listen(name, move |event| {
+                if let Ok(payload) = serde_json::from_str(event.payload()) {
+                    stream.emit(event_name, payload);
+                }
+            }));
+        }
+    }
+    let emit = |event: &str, fields: serde_json::Value| {
+        if let Some(stream) = &stream {
+            stream.emit(event, fields);
+        }
+    };
+
+    if !batch {
+        if let Some(stream) = &stream {
+            stream.set_input(&inputs[0]);
+        }
+        emit("start", json!({ "model": options.model }));
+        let result = transcribe_audio(app, options).await;
+
+        for id in listeners {
+            events.unlisten(id);
+        }
+        if is_tty {
+            // Terminate the in-place bar line so later output isn't overwritten.
+            eprintln!();
+        }
+
+        match result {
+            Ok(transcript) => {
+                write_transcript(&finish(transcript), format, output.as_deref(), &export);
+                emit("done", json!({ "status": "ok", "output": output }));
+                flush_and_exit(0);
+            }
+            Err(e) => {
+                emit("done", json!({ "status": "failed", "output": output, "error": e }));
+                fail(&e)
+            }
         }
+    }
+
+    let name_template = arg_str(&m, "name").unwrap_or_else(|| "{stem}.{ext}".to_string());
+    let overwrite = arg_flag(&m, "overwrite");
+    let files = expand_inputs(&inputs);
+    let total = files.len();
+    let mut results = Vec::with_capacity(total);
+    // One engine for the whole batch, so the model is loaded once.
+    let mut engine = None;
+
+    // Every outcome is recorded for the summary and, with `--progress json`,
+    // reported as a `done` event.
+    let mut record = |result: BatchResult| {
+        emit("done", json!(result));
+        results.push(result);
     };
 
+    for (i, file) in files.into_iter().enumerate() {
+        let input = match file {
+            Ok(path) => path,
+            Err((input, error)) => {
+                note(format_args!("autosubs: [{}/{total}] {input}: {error}", i + 1));
+                if let Some(stream) = &stream {
+                    stream.set_input(&input);
+                }
+                record(BatchResult { input, output: None, status: BatchStatus::Failed, error: Some(error) });
+                continue;
+            }
+        };
+        let out_path = batch_output_path(
+            &input,
+            output_dir.as_deref().map(std::path::Path::new),
+            &name_template,
+            format,
+            &options.model,
+        );
+        let display = input.display().to_string();
+        let out_display = out_path.display().to_string();
+        if let Some(stream) = &stream {
+            stream.set_input(&display);
+        }
+        if skips_existing(&out_path, overwrite) {
+            note(format_args!("autosubs: [{}/{total}] {display}: skipped, {out_display} exists", i + 1));
+            record(BatchResult {
+                input: display,
+                output: Some(out_display),
+                status: BatchStatus::Skipped,
+                error: None,
+            });
+            continue;
+        }
+
+        note(format_args!("autosubs: [{}/{total}] {display}", i + 1));
+        emit("start", json!({ "model": options.model }));
+        if let Ok(mut last) = last_stage.lock() {
+            *last = None;
+        }
+        let file_options = FrontendTranscribeOptions { audio_path: display.clone(), ..options.clone() };
+        let result = transcribe_audio_with(app.clone(), file_options, &mut engine, None).await;
+        if is_tty {
+            eprintln!();
+        }
+
+        let written = result.and_then(|transcript| write_output(&finish(transcript), format, &export, &out_path));
+        match written {
+            Ok(()) => {
+                note(format_args!("autosubs: wrote {} to {out_display}", format.name()));
+                record(BatchResult {
+                    input: display,
+                    output: Some(out_display),
+                    status: BatchStatus::Ok,
+                    error: None,
+                });
+            }
+            Err(error) => {
+                note(format_args!("autosubs: {display}: {error}"));
+                record(BatchResult {
+                    input: display,
+                    output: Some(out_display),
+                    status: BatchStatus::Failed,
+                    error: Some(error),
+                });
+            }
+        }
+    }
+    for id in listeners {
+        events.unlisten(id);
+    }
+
+    report_batch(&results)
+}
+
+/// The transcription options from the flags in `m` over `profile`, shared by
+/// plain runs and `watch`. `audio_path` is left for the caller to fill in.
+fn transcribe_options(m: &Matches, profile: &Profile) -> FrontendTranscribeOptions {
     // GPU: default on (mirrors transcribe_audio's `.or(Some(true))`); --no-gpu wins.
-    let enable_gpu = if arg_flag(&m, "no-gpu") {
+    let enable_gpu = if arg_flag(m, "no-gpu") {
         Some(false)
-    } else if arg_flag(&m, "gpu") {
+    } else if arg_flag(m, "gpu") {
         Some(true)
     } else {
         None
     };
 
-    // Validate --density up front: an unknown value is a usage error rather than
-    // a silent fall back to the default density (which would produce wrapping the
-    // user didn't ask for). The accepted values match `TextDensity`'s serde repr.
-    let density = match arg_str(&m, "density") {
-        Some(s) => match parse_density(&s) {
+    let density = resolve_density(m).or_else(|| {
+        let s = profile.density.as_deref()?;
+        match parse_density(s) {
             Some(d) => Some(d),
-            None => {
-                eprintln!(
-                    "autosubs: unknown density '{s}' (expected less, standard, more, single, or custom)"
-                );
-                flush_and_exit(2);
+            None => profile_error(&format!("unknown density '{s}' (expected less, standard, more, single, or custom)")),
+        }
+    });
+
+    let translate = arg_flag(m, "translate") || profile.translate.unwrap_or(false);
+    let forced_alignment = arg_flag(m, "forced-alignment");
+
+    FrontendTranscribeOptions {
+        audio_path: String::new(),
+        offset: None,
+        model: arg_str(m, "model").or_else(|| profile.model.clone()).unwrap_or_else(|| "small".to_string()),
+        lang: arg_str(m, "lang").or_else(|| profile.lang.clone()),
+        translate: Some(translate),
+        target_language: arg_str(m, "target-language").or_else(|| profile.target_language.clone()),
+        enable_dtw: None,
+        enable_gpu,
+        enable_diarize: Some(arg_toggle(m, "diarize").or(profile.diarize).unwrap_or(false)),
+        enable_forced_alignment: Some(forced_alignment),
+        max_speakers: arg_num(m, "max-speakers").or(profile.max_speakers),
+        density,
+        max_lines: arg_num(m, "max-lines").or(profile.max_lines),
+        custom_max_chars_per_line: arg_num(m, "max-chars-per-line").or(profile.max_chars_per_line),
+        text_case: arg_str(m, "text-case").or_else(|| profile.text_case.clone()),
+        remove_punctuation: Some(arg_toggle(m, "remove-punctuation").or(profile.remove_punctuation).unwrap_or(false)),
+        censored_words: profile.censored_words.clone(),
+        custom_prompt: arg_str(m, "prompt").or_else(|| profile.prompt.clone()),
+        asr_model_path: None,
+        vad_model_path: None,
+        diarize_segment_path: None,
+        diarize_embedding_path: None,
+        aligner_model_dir: None,
+    }
+}
+
+/// The step applied to each finished transcript before it is rendered as
+/// `format`. SCC captions live on a 32-column grid, STL on 40-column teletext
+/// rows and LRC lyrics read one sung line per cue, so unless the user chose
+/// their own line layout, re-wrap the raw segments with the format's preset
+/// instead of the language default (which would otherwise be re-wrapped mid-cue).
+fn format_preset(options: &FrontendFormattingOptions, format: OutputFormat) -> impl Fn(Transcript) -> Transcript {
+    let custom_layout = options.text_density.is_some()
+        || options.max_lines.is_some()
+        || options.custom_max_chars_per_line.is_some();
+    let preset_format = match format {
+        OutputFormat::Export(f) if !custom_layout => Some(f),
+        _ => None,
+    };
+    let content = ContentFormatting {
+        text_case: parse_text_case(options.text_case.as_deref()),
+        remove_punctuation: options.remove_punctuation.unwrap_or(false),
+        censored_words: options.censored_words.clone().unwrap_or_default(),
+    };
+    move |mut transcript: Transcript| {
+        let preset = preset_format.and_then(|f| {
+            format_for_export(&transcript.original_segments, f, &transcript.language, &content)
+        });
+        if let Some(segments) = preset {
+            transcript.segments = segments;
+        }
+        transcript
+    }
+}
+
+/// The cue layout and content settings of `options`, as `reformat` takes them.
+fn formatting_options(options: &FrontendTranscribeOptions) -> FrontendFormattingOptions {
+    FrontendFormattingOptions {
+        language: options.lang.clone(),
+        max_lines: options.max_lines,
+        text_density: options.density.map(|d| format!("{d:?}")),
+        custom_max_chars_per_line: options.custom_max_chars_per_line,
+        text_case: options.text_case.clone(),
+        remove_punctuation: options.remove_punctuation,
+        censored_words: options.censored_words.clone(),
+    }
+}
+
+/// `autosubs reformat <transcript.json>`: rebuild the cues of a saved JSON
+/// transcript (`-f json` output) from its raw segments with new layout and
+/// content settings, and render them in any format. Like the GUI's
+/// `reformat_subtitles`, nothing is re-transcribed.
+fn run_reformat(m: Matches) -> ! {
+    let input = match arg_str(&m, "input") {
+        Some(p) => p,
+        None => {
+            eprintln!("autosubs: missing required <input> transcript JSON");
+            flush_and_exit(2);
+        }
+    };
+    let output = arg_str(&m, "output");
+    let format = match resolve_format(arg_str(&m, "format").as_deref(), output.as_deref(), None) {
+        Ok(f) => f,
+        Err(e) => {
+            eprintln!("autosubs: {e}");
+            flush_and_exit(2);
+        }
+    };
+    let export = resolve_export_settings(&m);
+
+    let content = std::fs::read_to_string(&input)
+        .unwrap_or_else(|e| fail(&format!("failed to read '{input}': {e}")));
+    let transcript = reformat_transcript(&m, &content, format).unwrap_or_else(|e| {
+        fail(&format!("'{input}' is not an AutoSubs JSON transcript (write one with -f json): {e}"))
+    });
+    write_transcript(&transcript, format, output.as_deref(), &export);
+    flush_and_exit(0);
+}
+
+/// The JSON transcript `content` with its cues rebuilt for `format` from the
+/// layout and content flags in `m`.
+fn reformat_transcript(m: &Matches, content: &str, format: OutputFormat) -> Result<Transcript, serde_json::Error> {
+    let mut transcript: Transcript = serde_json::from_str(content)?;
+    // Transcripts imported from subtitle files have the cues as their raw segments.
+    if transcript.original_segments.is_empty() {
+        transcript.original_segments = transcript.segments.clone();
+    }
+
+    let options = FrontendFormattingOptions {
+        language: Some(transcript.language.clone()),
+        max_lines: arg_num(m, "max-lines"),
+        text_density: resolve_density(m).map(|d| format!("{d:?}")),
+        custom_max_chars_per_line: arg_num(m, "max-chars-per-line"),
+        text_case: arg_str(m, "text-case"),
+        remove_punctuation: Some(arg_flag(m, "remove-punctuation")),
+        censored_words: None,
+    };
+    // As when transcribing, SCC, STL and LRC then get their format's line
+    // layout unless the user chose one.
+    transcript.segments = reformat_segments(&transcript.original_segments, &options);
+    Ok(format_preset(&options, format)(transcript))
+}
+
+/// `autosubs convert <file>`: parse an SRT, VTT or ASS file and re-render it in
+/// another format. Nothing is transcribed, so this needs no models or ffmpeg.
+fn run_convert(m: Matches) -> ! {
+    let input = match arg_str(&m, "input") {
+        Some(p) => p,
+        None => {
+            eprintln!("autosubs: missing required <input> subtitle file");
+            flush_and_exit(2);
+        }
+    };
+    let output = arg_str(&m, "output");
+    let format = match resolve_format(arg_str(&m, "format").as_deref(), output.as_deref(), None) {
+        Ok(f) => f,
+        Err(e) => {
+            eprintln!("autosubs: {e}");
+            flush_and_exit(2);
+        }
+    };
+    let export = resolve_export_settings(&m);
+
+    let content = std::fs::read_to_string(&input)
+        .unwrap_or_else(|e| fail(&format!("failed to read '{input}': {e}")));
+    let Some(input_format) = SubtitleFormat::from_path(std::path::Path::new(&input))
+        .filter(|f| f.importable())
+        .or_else(|| transcription_engine::formats::detect(&content))
+    else {
+        fail(&format!("'{input}' is not an srt, vtt, or ass file"));
+    };
+    let transcript = import_transcript(&content, input_format)
+        .unwrap_or_else(|e| fail(&format!("failed to parse '{input}': {e}")));
+    write_transcript(&transcript, format, output.as_deref(), &export);
+    flush_and_exit(0);
+}
+
+// --- model management ---
+
+/// `autosubs models list|info|pull|rm`: inspect and manage the model cache from
+/// the embedded manifest, so models can be fetched ahead of a job.
+async fn run_models<R: Runtime>(app: AppHandle<R>, m: Matches) -> ! {
+    let Some(sub) = m.subcommand else {
+        eprintln!("autosubs: expected a models subcommand: list, info, pull, or rm");
+        flush_and_exit(2);
+    };
+    let sub = *sub;
+    if let Some(text) = help_text(&sub.matches) {
+        println!("{text}");
+        flush_and_exit(0);
+    }
+    let cache_dir = crate::models::get_cache_dir(app).unwrap_or_else(|e| fail(&e));
+    let manager = ModelManager::new(cache_dir);
+    let json = arg_flag(&sub.matches, "json");
+    match sub.name.as_str() {
+        "list" => models_list(&manager, json),
+        "info" => models_info(&manager, &sub.matches, json),
+        "pull" => models_pull(&manager, &sub.matches, json).await,
+        "rm" => models_rm(&manager, &sub.matches, json),
+        other => {
+            eprintln!("autosubs: unknown models subcommand '{other}'");
+            flush_and_exit(2);
+        }
+    }
+}
+
+/// A downloadable model as reported by `autosubs models`: a transcription model,
+/// or the auxiliary diarization or alignment model.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ModelInfo {
+    id: String,
+    /// `transcription`, `diarization` or `alignment`.
+    kind: &'static str,
+    engine: Option<String>,
+    /// Download size, as listed in the manifest.
+    size: Option<String>,
+    ram_mb: Option<u32>,
+    language_support: Option<LanguageSupport>,
+    license: Option<ModelLicense>,
+    /// Every file is present and passes validation.
+    cached: bool,
+}
+
+fn model_info(
+    id: &str,
+    kind: &'static str,
+    engine: Option<manifest::Engine>,
+    ui: Option<&Ui>,
+    license: Option<&ModelLicense>,
+    cached: &[String],
+) -> ModelInfo {
+    ModelInfo {
+        id: id.to_string(),
+        kind,
+        engine: engine.and_then(|e| serde_json::to_value(e).ok()?.as_str().map(str::to_string)),
+        size: ui.map(|u| u.size.clone()),
+        ram_mb: ui.and_then(|u| u.ram_mb),
+        language_support: ui.map(|u| u.language_support.clone()),
+        license: license.cloned(),
+        cached: cached.iter().any(|c| c == id),
+    }
+}
+
+/// Every model in the manifest, in manifest order, with its cache state.
+fn model_catalog(manager: &ModelManager) -> Vec<ModelInfo> {
+    let cached = manager
+        .list_cached_models()
+        .unwrap_or_else(|e| fail(&format!("failed to read the model cache: {e}")));
+    let mut models: Vec<ModelInfo> = manifest::MANIFEST
+        .models
+        .iter()
+        .map(|e| model_info(&e.id, "transcription", Some(e.engine), e.ui.as_ref(), e.license.as_ref(), &cached))
+        .collect();
+    let diarize = manifest::diarize();
+    models.push(model_info(&diarize.id, "diarization", None, diarize.ui.as_ref(), None, &cached));
+    let aligner = manifest::aligner();
+    models.push(model_info(&aligner.id, "alignment", None, Some(&aligner.ui), Some(&aligner.license), &cached));
+    models
+}
+
+/// The `<model>` arguments, each checked against the manifest. No ids, or an
+/// unknown one, is a usage error.
+fn model_args(m: &Matches, catalog: &[ModelInfo]) -> Vec<String> {
+    let ids = arg_list(m, "model");
+    if ids.is_empty() {
+        eprintln!("autosubs: missing required <model> id (see `autosubs models list`)");
+        flush_and_exit(2);
+    }
+    for id in &ids {
+        if !catalog.iter().any(|info| &info.id == id) {
+            eprintln!("autosubs: unknown model '{id}' (see `autosubs models list`)");
+            flush_and_exit(2);
+        }
+    }
+    ids
+}
+
+fn languages_label(support: Option<&LanguageSupport>, limit: usize) -> String {
+    match support {
+        None => "-".into(),
+        Some(LanguageSupport::Multilingual) => "multilingual".into(),
+        Some(LanguageSupport::SingleLanguage { language }) => language.clone(),
+        Some(LanguageSupport::Restricted { languages }) if languages.len() <= limit => languages.join(", "),
+        Some(LanguageSupport::Restricted { languages }) => format!("{} languages", languages.len()),
+    }
+}
+
+fn print_json(value: &impl Serialize) {
+    println!("{}", serde_json::to_string_pretty(value).unwrap_or_default());
+}
+
+fn models_list(manager: &ModelManager, json: bool) -> ! {
+    let catalog = model_catalog(manager);
+    if json {
+        print_json(&catalog);
+        flush_and_exit(0);
+    }
+    println!(
+        "{:<22} {:<12} {:>7} {:>8}  {:<16} {:<14} CACHED",
+        "ID", "ENGINE", "SIZE", "RAM", "LANGUAGES", "LICENSE"
+    );
+    for info in &catalog {
+        println!(
+            "{:<22} {:<12} {:>7} {:>8}  {:<16} {:<14} {}",
+            info.id,
+            info.engine.as_deref().unwrap_or(info.kind),
+            info.size.as_deref().unwrap_or("-"),
+            info.ram_mb.map(|mb| format!("{mb} MB")).unwrap_or_else(|| "-".into()),
+            languages_label(info.language_support.as_ref(), 3),
+            info.license.as_ref().map_or("-", |l| l.spdx.as_str()),
+            if info.cached { "yes" } else { "no" },
+        );
+    }
+    flush_and_exit(0);
+}
+
+fn models_info(manager: &ModelManager, m: &Matches, json: bool) -> ! {
+    let catalog = model_catalog(manager);
+    let ids = model_args(m, &catalog);
+    let infos: Vec<&ModelInfo> = ids.iter().filter_map(|id| catalog.iter().find(|info| &info.id == id)).collect();
+    if json {
+        match infos.as_slice() {
+            [info] => print_json(info),
+            _ => print_json(&infos),
+        }
+        flush_and_exit(0);
+    }
+    for (i, info) in infos.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        println!("id:         {}", info.id);
+        println!("kind:       {}", info.kind);
+        if let Some(engine) = &info.engine {
+            println!("engine:     {engine}");
+        }
+        println!("size:       {}", info.size.as_deref().unwrap_or("unknown"));
+        if let Some(mb) = info.ram_mb {
+            println!("ram:        {mb} MB");
+        }
+        println!("languages:  {}", languages_label(info.language_support.as_ref(), usize::MAX));
+        match &info.license {
+            Some(l) => {
+                println!("license:    {} ({})", l.spdx, l.url);
+                println!("commercial: {}", if l.commercial_use { "yes" } else { "no" });
+                println!("credit:     {}", l.attribution);
             }
-        },
-        None => None,
+            None => println!("license:    not listed"),
+        }
+        println!("cached:     {}", if info.cached { "yes" } else { "no" });
+    }
+    flush_and_exit(0);
+}
+
+/// Outcome of `models pull` or `models rm` for one model.
+#[derive(Serialize)]
+struct ModelAction {
+    id: String,
+    /// `downloaded`, `cached`, `removed`, `not_cached` or `failed`.
+    status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Print the per-model outcomes (JSON on stdout with `--json`, lines on stderr
+/// otherwise) and exit 1 if any failed.
+fn report_model_actions(actions: &[ModelAction], json: bool) -> ! {
+    if json {
+        print_json(&actions);
+    } else {
+        for a in actions {
+            match &a.error {
+                Some(e) => eprintln!("autosubs: {}: {} ({e})", a.id, a.status),
+                None => eprintln!("autosubs: {}: {}", a.id, a.status.replace('_', " ")),
+            }
+        }
+    }
+    let failed = actions.iter().any(|a| a.status == "failed");
+    flush_and_exit(if failed { 1 } else { 0 });
+}
+
+/// Download one model (or the diarization/alignment bundle) into the cache.
+async fn pull_model(manager: &ModelManager, id: &str, progress: &LabeledProgressFn) -> Result<(), String> {
+    let result = if id == manifest::diarize().id {
+        manager.ensure_diarize_models(Some(progress), None).await.map(drop)
+    } else if id == manifest::aligner().id {
+        manager.ensure_aligner_model(Some(progress), None).await.map(drop)
+    } else {
+        let Some(entry) = manifest::get(id) else {
+            return Err(format!("unknown model '{id}'"));
+        };
+        manager.ensure_model(entry, Some(progress), None).await.map(drop)
     };
+    result.map_err(|e| e.to_string())
+}
+
+async fn models_pull(manager: &ModelManager, m: &Matches, json: bool) -> ! {
+    let catalog = model_catalog(manager);
+    let ids = model_args(m, &catalog);
+    let show_bar = std::io::stderr().is_terminal();
+    let mut actions = Vec::new();
+    for id in ids {
+        if catalog.iter().any(|info| info.id == id && info.cached) {
+            actions.push(ModelAction { id, status: "cached", error: None });
+            continue;
+        }
+        let progress = move |percent: i32, _: ProgressType, _: &str| {
+            if show_bar {
+                eprint!("{}", render_bar("Prepare", percent));
+            }
+        };
+        if !show_bar {
+            eprintln!("autosubs: downloading {id}");
+        }
+        let result = pull_model(manager, &id, &progress).await;
+        if show_bar {
+            eprintln!();
+        }
+        // Re-check the cache so a download that left partial or corrupt files
+        // is reported as a failure rather than a success.
+        let verified = result.is_ok() && model_catalog(manager).iter().any(|info| info.id == id && info.cached);
+        let action = match result {
+            Err(e) => ModelAction { id, status: "failed", error: Some(e) },
+            Ok(()) if verified => ModelAction { id, status: "downloaded", error: None },
+            Ok(()) => ModelAction { id, status: "failed", error: Some("downloaded files failed validation".into()) },
+        };
+        actions.push(action);
+    }
+    report_model_actions(&actions, json)
+}
+
+fn models_rm(manager: &ModelManager, m: &Matches, json: bool) -> ! {
+    let catalog = model_catalog(manager);
+    let ids = model_args(m, &catalog);
+    let mut actions = Vec::new();
+    for id in ids {
+        let action = if !catalog.iter().any(|info| info.id == id && info.cached) {
+            ModelAction { id, status: "not_cached", error: None }
+        } else if manager.delete_cached_model(&id) {
+            ModelAction { id, status: "removed", error: None }
+        } else {
+            ModelAction { id, status: "failed", error: Some("could not delete the cached files".into()) }
+        };
+        actions.push(action);
+    }
+    report_model_actions(&actions, json)
+}
+
+/// Render `transcript` in `format` as file contents. Text formats end with a
+/// newline.
+fn render_transcript(transcript: &Transcript, format: OutputFormat, export: &ExportOptions) -> Result<Vec<u8>, String> {
+    let json = |value: Result<String, serde_json::Error>| {
+        value
+            .map(String::into_bytes)
+            .map_err(|e| format!("failed to serialize transcript: {e}"))
+    };
+    let mut rendered = match format {
+        OutputFormat::Json => json(serde_json::to_string_pretty(transcript))?,
+        OutputFormat::VerboseJson => json(serde_json::to_string_pretty(&transcript.to_verbose_json()))?,
+        OutputFormat::WhisperX => json(serde_json::to_string_pretty(&transcript.to_whisperx()))?,
+        OutputFormat::Export(format) => {
+            let mut opts = export_options(
+                &transcript.segments,
+                &transcript.speakers,
+                Some(&transcript.language),
+            );
+            opts.karaoke = export.karaoke;
+            opts.frame_rate = export.frame_rate;
+            opts.roll_up = export.roll_up;
+            opts.start_frame = export.start_frame;
+            opts.word_rows = export.word_rows;
+            opts.media_url = export.media_url.clone();
+            opts.paragraph_timecodes = export.paragraph_timecodes;
+            // Word rows list what the engine heard, not the reflowed cues.
+            let word_table = export.word_rows && matches!(format, SubtitleFormat::Csv | SubtitleFormat::Tsv);
+            let segments = if word_table { transcript.source_segments() } else { &transcript.segments };
+            render_export(segments, format, &opts)
+        }
+    };
+    if !format.is_binary() && !rendered.ends_with(b"\n") {
+        rendered.push(b'\n');
+    }
+    Ok(rendered)
+}
+
+/// Render `transcript` to the file at `path`, creating its directory.
+fn write_output(
+    transcript: &Transcript,
+    format: OutputFormat,
+    export: &ExportOptions,
+    path: &std::path::Path,
+) -> Result<(), String> {
+    let rendered = render_transcript(transcript, format, export)?;
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir).map_err(|e| format!("failed to create '{}': {e}", dir.display()))?;
+    }
+    std::fs::write(path, rendered).map_err(|e| format!("failed to write '{}': {e}", path.display()))
+}
+
+/// Render `transcript` in `format` and write it to `output` (or stdout). Exits
+/// on failure.
+fn write_transcript(transcript: &Transcript, format: OutputFormat, output: Option<&str>, export: &ExportOptions) {
+    let rendered = render_transcript(transcript, format, export).unwrap_or_else(|e| fail(&e));
+    match output {
+        Some(path) => {
+            if let Err(e) = std::fs::write(path, &rendered) {
+                fail(&format!("failed to write '{path}': {e}"));
+            }
+            note(format_args!("autosubs: wrote {} to {path}", format.name()));
+        }
+        None if format.is_binary() && std::io::stdout().is_terminal() => {
+            eprintln!("autosubs: {} is a binary format; write it to a file with -o", format.name());
+            flush_and_exit(2);
+        }
+        None => {
+            if let Err(e) = std::io::stdout().lock().write_all(&rendered) {
+                fail(&format!("failed to write output: {e}"));
+            }
+        }
+    }
+}
+
+// --- server ---
+
+/// `autosubs serve`: run the local HTTP transcription server (see
+/// `crate::server`) until the process is killed.
+async fn run_serve<R: Runtime>(app: AppHandle<R>, m: Matches) -> ! {
+    let host = arg_str(&m, "host").unwrap_or_else(|| "127.0.0.1".into());
+    let port = match arg_str(&m, "port") {
+        None => 8000,
+        Some(p) => p.parse::<u16>().unwrap_or_else(|_| {
+            eprintln!("autosubs: invalid port '{p}'");
+            flush_and_exit(2);
+        }),
+    };
+    let model = arg_str(&m, "model").unwrap_or_else(|| "small".into());
+    if manifest::get(&model).is_none() {
+        eprintln!("autosubs: unknown model '{model}' (see `autosubs models list`)");
+        flush_and_exit(2);
+    }
+    let addr: std::net::SocketAddr = match std::net::ToSocketAddrs::to_socket_addrs(&(host.as_str(), port)).ok().and_then(|mut a| a.next()) {
+        Some(addr) => addr,
+        None => {
+            eprintln!("autosubs: invalid host '{host}'");
+            flush_and_exit(2);
+        }
+    };
+    // There is no authentication, so anything beyond loopback is the user's call.
+    if !addr.ip().is_loopback() {
+        eprintln!("autosubs: warning: serving on {addr} without authentication; anyone who can reach it can transcribe");
+    }
+    match crate::server::serve(app, addr, model).await {
+        Ok(()) => flush_and_exit(0),
+        Err(e) => fail(&e),
+    }
+}
+
+// --- profiles ---
+
+/// A named set of transcription options from the config file, applied with
+/// `--profile NAME`. Keys are the matching flag names; any flag given on the
+/// command line overrides the profile's value.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct Profile {
+    model: Option<String>,
+    lang: Option<String>,
+    density: Option<String>,
+    max_lines: Option<usize>,
+    max_chars_per_line: Option<usize>,
+    text_case: Option<String>,
+    remove_punctuation: Option<bool>,
+    /// Words to mask in the output; there is no flag for this.
+    censored_words: Option<Vec<String>>,
+    diarize: Option<bool>,
+    max_speakers: Option<usize>,
+    translate: Option<bool>,
+    target_language: Option<String>,
+    format: Option<String>,
+    prompt: Option<String>,
+}
+
+/// The CLI config file: `[profiles.NAME]` tables in TOML, or a `"profiles"`
+/// object in JSON.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CliConfig {
+    #[serde(default)]
+    profiles: std::collections::BTreeMap<String, Profile>,
+}
+
+/// Config file names looked up in the app config directory, in order.
+const CONFIG_FILES: &[&str] = &["cli.toml", "cli.json"];
+
+/// The config file: `--config`, else the first of [`CONFIG_FILES`] that exists
+/// in the app config directory.
+fn config_path<R: Runtime>(app: &AppHandle<R>, m: &Matches) -> Option<std::path::PathBuf> {
+    if let Some(path) = arg_str(m, "config") {
+        return Some(path.into());
+    }
+    let dir = app.path().app_config_dir().ok()?;
+    CONFIG_FILES.iter().map(|name| dir.join(name)).find(|path| path.is_file())
+}
+
+/// Load the `--profile` named on the command line (an empty profile without
+/// one), along with the config file it came from. A missing file or profile,
+/// or a malformed file, is a usage error.
+fn load_profile<R: Runtime>(app: &AppHandle<R>, m: &Matches) -> (Option<std::path::PathBuf>, Profile) {
+    let path = config_path(app, m);
+    let Some(name) = arg_str(m, "profile") else {
+        return (path, Profile::default());
+    };
+    let Some(path) = path else {
+        profile_error(&format!(
+            "--profile {name} needs a config file: pass --config or create {} in the app config directory",
+            CONFIG_FILES.join(" or ")
+        ));
+    };
+    match read_profile(&path, &name) {
+        Ok(profile) => (Some(path), profile),
+        Err(e) => profile_error(&e),
+    }
+}
+
+/// Profile `name` from the config file at `path`, read as JSON for a `.json`
+/// file and as TOML otherwise.
+fn read_profile(path: &std::path::Path, name: &str) -> Result<Profile, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("failed to read '{}': {e}", path.display()))?;
+    let is_json = path.extension().is_some_and(|e| e.eq_ignore_ascii_case("json"));
+    let parsed = if is_json {
+        serde_json::from_str::<CliConfig>(&text).map_err(|e| e.to_string())
+    } else {
+        toml::from_str::<CliConfig>(&text).map_err(|e| e.to_string())
+    };
+    let mut config = parsed.map_err(|e| format!("invalid config '{}': {e}", path.display()))?;
+    config.profiles.remove(name).ok_or_else(|| {
+        let known: Vec<&str> = config.profiles.keys().map(String::as_str).collect();
+        format!(
+            "no profile '{name}' in '{}' (profiles: {})",
+            path.display(),
+            if known.is_empty() { "none".to_string() } else { known.join(", ") }
+        )
+    })
+}
+
+/// What `--print-config` shows: the merged options and where they came from.
+fn config_report(
+    config_path: Option<&std::path::Path>,
+    profile: Option<String>,
+    format: OutputFormat,
+    options: &FrontendTranscribeOptions,
+) -> serde_json::Value {
+    json!({
+        "config": config_path.map(|p| p.display().to_string()),
+        "profile": profile,
+        "format": format.name(),
+        "options": options,
+    })
+}
+
+fn profile_error(message: &str) -> ! {
+    eprintln!("autosubs: {message}");
+    flush_and_exit(2);
+}
+
+// --- batch transcription ---
+
+/// Extensions picked up when an input is a directory.
+const MEDIA_EXTENSIONS: &[&str] = &[
+    "wav", "mp3", "m4a", "aac", "flac", "ogg", "oga", "opus", "wma", "aif", "aiff", // audio
+    "mp4", "m4v", "mov", "mkv", "webm", "avi", "wmv", "mpg", "mpeg", "mts", "m2ts", "mxf", // video
+];
+
+fn is_glob(input: &str) -> bool {
+    input.contains(['*', '?', '['])
+}
+
+/// Expand the command-line inputs into the files to transcribe, in order and
+/// without duplicates. Directories contribute their media files (recursively,
+/// sorted by path) and globs their matches. Inputs that match nothing come back
+/// as `Err((input, reason))` so they are reported as failures.
+fn expand_inputs(inputs: &[String]) -> Vec<Result<std::path::PathBuf, (String, String)>> {
+    fn walk(dir: &std::path::Path, out: &mut Vec<std::path::PathBuf>) {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return;
+        };
+        let mut paths: Vec<_> = entries
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| !p.file_name().is_some_and(|n| n.to_string_lossy().starts_with('.')))
+            .collect();
+        paths.sort();
+        for path in paths {
+            if path.is_dir() {
+                walk(&path, out);
+            } else if path
+                .extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| MEDIA_EXTENSIONS.contains(&e.to_ascii_lowercase().as_str()))
+            {
+                out.push(path);
+            }
+        }
+    }
+
+    let mut seen = std::collections::HashSet::new();
+    let mut files = Vec::new();
+    for input in inputs {
+        let mut found = Vec::new();
+        if is_glob(input) {
+            match glob::glob(input) {
+                Ok(paths) => {
+                    for path in paths.flatten() {
+                        if path.is_dir() {
+                            walk(&path, &mut found);
+                        } else {
+                            found.push(path);
+                        }
+                    }
+                }
+                Err(e) => {
+                    files.push(Err((input.clone(), format!("invalid pattern: {e}"))));
+                    continue;
+                }
+            }
+        } else {
+            let path = std::path::PathBuf::from(input);
+            if path.is_dir() {
+                walk(&path, &mut found);
+            } else if path.exists() {
+                found.push(path);
+            } else {
+                files.push(Err((input.clone(), "no such file or directory".to_string())));
+                continue;
+            }
+        }
+        if found.is_empty() {
+            files.push(Err((input.clone(), "no media files found".to_string())));
+        }
+        for path in found {
+            if seen.insert(path.clone()) {
+                files.push(Ok(path));
+            }
+        }
+    }
+    files
+}
+
+/// Where a batch writes the output for `input`: `--name` (default
+/// `{stem}.{ext}`) in `--output-dir`, or next to the input. The template
+/// expands `{stem}` (input file name without extension), `{ext}` (the output
+/// format's extension), `{model}` and `{parent}` (the input's directory name).
+fn batch_output_path(
+    input: &std::path::Path,
+    output_dir: Option<&std::path::Path>,
+    template: &str,
+    format: OutputFormat,
+    model: &str,
+) -> std::path::PathBuf {
+    let stem = input.file_stem().map(|s| s.to_string_lossy()).unwrap_or_default();
+    let parent = input.parent().and_then(|p| p.file_name()).map(|p| p.to_string_lossy()).unwrap_or_default();
+    let name = template
+        .replace("{stem}", &stem)
+        .replace("{ext}", format.extension())
+        .replace("{model}", model)
+        .replace("{parent}", &parent);
+    match output_dir {
+        Some(dir) => dir.join(name),
+        None => input.with_file_name(name),
+    }
+}
+
+/// Whether a batch leaves `out_path` alone: it exists and `--overwrite` wasn't given.
+fn skips_existing(out_path: &std::path::Path, overwrite: bool) -> bool {
+    out_path.exists() && !overwrite
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum BatchStatus {
+    Ok,
+    Skipped,
+    Failed,
+}
 
-    let translate = arg_flag(&m, "translate");
-    let forced_alignment = arg_flag(&m, "forced-alignment");
-    if translate && forced_alignment {
-        eprintln!("autosubs: --forced-alignment cannot be used with --translate");
-        flush_and_exit(2);
+/// One line of the batch summary.
+#[derive(Serialize)]
+struct BatchResult {
+    input: String,
+    output: Option<String>,
+    status: BatchStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Print the batch summary (JSON on stdout, counts on stderr) and exit: 0 when
+/// every input was transcribed or skipped, 1 if any failed.
+fn report_batch(results: &[BatchResult]) -> ! {
+    let count = |status| results.iter().filter(|r| r.status == status).count();
+    let (ok, skipped, failed) = (count(BatchStatus::Ok), count(BatchStatus::Skipped), count(BatchStatus::Failed));
+    let summary = json!({ "transcribed": ok, "skipped": skipped, "failed": failed, "files": results });
+    println!("{}", serde_json::to_string_pretty(&summary).unwrap_or_default());
+    note(format_args!("autosubs: {ok} transcribed, {skipped} skipped, {failed} failed"));
+    for r in results.iter().filter(|r| r.status == BatchStatus::Failed) {
+        note(format_args!("autosubs: failed: {}: {}", r.input, r.error.as_deref().unwrap_or("unknown error")));
     }
-    if forced_alignment {
-        eprintln!(
-            "autosubs: MMS forced-alignment weights may be downloaded (317MB); CC BY-NC 4.0, noncommercial use"
-        );
+    flush_and_exit(batch_exit_code(results));
+}
+
+/// 0 when every input was transcribed or skipped, 1 if any failed.
+fn batch_exit_code(results: &[BatchResult]) -> i32 {
+    if results.iter().any(|r| r.status == BatchStatus::Failed) { 1 } else { 0 }
+}
+
+// --- watch folder ---
+
+/// File in the output directory recording what `watch` has processed.
+const WATCH_STATE_FILE: &str = ".autosubs-watch.json";
+
+/// An input `watch` has finished with, keyed in [`WatchState`] by its path
+/// relative to the watched directory. The size and modification time are those
+/// it was transcribed at, so a file replaced with new content is picked up again.
+#[derive(Serialize, Deserialize)]
+struct WatchedFile {
+    size: u64,
+    modified: u64,
+    status: BatchStatus,
+    output: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    /// Why a failed input is still in the watched folder: it couldn't be moved
+    /// to the error directory.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    move_error: Option<String>,
+    processed_at: String,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct WatchState {
+    files: std::collections::BTreeMap<String, WatchedFile>,
+}
+
+impl WatchState {
+    /// The saved state, or an empty one on the first run. An unreadable state
+    /// file is an error rather than a fresh start, which would redo everything.
+    fn load(path: &std::path::Path) -> Result<Self, String> {
+        match std::fs::read_to_string(path) {
+            Ok(text) => serde_json::from_str(&text).map_err(|e| format!("invalid watch state '{}': {e}", path.display())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(format!("failed to read '{}': {e}", path.display())),
+        }
     }
 
-    let options = FrontendTranscribeOptions {
-        audio_path: input,
-        offset: None,
-        model: arg_str(&m, "model").unwrap_or_else(|| "small".to_string()),
-        lang: arg_str(&m, "lang"),
-        translate: Some(translate),
-        target_language: arg_str(&m, "target-language"),
-        enable_dtw: None,
-        enable_gpu,
-        enable_diarize: Some(arg_flag(&m, "diarize")),
-        enable_forced_alignment: Some(forced_alignment),
-        max_speakers: arg_num(&m, "max-speakers"),
-        density,
-        max_lines: arg_num(&m, "max-lines"),
-        custom_max_chars_per_line: arg_num(&m, "max-chars-per-line"),
-        text_case: arg_str(&m, "text-case"),
-        remove_punctuation: Some(arg_flag(&m, "remove-punctuation")),
-        censored_words: None,
-        custom_prompt: arg_str(&m, "prompt"),
-        asr_model_path: None,
-        vad_model_path: None,
-        diarize_segment_path: None,
-        diarize_embedding_path: None,
-        aligner_model_dir: None,
-    };
+    /// Write through a temporary file so an interrupted save can't lose the state.
+    fn save(&self, path: &std::path::Path) -> Result<(), String> {
+        let text = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, text)
+            .and_then(|()| std::fs::rename(&tmp, path))
+            .map_err(|e| format!("failed to write '{}': {e}", path.display()))
+    }
 
-    let output = arg_str(&m, "output");
+    /// Whether `key` was handled at this size and modification time: transcribed,
+    /// or failed and left where it is because it couldn't be moved out (until it
+    /// changes, retrying would only fail it again). A failed file that was moved
+    /// is not: put back in the folder (a move keeps its modification time), it is
+    /// queued again.
+    fn is_done(&self, key: &str, size: u64, modified: u64) -> bool {
+        self.files.get(key).is_some_and(|f| {
+            f.size == size
+                && f.modified == modified
+                && (f.status == BatchStatus::Ok || f.move_error.is_some())
+        })
+    }
+}
 
-    // Resolve the output format up front so a bad value fails before doing work.
-    let format = match resolve_format(arg_str(&m, "format").as_deref(), output.as_deref()) {
+/// `autosubs watch <dir>`: transcribe media as it lands in `dir`. A file is
+/// picked up once its size and modification time have held still for
+/// `--settle` seconds, so copies still in progress are left alone. Outputs go
+/// to `--output-dir` (default: `<dir>-transcripts` beside `dir`), mirroring the
+/// input's subdirectories; a file that fails is moved to `--error-dir` (default
+/// `<dir>-failed`) with a `.log` beside it. Runs until killed, or with `--once`
+/// until the files already there are done.
+async fn run_watch<R: Runtime>(app: AppHandle<R>, m: Matches) -> ! {
+    let Some(dir) = arg_str(&m, "dir") else {
+        eprintln!("autosubs: missing required <dir> to watch");
+        flush_and_exit(2);
+    };
+    let dir = match std::fs::canonicalize(&dir) {
+        Ok(d) if d.is_dir() => d,
+        _ => {
+            eprintln!("autosubs: '{dir}' is not a directory");
+            flush_and_exit(2);
+        }
+    };
+    let (_, profile) = load_profile(&app, &m);
+    let options = transcribe_options(&m, &profile);
+    let format = match resolve_format(arg_str(&m, "format").as_deref(), None, profile.format.as_deref().or(Some("srt"))) {
         Ok(f) => f,
         Err(e) => {
             eprintln!("autosubs: {e}");
             flush_and_exit(2);
         }
     };
-
-    // Progress reporting on stderr. `transcribe_audio` already emits
-    // `labeled-progress` events ({progress, type, label}); we render them as a live
-    // bar on a TTY, or as one line per high-level stage when stderr is piped (so an
-    // agent capturing stderr gets clean, non-spammy output). stdout stays JSON-only.
-    let is_tty = std::io::stderr().is_terminal();
-    eprintln!("autosubs: starting (model={})", options.model);
-
-    let events = app.clone();
-    let last_stage: Arc<std::sync::Mutex<Option<String>>> = Arc::new(std::sync::Mutex::new(None));
-    let last_stage_cb = last_stage.clone();
-    let listener_id = events.listen("labeled-progress", move |event| {
-        let Ok(ev) = serde_json::from_str::<ProgressEvent>(event.payload()) else {
-            return;
-        };
-        let stage = ev.stage.as_deref().unwrap_or("");
-        // Ignore the initial null-stage tick (before the engine sets a real stage).
-        if stage.is_empty() {
-            return;
-        }
-        if is_tty {
-            let mut err = std::io::stderr();
-            let _ = write!(err, "{}", render_bar(stage, ev.progress));
-            let _ = err.flush();
-        } else if let Ok(mut last) = last_stage_cb.lock() {
-            if last.as_deref() != Some(stage) {
-                eprintln!("autosubs: {}...", stage_label(stage).to_lowercase());
-                *last = Some(stage.to_string());
+    let seconds = |name: &str, default: f64| match arg_str(&m, name) {
+        None => std::time::Duration::from_secs_f64(default),
+        Some(s) => match s.parse::<f64>() {
+            Ok(v) if v > 0.0 && v.is_finite() => std::time::Duration::from_secs_f64(v),
+            _ => {
+                eprintln!("autosubs: --{name} must be a positive number of seconds");
+                flush_and_exit(2);
             }
+        },
+    };
+    let interval = seconds("interval", 2.0);
+    let settle = seconds("settle", 5.0);
+    let once = arg_flag(&m, "once");
+    let export = resolve_export_settings(&m);
+    let name_template = arg_str(&m, "name").unwrap_or_else(|| "{stem}.{ext}".to_string());
+    let finish = format_preset(&formatting_options(&options), format);
+
+    let sibling = |suffix: &str| {
+        let name = dir.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_else(|| "autosubs".into());
+        dir.with_file_name(format!("{name}-{suffix}"))
+    };
+    let output_dir = arg_str(&m, "output-dir").map(std::path::PathBuf::from).unwrap_or_else(|| sibling("transcripts"));
+    let error_dir = arg_str(&m, "error-dir").map(std::path::PathBuf::from).unwrap_or_else(|| sibling("failed"));
+    for d in [&output_dir, &error_dir] {
+        if let Err(e) = std::fs::create_dir_all(d) {
+            fail(&format!("failed to create '{}': {e}", d.display()));
         }
-    });
-
-    let result = transcribe_audio(app, options).await;
-
-    events.unlisten(listener_id);
-    if is_tty {
-        // Terminate the in-place bar line so later output isn't overwritten.
-        eprintln!();
     }
-
-    match result {
-        Ok(transcript) => {
-            let mut rendered = match format {
-                OutputFormat::Json => serde_json::to_string_pretty(&transcript)
-                    .unwrap_or_else(|e| fail(&format!("failed to serialize transcript: {e}"))),
-                OutputFormat::Text => render_text(&transcript),
-                OutputFormat::Srt => render_srt(&transcript),
-                OutputFormat::Vtt => render_vtt(&transcript),
+    // Canonical, so the watched tree can skip them if they sit inside it.
+    let output_dir = std::fs::canonicalize(&output_dir).unwrap_or(output_dir);
+    let error_dir = std::fs::canonicalize(&error_dir).unwrap_or(error_dir);
+    let state_path = output_dir.join(WATCH_STATE_FILE);
+    let mut state = WatchState::load(&state_path).unwrap_or_else(|e| fail(&e));
+
+    note(format_args!(
+        "autosubs: watching {} (model={}, outputs in {}, failures in {})",
+        dir.display(),
+        options.model,
+        output_dir.display(),
+        error_dir.display()
+    ));
+
+    // Files seen but not yet settled: their last size and modification time,
+    // and when those were first seen.
+    let mut pending: std::collections::HashMap<std::path::PathBuf, (u64, u64, std::time::Instant)> =
+        std::collections::HashMap::new();
+    let mut engine = None;
+    let mut failed = 0usize;
+    let watched = dir.display().to_string();
+    loop {
+        let mut ready = Vec::new();
+        let files = expand_inputs(std::slice::from_ref(&watched)).into_iter().filter_map(Result::ok);
+        for path in files.filter(|p| !p.starts_with(&output_dir) && !p.starts_with(&error_dir)) {
+            let Ok(meta) = std::fs::metadata(&path) else {
+                continue;
             };
-            if !rendered.ends_with('\n') {
-                rendered.push('\n');
+            let size = meta.len();
+            let modified = meta
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                .map(|d| d.as_secs())
+                .unwrap_or_default();
+            let key = watch_key(&dir, &path);
+            if state.is_done(&key, size, modified) {
+                pending.remove(&path);
+                continue;
             }
-            match output {
-                Some(path) => {
-                    if let Err(e) = std::fs::write(&path, &rendered) {
-                        fail(&format!("failed to write '{path}': {e}"));
+            match pending.get(&path) {
+                Some(&(s, t, since)) if s == size && t == modified => {
+                    if since.elapsed() >= settle {
+                        ready.push((path, key, size, modified));
                     }
-                    eprintln!("autosubs: wrote {} to {path}", format.name());
                 }
-                None => print!("{rendered}"),
+                _ => {
+                    pending.insert(path, (size, modified, std::time::Instant::now()));
+                }
             }
-            flush_and_exit(0);
         }
-        Err(e) => fail(&e),
+        pending.retain(|path, _| path.exists());
+
+        for (path, key, size, modified) in ready {
+            pending.remove(&path);
+            let relative = std::path::Path::new(&key);
+            let out_dir = output_dir.join(relative.parent().unwrap_or(std::path::Path::new("")));
+            let out_path = batch_output_path(&path, Some(&out_dir), &name_template, format, &options.model);
+            note(format_args!("autosubs: {key}: transcribing"));
+            let file_options = FrontendTranscribeOptions { audio_path: path.display().to_string(), ..options.clone() };
+            let written = transcribe_audio_with(app.clone(), file_options, &mut engine, None)
+                .await
+                .and_then(|transcript| write_output(&finish(transcript), format, &export, &out_path));
+
+            let (status, output, error, move_error) = match written {
+                Ok(()) => {
+                    note(format_args!("autosubs: {key}: wrote {}", out_path.display()));
+                    (BatchStatus::Ok, Some(out_path.display().to_string()), None, None)
+                }
+                Err(error) => {
+                    failed += 1;
+                    note(format_args!("autosubs: {key}: failed: {error}"));
+                    let move_error = match quarantine(&path, &error_dir.join(relative), &error, &options.model) {
+                        Ok(moved) => {
+                            note(format_args!("autosubs: {key}: moved to {}", moved.display()));
+                            None
+                        }
+                        Err(e) => {
+                            note(format_args!("autosubs: {key}: {e}; skipping it until it changes"));
+                            Some(e)
+                        }
+                    };
+                    (BatchStatus::Failed, None, Some(error), move_error)
+                }
+            };
+            state.files.insert(
+                key,
+                WatchedFile {
+                    size,
+                    modified,
+                    status,
+                    output,
+                    error,
+                    move_error,
+                    processed_at: chrono::Local::now().to_rfc3339(),
+                },
+            );
+            if let Err(e) = state.save(&state_path) {
+                note(format_args!("autosubs: {e}"));
+            }
+        }
+
+        if once && pending.is_empty() {
+            flush_and_exit(if failed > 0 { 1 } else { 0 });
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
+
+/// `path` relative to the watched directory, with `/` separators on every
+/// platform so the state file stays portable.
+fn watch_key(dir: &std::path::Path, path: &std::path::Path) -> String {
+    let relative = path.strip_prefix(dir).unwrap_or(path);
+    relative.components().map(|c| c.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/")
+}
+
+/// Move a failed input to `dest` (falling back to copy and delete across
+/// filesystems) and write `<dest>.log` with the error beside it.
+fn quarantine(path: &std::path::Path, dest: &std::path::Path, error: &str, model: &str) -> Result<std::path::PathBuf, String> {
+    if let Some(parent) = dest.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| format!("failed to create '{}': {e}", parent.display()))?;
     }
+    let mut log = dest.as_os_str().to_owned();
+    log.push(".log");
+    let entry = format!(
+        "time: {}\ninput: {}\nmodel: {model}\nerror: {error}\n",
+        chrono::Local::now().to_rfc3339(),
+        path.display()
+    );
+    std::fs::write(&log, entry).map_err(|e| format!("failed to write '{}': {e}", std::path::Path::new(&log).display()))?;
+    std::fs::rename(path, dest)
+        .or_else(|_| std::fs::copy(path, dest).and_then(|_| std::fs::remove_file(path)))
+        .map_err(|e| format!("failed to move to '{}': {e}", dest.display()))?;
+    Ok(dest.to_path_buf())
 }
 
 // --- output formats ---
 
 #[derive(Clone, Copy)]
 enum OutputFormat {
-    /// Readable transcript: one line per segment, speaker-labelled, no word timings.
-    Text,
     /// Full structured transcript including word-level timestamps.
     Json,
-    Srt,
-    Vtt,
+    /// OpenAI `verbose_json` transcription response.
+    VerboseJson,
+    /// WhisperX aligned JSON (segments, word_segments, pyannote speaker labels).
+    WhisperX,
+    /// Any format rendered by the engine's shared exporters (text, markdown, html,
+    /// srt, vtt, ass, imsc, ebu-tt-d, scc, fcpxml, xmeml, otio, lrc, stl, csv, tsv).
+    Export(SubtitleFormat),
 }
 
 impl OutputFormat {
     fn parse(s: &str) -> Result<Self, String> {
         match s.trim().to_ascii_lowercase().as_str() {
-            "text" | "txt" => Ok(Self::Text),
-            "json" => Ok(Self::Json),
-            "srt" => Ok(Self::Srt),
-            "vtt" | "webvtt" => Ok(Self::Vtt),
-            other => Err(format!(
-                "unknown format '{other}' (expected text, json, srt, or vtt)"
-            )),
+            "json" => return Ok(Self::Json),
+            "verbose_json" | "verbose-json" | "openai" => return Ok(Self::VerboseJson),
+            "whisperx" => return Ok(Self::WhisperX),
+            _ => {}
+        }
+        SubtitleFormat::parse(s).map(Self::Export).ok_or_else(|| {
+            format!(
+                "unknown format '{}' (expected text, markdown, html, json, verbose_json, whisperx, srt, vtt, ass, imsc, ebu-tt-d, scc, fcpxml, xmeml, otio, lrc, stl, csv, or tsv)",
+                s.trim().to_ascii_lowercase()
+            )
+        })
+    }
+
+    /// File extension for batch outputs.
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Json | Self::VerboseJson | Self::WhisperX => "json",
+            Self::Export(format) => format.extension(),
         }
     }
 
+    fn is_binary(self) -> bool {
+        matches!(self, Self::Export(f) if f.is_binary())
+    }
+
     fn name(self) -> &'static str {
         match self {
-            Self::Text => "transcript",
             Self::Json => "JSON",
-            Self::Srt => "SRT",
-            Self::Vtt => "VTT",
+            Self::VerboseJson => "verbose_json",
+            Self::WhisperX => "WhisperX JSON",
+            Self::Export(format) => format.name(),
         }
     }
 }
 
-/// Explicit `--format` wins; otherwise infer from the `-o` file extension; else text.
-fn resolve_format(explicit: Option<&str>, output: Option<&str>) -> Result<OutputFormat, String> {
+/// Explicit `--format` wins; otherwise infer from the `-o` file extension; then
+/// the profile's format (`fallback`); else text.
+fn resolve_format(explicit: Option<&str>, output: Option<&str>, fallback: Option<&str>) -> Result<OutputFormat, String> {
     if let Some(f) = explicit {
         return OutputFormat::parse(f);
     }
-    if let Some(ext) = output
-        .and_then(|p| std::path::Path::new(p).extension())
-        .and_then(|e| e.to_str())
-    {
-        if let Ok(f) = OutputFormat::parse(ext) {
+    if let Some(path) = output.map(std::path::Path::new) {
+        if let Some(f) = SubtitleFormat::from_path(path) {
+            return Ok(OutputFormat::Export(f));
+        }
+        if let Some(Ok(f)) = path.extension().and_then(|e| e.to_str()).map(OutputFormat::parse) {
             return Ok(f);
         }
     }
-    Ok(OutputFormat::Text)
-}
-
-/// Speaker label for a segment, e.g. `Speaker 1`. `speaker_id` is the engine's
-/// numeric id ("1", "2", …) or "?" when unknown.
-fn speaker_prefix(seg: &Segment) -> String {
-    seg.speaker_id
-        .as_deref()
-        .map(|id| format!("Speaker {id}: "))
-        .unwrap_or_default()
-}
-
-fn hms(seconds: f64) -> (u64, u64, u64, u64) {
-    let total_ms = (seconds.max(0.0) * 1000.0).round() as u64;
-    (
-        total_ms / 3_600_000,
-        (total_ms / 60_000) % 60,
-        (total_ms / 1000) % 60,
-        total_ms % 1000,
-    )
-}
-
-fn ts_srt(s: f64) -> String {
-    let (h, m, s, ms) = hms(s);
-    format!("{h:02}:{m:02}:{s:02},{ms:03}")
-}
-
-fn ts_vtt(s: f64) -> String {
-    let (h, m, s, ms) = hms(s);
-    format!("{h:02}:{m:02}:{s:02}.{ms:03}")
+    fallback.map_or(Ok(OutputFormat::Export(SubtitleFormat::Text)), OutputFormat::parse)
 }
 
-fn ts_clock(s: f64) -> String {
-    let (h, m, s, _) = hms(s);
-    format!("{h:02}:{m:02}:{s:02}")
+/// Rendering flags shared by transcribe and `convert` (`--karaoke`,
+/// `--frame-rate`, `--roll-up`, `--start-timecode`, `--word-rows`, `--media-url`,
+/// `--paragraph-timecodes`), validated up front. Speaker styling is filled in
+/// from the transcript when writing.
+fn resolve_export_settings(m: &Matches) -> ExportOptions {
+    let mut export = ExportOptions {
+        karaoke: arg_flag(m, "karaoke"),
+        frame_rate: resolve_frame_rate(m),
+        roll_up: resolve_roll_up(m),
+        word_rows: arg_flag(m, "word-rows"),
+        media_url: arg_str(m, "media-url"),
+        paragraph_timecodes: arg_flag(m, "paragraph-timecodes"),
+        ..Default::default()
+    };
+    if let Some(tc) = arg_str(m, "start-timecode") {
+        let rate = export.sequence_rate();
+        match rate.parse_timecode(&tc) {
+            Some(frame) => export.start_frame = frame,
+            None => {
+                eprintln!("autosubs: invalid start timecode '{tc}' (expected HH:MM:SS:FF at {rate} fps)");
+                flush_and_exit(2);
+            }
+        }
+    }
+    export
 }
 
-/// `[HH:MM:SS] Speaker N: text` per *speaker turn* — the default, human-readable
-/// transcript. The engine's segments are short subtitle cues (wrapped for on-screen
-/// display), which read poorly as prose, so consecutive cues are merged into one
-/// paragraph while the speaker stays the same. When there is no diarization (no
-/// speaker labels), a silence gap longer than `GAP_BREAK_SECS` starts a new
-/// paragraph instead, so the transcript still breaks at natural pauses.
-fn render_text(t: &Transcript) -> String {
-    const GAP_BREAK_SECS: f64 = 2.0;
-
-    let mut out = String::new();
-    let mut start = 0.0;
-    let mut end = 0.0;
-    let mut speaker: Option<String> = None;
-    let mut text = String::new();
-    let mut open = false;
-
-    for seg in &t.segments {
-        let line = seg.text.trim();
-        if line.is_empty() {
-            continue;
+/// `--frame-rate`, validated up front like `--density`: an unparseable value is
+/// a usage error rather than silently falling back to millisecond timing.
+fn resolve_frame_rate(m: &Matches) -> Option<FrameRate> {
+    let s = arg_str(m, "frame-rate")?;
+    match FrameRate::parse(&s) {
+        Some(rate) => Some(rate),
+        None => {
+            eprintln!("autosubs: invalid frame rate '{s}' (expected e.g. 25, 29.97, or 30000/1001)");
+            flush_and_exit(2);
         }
-        // Continue the current paragraph if the speaker matches and either we have
-        // a speaker label (turns are the unit) or the gap since the last cue is small.
-        let continues = open
-            && speaker.as_deref() == seg.speaker_id.as_deref()
-            && (seg.speaker_id.is_some() || seg.start - end <= GAP_BREAK_SECS);
-
-        if continues {
-            if !text.ends_with(' ') {
-                text.push(' ');
-            }
-            text.push_str(line);
-            end = seg.end.max(end);
-        } else {
-            if open {
-                out.push_str(&text_line(start, &speaker, &text));
-            }
-            start = seg.start;
-            end = seg.end;
-            speaker = seg.speaker_id.clone();
-            text = line.to_string();
-            open = true;
-        }
-    }
-    if open {
-        out.push_str(&text_line(start, &speaker, &text));
-    }
-    out
-}
-
-fn text_line(start: f64, speaker: &Option<String>, text: &str) -> String {
-    let prefix = speaker
-        .as_deref()
-        .map(|id| format!("Speaker {id}: "))
-        .unwrap_or_default();
-    format!("[{}] {prefix}{text}\n", ts_clock(start))
-}
-
-fn render_srt(t: &Transcript) -> String {
-    let mut out = String::new();
-    for (i, seg) in t.segments.iter().enumerate() {
-        let end = seg.end.max(seg.start);
-        out.push_str(&format!(
-            "{}\n{} --> {}\n{}{}\n\n",
-            i + 1,
-            ts_srt(seg.start),
-            ts_srt(end),
-            speaker_prefix(seg),
-            seg.text.trim()
-        ));
     }
-    out
 }
 
-fn render_vtt(t: &Transcript) -> String {
-    let mut out = String::from("WEBVTT\n\n");
-    for seg in &t.segments {
-        let end = seg.end.max(seg.start);
-        out.push_str(&format!(
-            "{} --> {}\n{}{}\n\n",
-            ts_vtt(seg.start),
-            ts_vtt(end),
-            speaker_prefix(seg),
-            seg.text.trim()
-        ));
+/// `--roll-up`: CEA-608 roll-up captions use a 2-, 3- or 4-row window; anything
+/// else is a usage error.
+fn resolve_roll_up(m: &Matches) -> Option<u8> {
+    let s = arg_str(m, "roll-up")?;
+    match s.trim().parse::<u8>() {
+        Ok(rows @ 2..=4) => Some(rows),
+        _ => {
+            eprintln!("autosubs: invalid roll-up depth '{s}' (expected 2, 3, or 4)");
+            flush_and_exit(2);
+        }
     }
-    out
 }
 
 /// Print an error as a JSON object on stderr and exit non-zero. Never returns.
@@ -471,12 +1584,155 @@ fn flush_and_exit(code: i32) -> ! {
 // --- progress rendering ---
 
 /// Subset of the `labeled-progress` event payload emitted by `transcribe_audio`.
-/// The `label` field is intentionally ignored; the stage is derived from `type`.
+/// The bar and stage lines derive the stage from `type`; only the JSON stream
+/// passes the `label` (a frontend translation key) on.
 #[derive(Deserialize)]
 struct ProgressEvent {
     progress: i32,
     #[serde(rename = "type")]
     stage: Option<String>,
+    label: Option<String>,
+}
+
+/// `--progress`: how transcription progress is reported.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ProgressMode {
+    /// A live bar on a terminal, one line per stage otherwise.
+    Auto,
+    /// An NDJSON [`EventStream`] instead of any human-readable status lines.
+    Json,
+    /// Nothing but errors.
+    Quiet,
+}
+
+fn resolve_progress(m: &Matches) -> ProgressMode {
+    match arg_str(m, "progress").as_deref().map(str::trim) {
+        None | Some("auto") => ProgressMode::Auto,
+        Some("json") => ProgressMode::Json,
+        Some("none") => ProgressMode::Quiet,
+        Some(other) => {
+            eprintln!("autosubs: unknown progress mode '{other}' (expected auto, json, or none)");
+            flush_and_exit(2);
+        }
+    }
+}
+
+/// `--progress-fd`: a file descriptor the parent process opened for the JSON
+/// stream, instead of stderr. Only meaningful with `--progress json`.
+fn resolve_progress_fd(m: &Matches) -> Option<i32> {
+    let s = arg_str(m, "progress-fd")?;
+    match parse_progress_fd(&s) {
+        Ok(fd) => Some(fd),
+        Err(e) => {
+            eprintln!("autosubs: {e}");
+            flush_and_exit(2);
+        }
+    }
+}
+
+/// A `--progress-fd` value. stdin and stdout are refused: the events would be
+/// lost, or mixed into the transcript printed on stdout.
+fn parse_progress_fd(s: &str) -> Result<i32, String> {
+    match s.trim().parse::<i32>() {
+        Ok(fd @ (0 | 1)) => Err(format!(
+            "--progress-fd {fd} is {}; use 2 (stderr) or a descriptor opened for the events",
+            if fd == 0 { "stdin" } else { "stdout, which carries the transcript" }
+        )),
+        Ok(fd) if fd >= 0 => Ok(fd),
+        _ => Err(format!("invalid progress file descriptor '{s}'")),
+    }
+}
+
+/// Set by `--progress json` and `--progress none` to drop the human-readable
+/// status lines from stderr. Errors are still printed.
+static QUIET: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
+/// Print a human-readable status line on stderr unless `--progress` silenced them.
+fn note(message: std::fmt::Arguments) {
+    if !QUIET.load(std::sync::atomic::Ordering::Relaxed) {
+        eprintln!("{message}");
+    }
+}
+
+/// `--progress json`: one JSON object per line, each with an `event` name,
+/// `elapsed` seconds since the run started and the `input` file it concerns.
+///
+/// - `start`: a file begins transcribing (`model`)
+/// - `progress`: `stage`, `percent` and the engine's `label`
+/// - `segment`: a segment was produced or updated (`index`, `stage`, `segment`)
+/// - `speakers`: diarization found `count` speakers
+/// - `done`: a file finished (`status`, `output`, and `error` on failure)
+struct EventStream {
+    out: std::sync::Mutex<Box<dyn Write + Send>>,
+    started: std::time::Instant,
+    input: std::sync::Mutex<Option<String>>,
+}
+
+impl EventStream {
+    /// Stream to `fd` when given, otherwise to stderr. A descriptor that can't
+    /// be written to is a usage error.
+    fn open(fd: Option<i32>) -> Self {
+        let out: Box<dyn Write + Send> = match fd {
+            None => Box::new(std::io::stderr()),
+            Some(fd) => fd_writer(fd).unwrap_or_else(|e| {
+                eprintln!("autosubs: {e}");
+                flush_and_exit(2);
+            }),
+        };
+        Self {
+            out: std::sync::Mutex::new(out),
+            started: std::time::Instant::now(),
+            input: std::sync::Mutex::new(None),
+        }
+    }
+
+    /// Tag the following events with `input`.
+    fn set_input(&self, input: &str) {
+        if let Ok(mut current) = self.input.lock() {
+            *current = Some(input.to_string());
+        }
+    }
+
+    /// Write one `event` line with `fields` (an object) added. The `event`,
+    /// `elapsed` and `input` the stream sets win over fields of the same name.
+    fn emit(&self, event: &str, fields: serde_json::Value) {
+        let elapsed = (self.started.elapsed().as_secs_f64() * 1000.0).round() / 1000.0;
+        let mut line = serde_json::Map::new();
+        line.insert("event".into(), json!(event));
+        line.insert("elapsed".into(), json!(elapsed));
+        let input = self.input.lock().ok().and_then(|i| i.clone());
+        if let Some(input) = input {
+            line.insert("input".into(), json!(input));
+        }
+        if let serde_json::Value::Object(fields) = fields {
+            for (key, value) in fields {
+                line.entry(key).or_insert(value);
+            }
+        }
+        if let Ok(mut out) = self.out.lock() {
+            let _ = writeln!(out, "{}", serde_json::Value::Object(line));
+            let _ = out.flush();
+        }
+    }
+}
+
+#[cfg(unix)]
+fn fd_writer(fd: i32) -> Result<Box<dyn Write + Send>, String> {
+    use std::os::fd::BorrowedFd;
+    // SAFETY: the parent process opened this descriptor for us to write to, and
+    // it is only borrowed long enough to duplicate it, so the stream owns a copy
+    // and the original (stderr, say) is never closed by us. A descriptor that
+    // isn't open fails to duplicate.
+    let borrowed = unsafe { BorrowedFd::borrow_raw(fd) };
+    let owned = borrowed
+        .try_clone_to_owned()
+        .map_err(|e| format!("cannot write progress to file descriptor {fd}: {e}"))?;
+    Ok(Box::new(std::fs::File::from(owned)))
+}
+
+#[cfg(not(unix))]
+fn fd_writer(_fd: i32) -> Result<Box<dyn Write + Send>, String> {
+    Err("--progress-fd is only supported on Unix; the JSON stream goes to stderr".into())
 }
 
 /// Map an engine `ProgressType` (Debug string) to a human stage name.
@@ -511,6 +1767,15 @@ fn arg_str(m: &Matches, name: &str) -> Option<String> {
         .and_then(|a| a.value.as_str().map(|s| s.to_string()))
 }
 
+/// All values of an argument that takes several (`"multiple": true`).
+fn arg_list(m: &Matches, name: &str) -> Vec<String> {
+    match m.args.get(name).map(|a| &a.value) {
+        Some(serde_json::Value::Array(values)) => values.iter().filter_map(|v| v.as_str().map(str::to_string)).collect(),
+        Some(serde_json::Value::String(s)) => vec![s.clone()],
+        _ => Vec::new(),
+    }
+}
+
 fn arg_flag(m: &Matches, name: &str) -> bool {
     m.args
         .get(name)
@@ -518,10 +1783,36 @@ fn arg_flag(m: &Matches, name: &str) -> bool {
         .unwrap_or(false)
 }
 
+/// A switch with a `--no-` form: `Some(true)` for `--NAME`, `Some(false)` for
+/// `--no-NAME` (which wins), `None` when neither is given.
+fn arg_toggle(m: &Matches, name: &str) -> Option<bool> {
+    if arg_flag(m, &format!("no-{name}")) {
+        Some(false)
+    } else if arg_flag(m, name) {
+        Some(true)
+    } else {
+        None
+    }
+}
+
 fn arg_num<T: std::str::FromStr>(m: &Matches, name: &str) -> Option<T> {
     arg_str(m, name).and_then(|s| s.parse().ok())
 }
 
+/// `--density`, validated up front: an unknown value is a usage error rather than
+/// a silent fall back to the default density (which would produce wrapping the
+/// user didn't ask for). The accepted values match `TextDensity`'s serde repr.
+fn resolve_density(m: &Matches) -> Option<TextDensity> {
+    let s = arg_str(m, "density")?;
+    match parse_density(&s) {
+        Some(d) => Some(d),
+        None => {
+            eprintln!("autosubs: unknown density '{s}' (expected less, standard, more, single, or custom)");
+            flush_and_exit(2);
+        }
+    }
+}
+
 /// Parse a `--density` value into `TextDensity`, case-insensitively. Returns
 /// `None` for unrecognized values so the caller can report a usage error.
 fn parse_density(s: &str) -> Option<TextDensity> {
@@ -816,3 +2107,437 @@ fn windows_remove_user_path(dir: &str) -> Result<(), String> {
     );
     run_powershell(&script)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transcript_types::{Segment, WordTimestamp};
+
+    /// `Matches` as tauri-plugin-cli builds them: flags are `true`, values strings.
+    fn matches(args: &[(&str, serde_json::Value)]) -> Matches {
+        let mut m = Matches::default();
+        for (name, value) in args {
+            m.args.entry(name.to_string()).or_default().value = value.clone();
+        }
+        m
+    }
+
+    /// A writer whose output the test can still read.
+    #[derive(Clone, Default)]
+    struct SharedBuf(Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuf {
+        fn write(&mut self, bytes: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(bytes);
+            Ok(bytes.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn events_are_tagged_and_merged_with_their_fields() {
+        let out = SharedBuf::default();
+        let stream = EventStream {
+            out: std::sync::Mutex::new(Box::new(out.clone())),
+            started: std::time::Instant::now(),
+            input: std::sync::Mutex::new(None),
+        };
+        stream.emit("start", json!({ "model": "tiny" }));
+        stream.set_input("talk.wav");
+        stream.emit("done", json!({ "status": "ok", "event": "other", "input": "other.wav" }));
+        stream.emit("progress", json!("not an object"));
+
+        let text = String::from_utf8(out.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<serde_json::Value> = text.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0]["event"], "start");
+        assert_eq!(lines[0]["model"], "tiny");
+        assert!(lines[0].get("input").is_none());
+        assert!(lines[0]["elapsed"].as_f64().is_some_and(|e| (0.0..5.0).contains(&e)));
+        assert_eq!(lines[1], json!({ "event": "done", "elapsed": lines[1]["elapsed"], "input": "talk.wav", "status": "ok" }));
+        assert_eq!(lines[2].as_object().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn progress_fd_refuses_stdin_and_stdout() {
+        assert_eq!(parse_progress_fd(" 3 "), Ok(3));
+        assert_eq!(parse_progress_fd("2"), Ok(2));
+        assert!(parse_progress_fd("0").unwrap_err().contains("stdin"));
+        assert!(parse_progress_fd("1").unwrap_err().contains("stdout"));
+        assert!(parse_progress_fd("-1").is_err());
+        assert!(parse_progress_fd("three").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn progress_fd_is_duplicated_not_taken_over() {
+        use std::os::fd::AsRawFd;
+        let file = tempfile::tempfile().unwrap();
+        let mut writer = fd_writer(file.as_raw_fd()).unwrap();
+        writeln!(writer, "{{}}").unwrap();
+        drop(writer);
+        // Still open after the duplicate is closed.
+        assert_eq!(file.metadata().unwrap().len(), 3);
+    }
+
+    const BROADCAST_TOML: &str = r#"
+        [profiles.broadcast]
+        model = "large-v3"
+        lang = "de"
+        max-lines = 2
+        diarize = true
+        remove-punctuation = true
+        censored-words = ["fantastic"]
+        format = "stl"
+
+        [profiles.social]
+        density = "single"
+    "#;
+
+    fn broadcast() -> Profile {
+        toml::from_str::<CliConfig>(BROADCAST_TOML).unwrap().profiles.remove("broadcast").unwrap()
+    }
+
+    #[test]
+    fn flags_override_profile_over_defaults() {
+        let defaults = transcribe_options(&matches(&[]), &Profile::default());
+        assert_eq!(defaults.model, "small");
+        assert_eq!(defaults.lang, None);
+        assert_eq!(defaults.enable_diarize, Some(false));
+        assert_eq!(defaults.remove_punctuation, Some(false));
+        assert_eq!(defaults.censored_words, None);
+
+        let profile = transcribe_options(&matches(&[]), &broadcast());
+        assert_eq!(profile.model, "large-v3");
+        assert_eq!(profile.lang.as_deref(), Some("de"));
+        assert_eq!(profile.max_lines, Some(2));
+        assert_eq!(profile.enable_diarize, Some(true));
+        assert_eq!(profile.remove_punctuation, Some(true));
+        assert_eq!(profile.censored_words, Some(vec!["fantastic".to_string()]));
+
+        let flags = matches(&[
+            ("model", json!("tiny")),
+            ("max-lines", json!("1")),
+            ("no-diarize", json!(true)),
+            ("no-remove-punctuation", json!(true)),
+        ]);
+        let overridden = transcribe_options(&flags, &broadcast());
+        assert_eq!(overridden.model, "tiny");
+        assert_eq!(overridden.lang.as_deref(), Some("de"));
+        assert_eq!(overridden.max_lines, Some(1));
+        assert_eq!(overridden.enable_diarize, Some(false));
+        assert_eq!(overridden.remove_punctuation, Some(false));
+
+        let on = transcribe_options(&matches(&[("diarize", json!(true))]), &Profile::default());
+        assert_eq!(on.enable_diarize, Some(true));
+    }
+
+    #[test]
+    fn profiles_load_from_toml_and_json() {
+        let dir = tempfile::tempdir().unwrap();
+        let toml_path = dir.path().join("cli.toml");
+        std::fs::write(&toml_path, BROADCAST_TOML).unwrap();
+        let json_path = dir.path().join("cli.json");
+        std::fs::write(&json_path, r#"{ "profiles": { "broadcast": { "model": "large-v3", "max-lines": 2, "diarize": true } } }"#)
+            .unwrap();
+
+        for path in [&toml_path, &json_path] {
+            let profile = read_profile(path, "broadcast").unwrap();
+            assert_eq!(profile.model.as_deref(), Some("large-v3"));
+            assert_eq!(profile.max_lines, Some(2));
+            assert_eq!(profile.diarize, Some(true));
+        }
+        assert_eq!(read_profile(&toml_path, "social").unwrap().density.as_deref(), Some("single"));
+    }
+
+    #[test]
+    fn profile_errors_name_the_problem() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cli.toml");
+        std::fs::write(&path, BROADCAST_TOML).unwrap();
+        let err = read_profile(&path, "podcast").unwrap_err();
+        assert!(err.contains("no profile 'podcast'"), "{err}");
+        assert!(err.ends_with("(profiles: broadcast, social)"), "{err}");
+
+        std::fs::write(&path, "[profiles.a]\nmodle = \"tiny\"\n").unwrap();
+        let err = read_profile(&path, "a").unwrap_err();
+        assert!(err.starts_with("invalid config"), "{err}");
+
+        let empty = dir.path().join("empty.json");
+        std::fs::write(&empty, "{}").unwrap();
+        assert!(read_profile(&empty, "a").unwrap_err().ends_with("(profiles: none)"));
+        assert!(read_profile(&dir.path().join("missing.toml"), "a").unwrap_err().starts_with("failed to read"));
+    }
+
+    #[test]
+    fn print_config_shows_merged_options() {
+        let profile = broadcast();
+        let options = transcribe_options(&matches(&[("lang", json!("fr"))]), &profile);
+        let format = resolve_format(None, None, profile.format.as_deref()).unwrap();
+        let report = config_report(Some(std::path::Path::new("/etc/cli.toml")), Some("broadcast".into()), format, &options);
+        assert_eq!(report["config"], "/etc/cli.toml");
+        assert_eq!(report["profile"], "broadcast");
+        assert_eq!(report["format"], SubtitleFormat::Stl.name());
+        assert_eq!(report["options"]["model"], "large-v3");
+        assert_eq!(report["options"]["lang"], "fr");
+        assert_eq!(report["options"]["enableDiarize"], true);
+        assert_eq!(report["options"]["censoredWords"], json!(["fantastic"]));
+    }
+
+    #[test]
+    fn format_presets_keep_censored_words() {
+        let word = |word: &str, start: f64, end: f64| WordTimestamp { word: word.into(), start, end, probability: None };
+        let segment = Segment {
+            start: 0.0,
+            end: 2.0,
+            text: "Hello fantastic world".into(),
+            speaker_id: None,
+            words: Some(vec![word("Hello", 0.0, 0.5), word(" fantastic", 0.6, 1.2), word(" world", 1.3, 2.0)]),
+            stats: None,
+        };
+        let transcript = Transcript {
+            processing_time_sec: 0,
+            language: "en".into(),
+            segments: Vec::new(),
+            original_segments: vec![segment],
+            speakers: Vec::new(),
+        };
+        let options = formatting_options(&transcribe_options(&matches(&[]), &broadcast()));
+        let finished = format_preset(&options, OutputFormat::Export(SubtitleFormat::Stl))(transcript);
+        let text: Vec<&str> = finished.segments.iter().map(|s| s.text.as_str()).collect();
+        assert!(text.concat().contains("f*******c"), "{text:?}");
+    }
+
+    #[test]
+    fn reformat_rebuilds_cues_from_the_raw_segments() {
+        let text = "the quick brown fox jumps over the lazy dog while the slow grey cat \
+                    watches from the warm window sill all afternoon";
+        let words: Vec<WordTimestamp> = text
+            .split(' ')
+            .enumerate()
+            .map(|(i, w)| WordTimestamp {
+                word: if i == 0 { w.into() } else { format!(" {w}") },
+                start: i as f64 * 0.4,
+                end: i as f64 * 0.4 + 0.35,
+                probability: None,
+            })
+            .collect();
+        let segment = Segment {
+            start: 0.0,
+            end: words.last().unwrap().end,
+            text: text.into(),
+            speaker_id: None,
+            words: Some(words.clone()),
+            stats: None,
+        };
+        let transcript = Transcript {
+            processing_time_sec: 0,
+            language: "en".into(),
+            segments: vec![segment.clone()],
+            original_segments: vec![segment],
+            speakers: Vec::new(),
+        };
+        let json = serde_json::to_string_pretty(&transcript).unwrap();
+        let srt = OutputFormat::Export(SubtitleFormat::Srt);
+        let reformat = |args: &[(&str, serde_json::Value)]| reformat_transcript(&matches(args), &json, srt).unwrap();
+        let original = |t: &Transcript| serde_json::to_value(&t.original_segments).unwrap();
+
+        let standard = reformat(&[]);
+        assert!(standard.segments.len() < words.len(), "{:?}", standard.segments);
+
+        let two_lines = reformat(&[("max-lines", json!("2"))]);
+        assert!(two_lines.segments.len() < standard.segments.len(), "{:?}", two_lines.segments);
+        assert!(two_lines.segments.iter().any(|s| s.text.contains('\n')), "{:?}", two_lines.segments);
+        assert_eq!(original(&two_lines), original(&transcript));
+
+        let single_word = reformat(&[("density", json!("single"))]);
+        assert_eq!(single_word.segments.len(), words.len());
+        assert_eq!(original(&single_word), original(&transcript));
+    }
+
+    #[test]
+    fn languages_are_labelled_by_kind_of_support() {
+        let restricted = |languages: &[&str]| LanguageSupport::Restricted {
+            languages: languages.iter().map(|l| l.to_string()).collect(),
+        };
+        assert_eq!(languages_label(None, 3), "-");
+        assert_eq!(languages_label(Some(&LanguageSupport::Multilingual), 3), "multilingual");
+        let single = LanguageSupport::SingleLanguage { language: "en".into() };
+        assert_eq!(languages_label(Some(&single), 3), "en");
+        assert_eq!(languages_label(Some(&restricted(&["en", "de", "fr"])), 3), "en, de, fr");
+        assert_eq!(languages_label(Some(&restricted(&["en", "de", "fr", "es"])), 3), "4 languages");
+    }
+
+    #[test]
+    fn model_catalog_lists_every_model_with_its_cache_state() {
+        let cache = tempfile::tempdir().unwrap();
+        // A complete diarization snapshot in the hf-hub layout, nothing else.
+        let diarize = manifest::diarize();
+        let snapshot = cache.path().join(format!("models--{}", diarize.repo.replace('/', "--"))).join("snapshots/main");
+        for file in &diarize.files {
+            let path = snapshot.join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::File::create(path).unwrap().set_len(1_000_000).unwrap();
+        }
+
+        let catalog = model_catalog(&ModelManager::new(cache.path().to_path_buf()));
+        assert_eq!(catalog.len(), manifest::MANIFEST.models.len() + 2);
+        for (info, entry) in catalog.iter().zip(&manifest::MANIFEST.models) {
+            assert_eq!(info.id, entry.id);
+            assert_eq!(info.kind, "transcription");
+            assert!(info.engine.is_some(), "{}", info.id);
+            assert!(!info.cached, "{}", info.id);
+        }
+
+        let [.., diarization, alignment] = catalog.as_slice() else { unreachable!() };
+        assert_eq!((diarization.id.as_str(), diarization.kind), (diarize.id.as_str(), "diarization"));
+        assert!(diarization.engine.is_none() && diarization.license.is_none());
+        assert!(matches!(diarization.language_support, Some(LanguageSupport::Multilingual)));
+        assert!(diarization.cached);
+
+        let aligner = manifest::aligner();
+        assert_eq!((alignment.id.as_str(), alignment.kind), (aligner.id.as_str(), "alignment"));
+        assert!(alignment.license.is_some() && alignment.size.is_some());
+        assert!(!alignment.cached);
+    }
+
+    #[test]
+    fn inputs_expand_directories_and_globs_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        for name in ["a.wav", "b.MP3", "notes.txt", ".hidden.wav", "sub/c.mp4"] {
+            let path = root.join(name);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, b"").unwrap();
+        }
+        std::fs::create_dir(root.join("empty")).unwrap();
+        let arg = |name: &str| root.join(name).display().to_string();
+
+        // The hidden file is skipped when walking the directory; the glob only
+        // matches what the directory already contributed.
+        let expanded = expand_inputs(&[
+            root.display().to_string(),
+            arg("a.wav"),
+            arg("a.*"),
+            arg("notes.txt"),
+            arg("missing.wav"),
+            arg("empty"),
+            arg("*.flac"),
+        ]);
+        let files: Vec<std::path::PathBuf> = expanded.iter().filter_map(|r| r.as_ref().ok().cloned()).collect();
+        assert_eq!(files, [root.join("a.wav"), root.join("b.MP3"), root.join("sub/c.mp4"), root.join("notes.txt")]);
+        let errors: Vec<(String, String)> = expanded.into_iter().filter_map(Result::err).collect();
+        assert_eq!(
+            errors,
+            [
+                (arg("missing.wav"), "no such file or directory".to_string()),
+                (arg("empty"), "no media files found".to_string()),
+                (arg("*.flac"), "no media files found".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn batch_outputs_follow_the_name_template() {
+        let input = std::path::Path::new("/media/show/ep1.final.mp4");
+        let srt = OutputFormat::Export(SubtitleFormat::Srt);
+        assert_eq!(batch_output_path(input, None, "{stem}.{ext}", srt, "small"), std::path::Path::new("/media/show/ep1.final.srt"));
+        assert_eq!(
+            batch_output_path(input, Some(std::path::Path::new("/out")), "{parent}-{stem}.{model}.{ext}", OutputFormat::Json, "large-v3"),
+            std::path::Path::new("/out/show-ep1.final.large-v3.json")
+        );
+    }
+
+    #[test]
+    fn existing_outputs_are_skipped_unless_overwriting() {
+        let dir = tempfile::tempdir().unwrap();
+        let existing = dir.path().join("a.srt");
+        std::fs::write(&existing, "1\n").unwrap();
+        assert!(skips_existing(&existing, false));
+        assert!(!skips_existing(&existing, true));
+        assert!(!skips_existing(&dir.path().join("b.srt"), false));
+    }
+
+    #[test]
+    fn batch_fails_if_any_input_failed() {
+        let result = |status| BatchResult { input: String::new(), output: None, status, error: None };
+        assert_eq!(batch_exit_code(&[]), 0);
+        assert_eq!(batch_exit_code(&[result(BatchStatus::Ok), result(BatchStatus::Skipped)]), 0);
+        assert_eq!(batch_exit_code(&[result(BatchStatus::Ok), result(BatchStatus::Failed)]), 1);
+    }
+
+    fn watched(size: u64, modified: u64, status: BatchStatus) -> WatchedFile {
+        WatchedFile { size, modified, status, output: None, error: None, move_error: None, processed_at: String::new() }
+    }
+
+    #[test]
+    fn watch_state_round_trips() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(WATCH_STATE_FILE);
+        assert!(WatchState::load(&path).unwrap().files.is_empty());
+
+        let mut state = WatchState::default();
+        state.files.insert("a/clip.wav".into(), watched(10, 20, BatchStatus::Ok));
+        state.files.insert("bad.mp3".into(), WatchedFile { error: Some("boom".into()), ..watched(5, 6, BatchStatus::Failed) });
+        state.save(&path).unwrap();
+        assert!(!path.with_extension("json.tmp").exists());
+
+        let loaded = WatchState::load(&path).unwrap();
+        assert_eq!(loaded.files.len(), 2);
+        assert!(loaded.files["a/clip.wav"].status == BatchStatus::Ok);
+        assert_eq!(loaded.files["bad.mp3"].error.as_deref(), Some("boom"));
+
+        std::fs::write(&path, "not json").unwrap();
+        assert!(WatchState::load(&path).unwrap_err().starts_with("invalid watch state"));
+    }
+
+    #[test]
+    fn only_successful_files_are_done() {
+        let mut state = WatchState::default();
+        state.files.insert("ok.wav".into(), watched(10, 20, BatchStatus::Ok));
+        state.files.insert("failed.wav".into(), watched(10, 20, BatchStatus::Failed));
+        assert!(state.is_done("ok.wav", 10, 20));
+        assert!(!state.is_done("ok.wav", 11, 20), "replaced with new content");
+        assert!(!state.is_done("ok.wav", 10, 21), "replaced with new content");
+        assert!(!state.is_done("failed.wav", 10, 20), "a failed file put back is retried");
+        assert!(!state.is_done("new.wav", 10, 20));
+    }
+
+    #[test]
+    fn failed_files_that_could_not_be_moved_wait_for_a_change() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("clip.wav");
+        std::fs::write(&input, b"audio").unwrap();
+        // A file where the error directory should be, so the move fails.
+        std::fs::write(dir.path().join("failed"), b"").unwrap();
+        let move_error = quarantine(&input, &dir.path().join("failed/clip.wav"), "decode error", "small").unwrap_err();
+        assert!(input.exists());
+
+        let mut state = WatchState::default();
+        let stuck = WatchedFile { move_error: Some(move_error), ..watched(10, 20, BatchStatus::Failed) };
+        state.files.insert("clip.wav".into(), stuck);
+        assert!(state.is_done("clip.wav", 10, 20));
+        assert!(!state.is_done("clip.wav", 11, 20), "replaced with new content");
+        assert!(!state.is_done("clip.wav", 10, 21), "replaced with new content");
+    }
+
+    #[test]
+    fn quarantine_moves_the_input_and_logs_the_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("clip.wav");
+        std::fs::write(&input, b"audio").unwrap();
+        let dest = dir.path().join("failed/sub/clip.wav");
+
+        let moved = quarantine(&input, &dest, "decode error", "small").unwrap();
+        assert_eq!(moved, dest);
+        assert!(!input.exists());
+        assert_eq!(std::fs::read(&dest).unwrap(), b"audio");
+        let log = std::fs::read_to_string(dir.path().join("failed/sub/clip.wav.log")).unwrap();
+        assert!(log.contains("model: small\n"), "{log}");
+        assert!(log.contains("error: decode error\n"), "{log}");
+        assert!(log.contains(&format!("input: {}\n", input.display())), "{log}");
+    }
+}
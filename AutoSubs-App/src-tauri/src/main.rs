@@ -32,6 +32,7 @@ mod logging;
 mod resolve_bridge;
 mod adobe_bridge;
 mod cli;
+mod server;
 #[cfg(target_os = "macos")]
 
 // Include integration-like tests that need crate visibility
@@ -541,6 +542,8 @@ fn main() {
             transcription_api::ensure_models,
             transcription_api::cancel_transcription,
             transcription_api::reformat_subtitles,
+            transcription_api::export_subtitles,
+            transcription_api::import_subtitles,
             models::get_downloaded_models,
             models::delete_model,
             logging::get_backend_logs,
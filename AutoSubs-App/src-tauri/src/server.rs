@@ -0,0 +1,1102 @@
+//! `autosubs serve`: a local HTTP transcription server.
+//!
+//! Tools that already talk to hosted Whisper can point at AutoSubs instead:
+//! `POST /v1/audio/transcriptions` takes the same multipart fields as OpenAI's
+//! endpoint (`file`, `model`, `language`, `prompt`, `response_format`,
+//! `timestamp_granularities[]`) and answers when the transcript is ready. For
+//! long recordings there is a job API that returns at once:
+//!
+//! - `POST /v1/jobs` — same fields; responds `202` with the job
+//! - `GET /v1/jobs`, `GET /v1/jobs/{id}` — status and progress
+//! - `GET /v1/jobs/{id}/result` — the transcript in the job's `response_format`
+//! - `DELETE /v1/jobs/{id}` — cancel a queued or running job
+//!
+//! plus `GET /v1/models` and `GET /health`.
+//!
+//! Every request runs through [`crate::transcription_api::transcribe_audio_with`]
+//! on one worker, one at a time, with a single engine kept for the life of the
+//! server so models stay loaded between requests. HTTP handling is deliberately
+//! minimal (HTTP/1.1, one request per connection, `Content-Length` bodies): it is
+//! meant for local tools, so it binds to loopback and has no authentication.
+//! Uploads are written to a temporary file as they arrive, and a synchronous
+//! transcription is cancelled if its client hangs up before it finishes.
+
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use serde::Serialize;
+use serde_json::json;
+use tauri::{AppHandle, Listener, Runtime};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{mpsc, watch};
+
+use crate::transcription_api::{
+    FrontendTranscribeOptions, export_options, render_export, transcribe_audio_with,
+};
+use crate::transcript_types::Transcript;
+use transcription_engine::{SubtitleFormat, manifest};
+
+/// Largest request body accepted (the uploaded recording plus form fields).
+const MAX_BODY_BYTES: usize = 1 << 30;
+/// Largest body part held in memory: a form field other than a file, or the
+/// body of a request that isn't a form.
+const MAX_BUFFERED_BYTES: usize = 1 << 20;
+/// Largest request line plus headers.
+const MAX_HEADER_BYTES: usize = 64 * 1024;
+/// Finished jobs kept for polling before the oldest are forgotten.
+const MAX_FINISHED_JOBS: usize = 64;
+/// OpenAI's model name, mapped to the server's default model.
+const OPENAI_MODEL: &str = "whisper-1";
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum JobStatus {
+    Queued,
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+impl JobStatus {
+    fn is_finished(self) -> bool {
+        matches!(self, Self::Completed | Self::Failed | Self::Cancelled)
+    }
+}
+
+/// `response_format`, as OpenAI names them.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ResponseFormat {
+    Json,
+    Text,
+    Srt,
+    Vtt,
+    VerboseJson,
+}
+
+impl ResponseFormat {
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "json" => Some(Self::Json),
+            "text" => Some(Self::Text),
+            "srt" => Some(Self::Srt),
+            "vtt" => Some(Self::Vtt),
+            "verbose_json" => Some(Self::VerboseJson),
+            _ => None,
+        }
+    }
+}
+
+/// A parsed transcription request: the engine options plus how to answer.
+struct JobRequest {
+    options: FrontendTranscribeOptions,
+    format: ResponseFormat,
+    /// `word` and/or `segment`; shapes the `verbose_json` response.
+    granularities: Vec<String>,
+    /// The uploaded recording, deleted when the request is dropped.
+    upload: tempfile::NamedTempFile,
+}
+
+struct Job {
+    model: String,
+    created_at: u64,
+    status: watch::Sender<JobStatus>,
+    /// Set by `DELETE`; the engine polls it while this job runs.
+    cancel: Arc<AtomicBool>,
+    progress: Option<(String, i32)>,
+    format: ResponseFormat,
+    granularities: Vec<String>,
+    /// Taken by the worker when the job starts.
+    request: Option<JobRequest>,
+    result: Option<Result<Transcript, String>>,
+}
+
+impl Job {
+    fn status(&self) -> JobStatus {
+        *self.status.borrow()
+    }
+
+    fn to_json(&self, id: u64) -> serde_json::Value {
+        let mut job = json!({
+            "id": job_id(id),
+            "object": "transcription.job",
+            "status": self.status(),
+            "model": self.model,
+            "created_at": self.created_at,
+            "progress": self.progress.as_ref().map(|(stage, percent)| json!({ "stage": stage, "percent": percent })),
+        });
+        if let Some(Err(error)) = &self.result {
+            job["error"] = json!(error);
+        }
+        job
+    }
+}
+
+fn job_id(id: u64) -> String {
+    format!("job_{id}")
+}
+
+fn parse_job_id(s: &str) -> Option<u64> {
+    s.strip_prefix("job_")?.parse().ok()
+}
+
+struct Server<R: Runtime> {
+    app: AppHandle<R>,
+    default_model: String,
+    jobs: Mutex<BTreeMap<u64, Job>>,
+    next_id: AtomicU64,
+    queue: mpsc::UnboundedSender<u64>,
+    /// The job the worker is transcribing, for progress events.
+    running: Mutex<Option<u64>>,
+}
+
+/// Bind `addr` and serve until the process exits. Returns only if the address
+/// can't be bound.
+pub async fn serve<R: Runtime>(app: AppHandle<R>, addr: SocketAddr, default_model: String) -> Result<(), String> {
+    let listener = TcpListener::bind(addr)
+        .await
+        .map_err(|e| format!("failed to listen on {addr}: {e}"))?;
+    let (queue, jobs_rx) = mpsc::unbounded_channel();
+    let server = Arc::new(Server {
+        app: app.clone(),
+        default_model,
+        jobs: Mutex::new(BTreeMap::new()),
+        next_id: AtomicU64::new(1),
+        queue,
+        running: Mutex::new(None),
+    });
+
+    let progress_server = server.clone();
+    app.listen("labeled-progress", move |event| {
+        let Ok(payload) = serde_json::from_str::<serde_json::Value>(event.payload()) else {
+            return;
+        };
+        let (Some(percent), Some(stage)) = (payload["progress"].as_i64(), payload["type"].as_str()) else {
+            return;
+        };
+        let running = progress_server.running.lock().ok().and_then(|r| *r);
+        let Some(id) = running else {
+            return;
+        };
+        if let Some(job) = progress_server.jobs.lock().ok().as_mut().and_then(|jobs| jobs.get_mut(&id)) {
+            job.progress = Some((stage.to_lowercase(), percent as i32));
+        }
+    });
+
+    tauri::async_runtime::spawn(run_worker(server.clone(), jobs_rx));
+    tracing::info!("transcription server listening on {addr}");
+    eprintln!("autosubs: listening on http://{addr} (default model {})", server.default_model);
+
+    loop {
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                let server = server.clone();
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) = handle_connection(&server, stream).await {
+                        tracing::debug!("server: connection error: {e}");
+                    }
+                });
+            }
+            Err(e) => tracing::error!("server: failed to accept connection: {e}"),
+        }
+    }
+}
+
+/// Transcribe queued jobs one at a time with one long-lived engine.
+async fn run_worker<R: Runtime>(server: Arc<Server<R>>, mut jobs_rx: mpsc::UnboundedReceiver<u64>) {
+    let mut engine = None;
+    while let Some(id) = jobs_rx.recv().await {
+        // Cancelled while queued: the request is already gone.
+        let (request, cancel) = {
+            let Ok(mut jobs) = server.jobs.lock() else {
+                continue;
+            };
+            let Some(job) = jobs.get_mut(&id) else {
+                continue;
+            };
+            let Some(request) = job.request.take() else {
+                continue;
+            };
+            job.status.send_replace(JobStatus::Running);
+            (request, job.cancel.clone())
+        };
+        if let Ok(mut running) = server.running.lock() {
+            *running = Some(id);
+        }
+
+        let options = FrontendTranscribeOptions {
+            audio_path: request.upload.path().display().to_string(),
+            ..request.options.clone()
+        };
+        let result = transcribe_audio_with(server.app.clone(), options, &mut engine, Some(cancel)).await;
+        drop(request);
+
+        if let Ok(mut running) = server.running.lock() {
+            *running = None;
+        }
+        if let Some(job) = server.jobs.lock().ok().as_mut().and_then(|jobs| jobs.get_mut(&id)) {
+            // A cancel that lands after the engine's last check still wins.
+            if job.status() == JobStatus::Cancelled {
+                job.result = Some(Err("Transcription cancelled".into()));
+            } else {
+                job.status.send_replace(if result.is_ok() { JobStatus::Completed } else { JobStatus::Failed });
+                job.result = Some(result);
+            }
+        }
+    }
+}
+
+impl<R: Runtime> Server<R> {
+    /// Queue a job and return its id, forgetting the oldest finished jobs.
+    fn submit(&self, request: JobRequest) -> Result<(u64, watch::Receiver<JobStatus>), Response> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (status, status_rx) = watch::channel(JobStatus::Queued);
+        let created_at = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        let job = Job {
+            model: request.options.model.clone(),
+            created_at,
+            status,
+            cancel: Arc::new(AtomicBool::new(false)),
+            progress: None,
+            format: request.format,
+            granularities: request.granularities.clone(),
+            request: Some(request),
+            result: None,
+        };
+        let mut jobs = self.jobs.lock().map_err(|_| Response::error(500, "job table unavailable"))?;
+        let finished: Vec<u64> = jobs.iter().filter(|(_, j)| j.status().is_finished()).map(|(id, _)| *id).collect();
+        for old in finished.iter().take(finished.len().saturating_sub(MAX_FINISHED_JOBS - 1)) {
+            jobs.remove(old);
+        }
+        jobs.insert(id, job);
+        drop(jobs);
+        self.queue.send(id).map_err(|_| Response::error(500, "transcription worker stopped"))?;
+        Ok((id, status_rx))
+    }
+
+    fn job_json(&self, id: u64) -> Response {
+        match self.jobs.lock().ok().and_then(|jobs| jobs.get(&id).map(|job| job.to_json(id))) {
+            Some(job) => Response::json(200, &job),
+            None => Response::error(404, &format!("no job '{}'", job_id(id))),
+        }
+    }
+
+    /// The finished job's transcript in its response format.
+    fn job_result(&self, id: u64) -> Response {
+        let Ok(jobs) = self.jobs.lock() else {
+            return Response::error(500, "job table unavailable");
+        };
+        let Some(job) = jobs.get(&id) else {
+            return Response::error(404, &format!("no job '{}'", job_id(id)));
+        };
+        match &job.result {
+            Some(Ok(transcript)) => render_response(transcript, job.format, &job.granularities),
+            Some(Err(error)) if job.status() == JobStatus::Cancelled => {
+                Response::error(409, &format!("job was cancelled: {error}"))
+            }
+            Some(Err(error)) => Response::error(500, error),
+            None => Response::error(409, &format!("job '{}' is {}", job_id(id), json!(job.status()).as_str().unwrap_or(""))),
+        }
+    }
+
+    /// Cancel a queued job outright, or ask the engine to stop a running one.
+    fn cancel(&self, id: u64) -> Response {
+        {
+            let Ok(mut jobs) = self.jobs.lock() else {
+                return Response::error(500, "job table unavailable");
+            };
+            let Some(job) = jobs.get_mut(&id) else {
+                return Response::error(404, &format!("no job '{}'", job_id(id)));
+            };
+            let status = job.status();
+            if matches!(status, JobStatus::Queued | JobStatus::Running) {
+                job.cancel.store(true, Ordering::Relaxed);
+                job.request = None;
+                job.status.send_replace(JobStatus::Cancelled);
+                if status == JobStatus::Queued {
+                    job.result = Some(Err("Cancelled".into()));
+                }
+            }
+        }
+        self.job_json(id)
+    }
+}
+
+// --- routing ---
+
+async fn handle_connection<R: Runtime>(server: &Server<R>, mut stream: TcpStream) -> std::io::Result<()> {
+    let response = match read_request(&mut stream).await? {
+        Ok(request) => route(server, request, &mut stream).await,
+        Err(response) => response,
+    };
+    response.write(&mut stream).await
+}
+
+/// Answer `request`. `stream` is only watched, so a synchronous transcription
+/// can be cancelled when its client goes away.
+async fn route<R: Runtime>(server: &Server<R>, mut request: Request, stream: &mut TcpStream) -> Response {
+    let path = request.path.split('?').next().unwrap_or_default().trim_end_matches('/');
+    let segments: Vec<&str> = path.split('/').skip(1).collect();
+    match (request.method.as_str(), segments.as_slice()) {
+        ("GET", ["health"]) => Response::json(200, &json!({ "status": "ok" })),
+        ("GET", ["v1", "models"]) => list_models(),
+        ("POST", ["v1", "audio", "transcriptions"]) => {
+            let job = match parse_transcription_request(server, &mut request) {
+                Ok(job) => job,
+                Err(response) => return response,
+            };
+            let (id, mut status) = match server.submit(job) {
+                Ok(submitted) => submitted,
+                Err(response) => return response,
+            };
+            tokio::select! {
+                finished = status.wait_for(|s| s.is_finished()) => {
+                    if finished.is_err() {
+                        return Response::error(500, "transcription worker stopped");
+                    }
+                }
+                () = hung_up(stream) => {
+                    // Nobody is waiting for the transcript: free the worker.
+                    tracing::debug!("server: client of {} hung up, cancelling it", job_id(id));
+                    return server.cancel(id);
+                }
+            }
+            server.job_result(id)
+        }
+        ("POST", ["v1", "jobs"]) => match parse_transcription_request(server, &mut request) {
+            Ok(job) => match server.submit(job) {
+                Ok((id, _)) => Response { status: 202, ..server.job_json(id) },
+                Err(response) => response,
+            },
+            Err(response) => response,
+        },
+        ("GET", ["v1", "jobs"]) => {
+            let jobs: Vec<serde_json::Value> = server
+                .jobs
+                .lock()
+                .map(|jobs| jobs.iter().map(|(id, job)| job.to_json(*id)).collect())
+                .unwrap_or_default();
+            Response::json(200, &json!({ "object": "list", "data": jobs }))
+        }
+        (method, ["v1", "jobs", id, rest @ ..]) => {
+            let Some(id) = parse_job_id(id) else {
+                return Response::error(404, &format!("no job '{id}'"));
+            };
+            match (method, rest) {
+                ("GET", []) => server.job_json(id),
+                ("DELETE", []) => server.cancel(id),
+                ("GET", ["result"]) => server.job_result(id),
+                _ => Response::error(405, "method not allowed"),
+            }
+        }
+        _ => Response::error(404, &format!("no route for {} {}", request.method, request.path)),
+    }
+}
+
+/// Resolves once the client closes its side of `stream` (or it fails). Anything
+/// it sends after its one request is ignored.
+async fn hung_up(stream: &mut TcpStream) {
+    let mut chunk = [0u8; 512];
+    while matches!(stream.read(&mut chunk).await, Ok(n) if n > 0) {}
+}
+
+/// `GET /v1/models`: every transcription model in the manifest, plus the
+/// OpenAI name that maps to the default model.
+fn list_models() -> Response {
+    let data: Vec<serde_json::Value> = std::iter::once(OPENAI_MODEL)
+        .chain(manifest::MANIFEST.models.iter().map(|m| m.id.as_str()))
+        .map(|id| json!({ "id": id, "object": "model", "owned_by": "autosubs" }))
+        .collect();
+    Response::json(200, &json!({ "object": "list", "data": data }))
+}
+
+/// Validate the multipart form of a transcription request, taking its upload.
+fn parse_transcription_request<R: Runtime>(server: &Server<R>, request: &mut Request) -> Result<JobRequest, Response> {
+    let parts = request
+        .parts
+        .as_mut()
+        .ok_or_else(|| Response::error(400, "expected a multipart/form-data body"))?;
+    let upload = parts
+        .iter_mut()
+        .find(|p| p.name == "file")
+        .and_then(|p| p.file.take())
+        .ok_or_else(|| Response::error(400, "missing 'file'"))?;
+    let field = |name: &str| {
+        parts
+            .iter()
+            .find(|p| p.name == name && p.filename.is_none())
+            .map(|p| String::from_utf8_lossy(&p.data).trim().to_string())
+            .filter(|v| !v.is_empty())
+    };
+
+    let model = match field("model") {
+        None => server.default_model.clone(),
+        Some(m) if m == OPENAI_MODEL => server.default_model.clone(),
+        Some(m) if manifest::get(&m).is_some() => m,
+        Some(m) => return Err(Response::error(400, &format!("unknown model '{m}' (see GET /v1/models)"))),
+    };
+    let format_name = field("response_format").unwrap_or_else(|| "json".into());
+    let format = ResponseFormat::parse(&format_name).ok_or_else(|| {
+        Response::error(400, &format!("unsupported response_format '{format_name}' (expected json, text, srt, vtt, or verbose_json)"))
+    })?;
+    let granularities = parts
+        .iter()
+        .filter(|p| p.name == "timestamp_granularities[]" || p.name == "timestamp_granularities")
+        .map(|p| String::from_utf8_lossy(&p.data).trim().to_string())
+        .collect();
+
+    let options = FrontendTranscribeOptions {
+        audio_path: String::new(),
+        offset: None,
+        model,
+        lang: field("language"),
+        translate: Some(false),
+        target_language: None,
+        enable_dtw: None,
+        enable_gpu: None,
+        enable_diarize: Some(false),
+        enable_forced_alignment: Some(false),
+        max_speakers: None,
+        density: None,
+        max_lines: None,
+        custom_max_chars_per_line: None,
+        text_case: None,
+        remove_punctuation: None,
+        censored_words: None,
+        custom_prompt: field("prompt"),
+        asr_model_path: None,
+        vad_model_path: None,
+        diarize_segment_path: None,
+        diarize_embedding_path: None,
+        aligner_model_dir: None,
+    };
+    Ok(JobRequest { options, format, granularities, upload })
+}
+
+/// Render a transcript the way OpenAI's endpoint does for `format`.
+fn render_response(transcript: &Transcript, format: ResponseFormat, granularities: &[String]) -> Response {
+    let mut verbose = serde_json::to_value(transcript.to_verbose_json()).unwrap_or_default();
+    let text = verbose["text"].as_str().unwrap_or_default().to_string();
+    match format {
+        ResponseFormat::Json => Response::json(200, &json!({ "text": text })),
+        ResponseFormat::Text => Response::text(200, format!("{text}\n")),
+        ResponseFormat::VerboseJson => {
+            // Segments are the default granularity; words only when asked for.
+            let wants = |g: &str| granularities.iter().any(|x| x == g);
+            if let Some(obj) = verbose.as_object_mut() {
+                if !wants("word") {
+                    obj.remove("words");
+                }
+                if !granularities.is_empty() && !wants("segment") {
+                    obj.remove("segments");
+                }
+            }
+            Response::json(200, &verbose)
+        }
+        ResponseFormat::Srt | ResponseFormat::Vtt => {
+            let subtitle = if format == ResponseFormat::Srt { SubtitleFormat::Srt } else { SubtitleFormat::Vtt };
+            let opts = export_options(&transcript.segments, &transcript.speakers, Some(&transcript.language));
+            let body = render_export(&transcript.segments, subtitle, &opts);
+            Response { status: 200, content_type: "text/plain; charset=utf-8", body }
+        }
+    }
+}
+
+// --- HTTP ---
+
+struct Request {
+    method: String,
+    path: String,
+    headers: Vec<(String, String)>,
+    /// The body, unless it is a form.
+    body: Vec<u8>,
+    /// The parts of a `multipart/form-data` body.
+    parts: Option<Vec<Part>>,
+}
+
+impl Request {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter().find(|(n, _)| n.eq_ignore_ascii_case(name)).map(|(_, v)| v.as_str())
+    }
+}
+
+struct Response {
+    status: u16,
+    content_type: &'static str,
+    body: Vec<u8>,
+}
+
+impl Response {
+    fn json(status: u16, value: &serde_json::Value) -> Self {
+        Self { status, content_type: "application/json", body: value.to_string().into_bytes() }
+    }
+
+    fn text(status: u16, text: String) -> Self {
+        Self { status, content_type: "text/plain; charset=utf-8", body: text.into_bytes() }
+    }
+
+    /// An error in OpenAI's shape.
+    fn error(status: u16, message: &str) -> Self {
+        let kind = if status >= 500 { "server_error" } else { "invalid_request_error" };
+        Self::json(status, &json!({ "error": { "message": message, "type": kind, "param": null, "code": null } }))
+    }
+
+    async fn write(&self, stream: &mut TcpStream) -> std::io::Result<()> {
+        let reason = match self.status {
+            200 => "OK",
+            202 => "Accepted",
+            400 => "Bad Request",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            409 => "Conflict",
+            411 => "Length Required",
+            413 => "Payload Too Large",
+            _ => "Internal Server Error",
+        };
+        let head = format!(
+            "HTTP/1.1 {} {reason}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            self.status,
+            self.content_type,
+            self.body.len()
+        );
+        stream.write_all(head.as_bytes()).await?;
+        stream.write_all(&self.body).await?;
+        stream.flush().await
+    }
+}
+
+/// Read one request. Malformed or oversized requests come back as the error
+/// response to send.
+async fn read_request(stream: &mut TcpStream) -> std::io::Result<Result<Request, Response>> {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 8192];
+    let header_end = loop {
+        if let Some(pos) = find(&buf, b"\r\n\r\n", 0) {
+            break pos;
+        }
+        if buf.len() > MAX_HEADER_BYTES {
+            return Ok(Err(Response::error(413, "request headers too large")));
+        }
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    };
+
+    let head = String::from_utf8_lossy(&buf[..header_end]).into_owned();
+    let mut lines = head.split("\r\n");
+    let mut request_line = lines.next().unwrap_or_default().split_whitespace();
+    let (Some(method), Some(path)) = (request_line.next(), request_line.next()) else {
+        return Ok(Err(Response::error(400, "malformed request line")));
+    };
+    let headers: Vec<(String, String)> = lines
+        .filter_map(|l| l.split_once(':'))
+        .map(|(n, v)| (n.trim().to_string(), v.trim().to_string()))
+        .collect();
+    let mut request =
+        Request { method: method.to_string(), path: path.to_string(), headers, body: Vec::new(), parts: None };
+
+    if request.header("transfer-encoding").is_some() {
+        return Ok(Err(Response::error(411, "send the body with a Content-Length")));
+    }
+    let length: usize = request.header("content-length").and_then(|v| v.parse().ok()).unwrap_or(0);
+    if length > MAX_BODY_BYTES {
+        return Ok(Err(Response::error(413, "request body too large")));
+    }
+    // curl asks before sending large uploads.
+    if request.header("expect").is_some_and(|v| v.eq_ignore_ascii_case("100-continue")) {
+        stream.write_all(b"HTTP/1.1 100 Continue\r\n\r\n").await?;
+    }
+    let mut body = Body { stream, buf: buf.split_off(header_end + 4), remaining: 0 };
+    body.buf.truncate(length);
+    body.remaining = length - body.buf.len();
+
+    let boundary = multipart_boundary(request.header("content-type").unwrap_or_default()).map(str::to_string);
+    if let Some(boundary) = boundary {
+        request.parts = match read_multipart(&mut body, &boundary).await? {
+            Ok(parts) => Some(parts),
+            Err(response) => return Ok(Err(response)),
+        };
+        return Ok(Ok(request));
+    }
+    if length > MAX_BUFFERED_BYTES {
+        return Ok(Err(Response::error(413, "request body too large")));
+    }
+    while body.fill().await? {}
+    request.body = body.buf;
+    Ok(Ok(request))
+}
+
+/// The boundary of a `multipart/form-data` content type.
+fn multipart_boundary(content_type: &str) -> Option<&str> {
+    content_type
+        .split(';')
+        .map(str::trim)
+        .find_map(|p| p.strip_prefix("boundary="))
+        .map(|b| b.trim_matches('"'))
+        .filter(|_| content_type.starts_with("multipart/form-data"))
+}
+
+/// A request body of known length, read as it is needed.
+struct Body<'a, S> {
+    stream: &'a mut S,
+    /// Read but not yet consumed.
+    buf: Vec<u8>,
+    /// Not yet read.
+    remaining: usize,
+}
+
+impl<S: AsyncRead + Unpin> Body<'_, S> {
+    /// Read more of the body onto `buf`; `false` once all of it has been read.
+    async fn fill(&mut self) -> std::io::Result<bool> {
+        if self.remaining == 0 {
+            return Ok(false);
+        }
+        let mut chunk = [0u8; 8192];
+        let want = self.remaining.min(chunk.len());
+        let n = self.stream.read(&mut chunk[..want]).await?;
+        if n == 0 {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+        self.buf.extend_from_slice(&chunk[..n]);
+        self.remaining -= n;
+        Ok(true)
+    }
+
+    /// Read until `needle` is in `buf`, giving up at the end of the body or
+    /// once `buf` holds [`MAX_HEADER_BYTES`] without it.
+    async fn find(&mut self, needle: &[u8]) -> std::io::Result<Option<usize>> {
+        loop {
+            if let Some(pos) = find(&self.buf, needle, 0) {
+                return Ok(Some(pos));
+            }
+            if self.buf.len() > MAX_HEADER_BYTES || !self.fill().await? {
+                return Ok(None);
+            }
+        }
+    }
+}
+
+/// One part of a `multipart/form-data` body.
+struct Part {
+    name: String,
+    filename: Option<String>,
+    /// The value of a field that isn't a file.
+    data: Vec<u8>,
+    /// The contents of a file field, written out as they arrived.
+    file: Option<tempfile::NamedTempFile>,
+}
+
+impl Part {
+    fn write(&mut self, bytes: &[u8]) -> Result<(), Response> {
+        match &mut self.file {
+            Some(file) => std::io::Write::write_all(file, bytes)
+                .map_err(|e| Response::error(500, &format!("failed to store upload: {e}"))),
+            None if self.data.len() + bytes.len() > MAX_BUFFERED_BYTES => {
+                Err(Response::error(413, &format!("form field '{}' too large", self.name)))
+            }
+            None => {
+                self.data.extend_from_slice(bytes);
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Read a `multipart/form-data` body into its parts, streaming files to disk
+/// so an upload is never held in memory whole. Malformed forms come back as the
+/// error response to send.
+async fn read_multipart<S: AsyncRead + Unpin>(
+    body: &mut Body<'_, S>,
+    boundary: &str,
+) -> std::io::Result<Result<Vec<Part>, Response>> {
+    let malformed = |message: &str| Ok(Err(Response::error(400, message)));
+    let delimiter = format!("--{boundary}").into_bytes();
+    let next = [b"\r\n".as_slice(), &delimiter].concat();
+    let Some(start) = body.find(&delimiter).await? else {
+        return malformed("multipart body has no parts");
+    };
+    body.buf.drain(..start + delimiter.len());
+
+    let mut parts = Vec::new();
+    loop {
+        while body.buf.len() < 2 && body.fill().await? {}
+        if body.buf.starts_with(b"--") {
+            return Ok(Ok(parts));
+        }
+        let Some(headers_end) = body.find(b"\r\n\r\n").await? else {
+            return malformed("malformed multipart part");
+        };
+        let headers = String::from_utf8_lossy(&body.buf[..headers_end]).into_owned();
+        body.buf.drain(..headers_end + 4);
+
+        let disposition = headers
+            .split("\r\n")
+            .filter_map(|l| l.split_once(':'))
+            .find(|(n, _)| n.trim().eq_ignore_ascii_case("content-disposition"))
+            .map(|(_, v)| v)
+            .unwrap_or_default();
+        let param = |key: &str| {
+            disposition.split(';').map(str::trim).find_map(|p| {
+                let (k, v) = p.split_once('=')?;
+                (k.trim() == key).then(|| v.trim().trim_matches('"').to_string())
+            })
+        };
+        let filename = param("filename");
+        let file = match &filename {
+            // Keep the upload's extension so audio normalization can tell what
+            // it is.
+            Some(filename) => {
+                let ext = std::path::Path::new(filename).extension().and_then(|e| e.to_str()).unwrap_or("bin");
+                let file = tempfile::Builder::new().prefix("autosubs-upload-").suffix(&format!(".{ext}")).tempfile();
+                match file {
+                    Ok(file) => Some(file),
+                    Err(e) => return Ok(Err(Response::error(500, &format!("failed to store upload: {e}")))),
+                }
+            }
+            None => None,
+        };
+        let mut part = Part { name: param("name").unwrap_or_default(), filename, data: Vec::new(), file };
+
+        // Pass the data on as it arrives, holding back only what could be the
+        // start of the next delimiter.
+        let data_end = loop {
+            if let Some(end) = find(&body.buf, &next, 0) {
+                break end;
+            }
+            let ready = body.buf.len().saturating_sub(next.len() - 1);
+            if let Err(response) = part.write(&body.buf[..ready]) {
+                return Ok(Err(response));
+            }
+            body.buf.drain(..ready);
+            if !body.fill().await? {
+                return malformed("unterminated multipart part");
+            }
+        };
+        if let Err(response) = part.write(&body.buf[..data_end]) {
+            return Ok(Err(response));
+        }
+        body.buf.drain(..data_end + next.len());
+        parts.push(part);
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+    haystack.get(from..)?.windows(needle.len()).position(|w| w == needle).map(|p| p + from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transcript_types::{Segment, WordTimestamp};
+    use tauri::App;
+    use tauri::test::{MockRuntime, mock_app};
+
+    const BOUNDARY: &str = "xYzBoundary";
+
+    /// A server without a worker; the receiver stands in for its queue.
+    fn test_server() -> (App<MockRuntime>, Server<MockRuntime>, mpsc::UnboundedReceiver<u64>) {
+        let app = mock_app();
+        let (queue, jobs_rx) = mpsc::unbounded_channel();
+        let server = Server {
+            app: app.handle().clone(),
+            default_model: manifest::MANIFEST.models[0].id.clone(),
+            jobs: Mutex::new(BTreeMap::new()),
+            next_id: AtomicU64::new(1),
+            queue,
+            running: Mutex::new(None),
+        };
+        (app, server, jobs_rx)
+    }
+
+    /// `(name, filename, data)` fields as a `multipart/form-data` body.
+    fn multipart_body(fields: &[(&str, Option<&str>, &[u8])]) -> Vec<u8> {
+        let mut body = Vec::new();
+        for (name, filename, data) in fields {
+            body.extend_from_slice(format!("--{BOUNDARY}\r\nContent-Disposition: form-data; name=\"{name}\"").as_bytes());
+            if let Some(filename) = filename {
+                body.extend_from_slice(format!("; filename=\"{filename}\"").as_bytes());
+            }
+            body.extend_from_slice(b"\r\n\r\n");
+            body.extend_from_slice(data);
+            body.extend_from_slice(b"\r\n");
+        }
+        body.extend_from_slice(format!("--{BOUNDARY}--\r\n").as_bytes());
+        body
+    }
+
+    /// Parse `body` as a form of `length` bytes, read in small pieces.
+    async fn read_form(body: &[u8], length: usize) -> std::io::Result<Result<Vec<Part>, Response>> {
+        let mut stream = tokio::io::BufReader::with_capacity(1000, body);
+        let mut body = Body { stream: &mut stream, buf: Vec::new(), remaining: length };
+        read_multipart(&mut body, BOUNDARY).await
+    }
+
+    /// A parsed form request, as `read_request` would hand it on.
+    fn form_request(fields: &[(&str, Option<&str>, &[u8])]) -> Request {
+        let parts = fields
+            .iter()
+            .map(|&(name, filename, data)| {
+                let file = filename.map(|_| {
+                    let mut file = tempfile::NamedTempFile::new().unwrap();
+                    std::io::Write::write_all(&mut file, data).unwrap();
+                    file
+                });
+                let data = if file.is_some() { Vec::new() } else { data.to_vec() };
+                Part { name: name.into(), filename: filename.map(Into::into), data, file }
+            })
+            .collect();
+        Request {
+            method: "POST".into(),
+            path: "/v1/jobs".into(),
+            headers: Vec::new(),
+            body: Vec::new(),
+            parts: Some(parts),
+        }
+    }
+
+    /// `fields` as the raw request for `path`.
+    fn raw_form_request(path: &str, fields: &[(&str, Option<&str>, &[u8])]) -> Vec<u8> {
+        let form = multipart_body(fields);
+        let mut raw = format!(
+            "POST {path} HTTP/1.1\r\nContent-Type: multipart/form-data; boundary={BOUNDARY}\r\nContent-Length: {}\r\n\r\n",
+            form.len()
+        )
+        .into_bytes();
+        raw.extend_from_slice(&form);
+        raw
+    }
+
+    fn error_message(response: &Response) -> String {
+        let body: serde_json::Value = serde_json::from_slice(&response.body).unwrap();
+        body["error"]["message"].as_str().unwrap().to_string()
+    }
+
+    fn transcript() -> Transcript {
+        let word = |word: &str, start: f64, end: f64| WordTimestamp { word: word.into(), start, end, probability: None };
+        Transcript {
+            processing_time_sec: 0,
+            language: "en".into(),
+            segments: Vec::new(),
+            original_segments: vec![Segment {
+                start: 0.0,
+                end: 1.0,
+                text: "Hello there".into(),
+                speaker_id: None,
+                words: Some(vec![word("Hello", 0.0, 0.4), word("there", 0.5, 1.0)]),
+                stats: None,
+            }],
+            speakers: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn multipart_streams_files_to_disk() {
+        // Long enough to span many reads, with near-delimiters across them.
+        let audio: Vec<u8> = (0..20_000u32).flat_map(|i| if i % 997 == 0 { *b"\r\n--xYzB" } else { [i as u8; 8] }).collect();
+        let body = multipart_body(&[("model", None, b"whisper-1"), ("file", Some("a b.mp3"), &audio)]);
+        let mut parts = read_form(&body, body.len()).await.unwrap().ok().unwrap();
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0].name, "model");
+        assert_eq!(parts[0].filename, None);
+        assert_eq!(parts[0].data, b"whisper-1");
+        assert!(parts[0].file.is_none());
+        assert_eq!(parts[1].name, "file");
+        assert_eq!(parts[1].filename.as_deref(), Some("a b.mp3"));
+        assert!(parts[1].data.is_empty());
+        let file = parts[1].file.take().unwrap();
+        assert_eq!(file.path().extension().and_then(|e| e.to_str()), Some("mp3"));
+        assert!(std::fs::read(file.path()).unwrap() == audio);
+    }
+
+    #[tokio::test]
+    async fn multipart_rejects_malformed_forms() {
+        let message = |result: std::io::Result<Result<Vec<Part>, Response>>| error_message(&result.unwrap().err().unwrap());
+        let body = format!("--{BOUNDARY}\r\nContent-Disposition: form-data; name=\"model\"\r\n\r\nwhisper-1");
+        assert_eq!(message(read_form(body.as_bytes(), body.len()).await), "unterminated multipart part");
+        assert_eq!(message(read_form(b"no delimiter here", 17).await), "multipart body has no parts");
+
+        let long = vec![b'x'; MAX_BUFFERED_BYTES + 1];
+        let body = multipart_body(&[("prompt", None, &long)]);
+        assert_eq!(message(read_form(&body, body.len()).await), "form field 'prompt' too large");
+
+        // The connection closing early is an I/O error, not a bad form.
+        let body = multipart_body(&[("model", None, b"whisper-1")]);
+        assert!(read_form(&body[..body.len() - 10], body.len()).await.is_err());
+    }
+
+    #[test]
+    fn boundary_may_be_quoted() {
+        assert_eq!(multipart_boundary("multipart/form-data; boundary=\"a b\""), Some("a b"));
+        assert_eq!(multipart_boundary("multipart/form-data;boundary=xyz"), Some("xyz"));
+        assert_eq!(multipart_boundary("application/json; boundary=xyz"), None);
+    }
+
+    #[test]
+    fn transcription_request_takes_the_upload() {
+        let (_app, server, _rx) = test_server();
+        let mut request = form_request(&[
+            ("file", Some("clip.mp3"), b"audio"),
+            ("model", None, b"whisper-1"),
+            ("language", None, b"de"),
+            ("response_format", None, b"verbose_json"),
+            ("timestamp_granularities[]", None, b"word"),
+        ]);
+        let job = parse_transcription_request(&server, &mut request).ok().unwrap();
+        assert_eq!(job.options.model, server.default_model);
+        assert_eq!(job.options.lang.as_deref(), Some("de"));
+        assert!(job.format == ResponseFormat::VerboseJson);
+        assert_eq!(job.granularities, ["word"]);
+        assert_eq!(std::fs::read(job.upload.path()).unwrap(), b"audio");
+    }
+
+    #[test]
+    fn transcription_request_errors() {
+        let (_app, server, _rx) = test_server();
+        let cases: [(&[(&str, Option<&str>, &[u8])], &str); 3] = [
+            (&[("model", None, b"whisper-1")], "missing 'file'"),
+            (&[("file", Some("a.wav"), b"x"), ("model", None, b"no-such-model")], "unknown model 'no-such-model'"),
+            (&[("file", Some("a.wav"), b"x"), ("response_format", None, b"xml")], "unsupported response_format 'xml'"),
+        ];
+        for (fields, expected) in cases {
+            let response = parse_transcription_request(&server, &mut form_request(fields)).err().unwrap();
+            assert_eq!(response.status, 400);
+            assert!(error_message(&response).starts_with(expected), "{}", error_message(&response));
+        }
+
+        let mut request = form_request(&[("file", Some("a.wav"), b"x")]);
+        request.parts = None;
+        assert_eq!(parse_transcription_request(&server, &mut request).err().unwrap().status, 400);
+    }
+
+    #[test]
+    fn verbose_json_filters_by_granularity() {
+        let keys = |granularities: &[&str]| {
+            let granularities: Vec<String> = granularities.iter().map(|g| g.to_string()).collect();
+            let response = render_response(&transcript(), ResponseFormat::VerboseJson, &granularities);
+            let body: serde_json::Value = serde_json::from_slice(&response.body).unwrap();
+            (body.get("segments").is_some(), body.get("words").is_some())
+        };
+        assert_eq!(keys(&[]), (true, false));
+        assert_eq!(keys(&["word"]), (false, true));
+        assert_eq!(keys(&["segment"]), (true, false));
+        assert_eq!(keys(&["word", "segment"]), (true, true));
+
+        let text = render_response(&transcript(), ResponseFormat::Text, &[]);
+        assert_eq!(text.body, b"Hello there\n");
+    }
+
+    #[test]
+    fn submit_forgets_oldest_finished_jobs() {
+        let (_app, server, mut rx) = test_server();
+        let submit = || {
+            let job = parse_transcription_request(&server, &mut form_request(&[("file", Some("a.wav"), b"x")])).ok().unwrap();
+            server.submit(job).ok().unwrap().0
+        };
+        let first: Vec<u64> = (0..MAX_FINISHED_JOBS).map(|_| submit()).collect();
+        for id in &first {
+            assert_eq!(rx.try_recv().ok(), Some(*id));
+            server.jobs.lock().unwrap()[id].status.send_replace(JobStatus::Completed);
+        }
+
+        let running = submit();
+        let jobs = server.jobs.lock().unwrap();
+        assert_eq!(jobs.len(), MAX_FINISHED_JOBS);
+        assert!(!jobs.contains_key(&first[0]));
+        assert!(jobs.contains_key(&first[1]));
+        assert!(jobs[&running].status() == JobStatus::Queued);
+    }
+
+    #[test]
+    fn cancel_sets_the_jobs_own_token() {
+        let (_app, server, _rx) = test_server();
+        let submit = || {
+            let job = parse_transcription_request(&server, &mut form_request(&[("file", Some("a.wav"), b"x")])).ok().unwrap();
+            server.submit(job).ok().unwrap().0
+        };
+        let (running, queued) = (submit(), submit());
+        server.jobs.lock().unwrap()[&running].status.send_replace(JobStatus::Running);
+
+        assert_eq!(server.cancel(running).status, 200);
+        let jobs = server.jobs.lock().unwrap();
+        assert!(jobs[&running].cancel.load(Ordering::Relaxed));
+        assert!(jobs[&running].status() == JobStatus::Cancelled);
+        assert!(!jobs[&queued].cancel.load(Ordering::Relaxed));
+        assert!(jobs[&queued].status() == JobStatus::Queued);
+    }
+
+    /// Send `raw` to a loopback connection served by `handle_connection` and
+    /// return the status line and body of the reply.
+    async fn round_trip(server: &Server<MockRuntime>, raw: Vec<u8>) -> (String, serde_json::Value) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let client = tokio::spawn(async move {
+            let mut stream = TcpStream::connect(addr).await.unwrap();
+            stream.write_all(&raw).await.unwrap();
+            let mut reply = Vec::new();
+            stream.read_to_end(&mut reply).await.unwrap();
+            reply
+        });
+        let (stream, _) = listener.accept().await.unwrap();
+        handle_connection(server, stream).await.unwrap();
+
+        let reply = client.await.unwrap();
+        let split = find(&reply, b"\r\n\r\n", 0).unwrap();
+        let head = String::from_utf8_lossy(&reply[..split]).into_owned();
+        let status = head.lines().next().unwrap().to_string();
+        (status, serde_json::from_slice(&reply[split + 4..]).unwrap())
+    }
+
+    #[tokio::test]
+    async fn serves_requests_over_loopback() {
+        let (_app, server, mut rx) = test_server();
+
+        let (status, body) = round_trip(&server, b"GET /health HTTP/1.1\r\nHost: localhost\r\n\r\n".to_vec()).await;
+        assert_eq!(status, "HTTP/1.1 200 OK");
+        assert_eq!(body["status"], "ok");
+
+        let (status, _) = round_trip(&server, b"GET /nowhere HTTP/1.1\r\n\r\n".to_vec()).await;
+        assert_eq!(status, "HTTP/1.1 404 Not Found");
+
+        let raw = raw_form_request("/v1/jobs", &[("file", Some("a.wav"), b"audio"), ("response_format", None, b"srt")]);
+        let (status, body) = round_trip(&server, raw).await;
+        assert_eq!(status, "HTTP/1.1 202 Accepted");
+        assert_eq!(body["status"], "queued");
+        assert_eq!(body["id"], "job_1");
+        assert_eq!(rx.try_recv().ok(), Some(1));
+
+        let (status, body) = round_trip(&server, b"DELETE /v1/jobs/job_1 HTTP/1.1\r\n\r\n".to_vec()).await;
+        assert_eq!(status, "HTTP/1.1 200 OK");
+        assert_eq!(body["status"], "cancelled");
+    }
+
+    #[tokio::test]
+    async fn hanging_up_cancels_a_synchronous_transcription() {
+        let (_app, server, mut rx) = test_server();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap()).await.unwrap();
+        let raw = raw_form_request("/v1/audio/transcriptions", &[("file", Some("a.wav"), b"audio")]);
+        client.write_all(&raw).await.unwrap();
+        let (stream, _) = listener.accept().await.unwrap();
+
+        // No worker runs the job; the client gives up once it is queued.
+        let hang_up = async {
+            while rx.try_recv().is_err() {
+                tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            }
+            drop(client);
+        };
+        let (_, ()) = tokio::join!(handle_connection(&server, stream), hang_up);
+
+        let jobs = server.jobs.lock().unwrap();
+        assert!(jobs[&1].status() == JobStatus::Cancelled);
+        assert!(jobs[&1].cancel.load(Ordering::Relaxed));
+    }
+}
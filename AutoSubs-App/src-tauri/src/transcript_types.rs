@@ -1,6 +1,7 @@
 // use eyre::Result;
 // use num::integer::div_floor;
 use serde::{Deserialize, Serialize};
+use transcription_engine::{SegmentStats, compression_ratio};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct WordTimestamp {
@@ -20,6 +21,9 @@ pub struct Segment {
     pub speaker_id: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub words: Option<Vec<WordTimestamp>>,
+    /// Decoder statistics from engines that report them (Whisper only).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stats: Option<SegmentStats>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -68,6 +72,11 @@ pub struct Transcript {
     pub speakers: Vec<Speaker>,
 }
 
+/// One segment of OpenAI's `verbose_json` response.
+///
+/// Fields the engine didn't report fall back to fixed defaults: `seek` 0,
+/// `tokens` empty, `temperature` 0, `avg_logprob` 0 and `no_speech_prob` 0.
+/// `compression_ratio` is always computed from the segment text.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct JsonSegment {
     id: usize,
@@ -85,10 +94,141 @@ pub struct JsonSegment {
     words: Vec<JsonWordTimestamp>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// A word in `verbose_json`; `probability` defaults to 1.0 when unknown.
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct JsonWordTimestamp {
     word: String,
     start: f64,
     end: f64,
     probability: f32,
 }
+
+impl JsonSegment {
+    fn new(id: usize, seg: &Segment) -> Self {
+        let stats = seg.stats.clone().unwrap_or_default();
+        JsonSegment {
+            id,
+            seek: stats.seek,
+            start: seg.start,
+            end: seg.end,
+            text: seg.text.clone(),
+            speaker_id: seg.speaker_id.clone(),
+            tokens: stats.tokens,
+            temperature: stats.temperature,
+            avg_logprob: stats.avg_logprob,
+            compression_ratio: compression_ratio(&seg.text),
+            no_speech_prob: stats.no_speech_prob,
+            words: seg
+                .words
+                .iter()
+                .flatten()
+                .map(|w| JsonWordTimestamp {
+                    word: w.word.clone(),
+                    start: w.start,
+                    end: w.end,
+                    probability: w.probability.unwrap_or(1.0),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// OpenAI's `verbose_json` transcription response.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VerboseJson {
+    task: String,
+    language: String,
+    duration: f64,
+    text: String,
+    segments: Vec<JsonSegment>,
+    words: Vec<JsonWordTimestamp>,
+}
+
+/// WhisperX's aligned JSON output. Speakers are labelled `SPEAKER_00`,
+/// `SPEAKER_01`, … like pyannote; `score` defaults to 1.0 when unknown.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WhisperXJson {
+    segments: Vec<WhisperXSegment>,
+    word_segments: Vec<WhisperXWord>,
+    language: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WhisperXSegment {
+    start: f64,
+    end: f64,
+    text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    speaker: Option<String>,
+    words: Vec<WhisperXWord>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WhisperXWord {
+    word: String,
+    start: f64,
+    end: f64,
+    score: f32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    speaker: Option<String>,
+}
+
+/// pyannote-style label for a 1-based AutoSubs speaker id ("1" -> "SPEAKER_00").
+fn whisperx_speaker(id: &str) -> String {
+    match id.trim().parse::<usize>() {
+        Ok(n) => format!("SPEAKER_{:02}", n.saturating_sub(1)),
+        Err(_) => id.to_string(),
+    }
+}
+
+impl Transcript {
+    /// Unformatted engine segments, which keep decoder statistics and word
+    /// data; falls back to the display segments for transcripts without them.
+    pub(crate) fn source_segments(&self) -> &[Segment] {
+        if self.original_segments.is_empty() {
+            &self.segments
+        } else {
+            &self.original_segments
+        }
+    }
+
+    pub fn to_verbose_json(&self) -> VerboseJson {
+        let source = self.source_segments();
+        let segments: Vec<JsonSegment> =
+            source.iter().enumerate().map(|(i, seg)| JsonSegment::new(i, seg)).collect();
+        let words = segments.iter().flat_map(|s| s.words.iter().cloned()).collect();
+        VerboseJson {
+            task: "transcribe".to_string(),
+            language: self.language.clone(),
+            duration: source.iter().map(|s| s.end).fold(0.0, f64::max),
+            text: source.iter().map(|s| s.text.trim()).filter(|t| !t.is_empty()).collect::<Vec<_>>().join(" "),
+            segments,
+            words,
+        }
+    }
+
+    pub fn to_whisperx(&self) -> WhisperXJson {
+        let segments: Vec<WhisperXSegment> = self
+            .source_segments()
+            .iter()
+            .map(|seg| {
+                let speaker = seg.speaker_id.as_deref().map(whisperx_speaker);
+                let words = seg
+                    .words
+                    .iter()
+                    .flatten()
+                    .map(|w| WhisperXWord {
+                        word: w.word.trim().to_string(),
+                        start: w.start,
+                        end: w.end,
+                        score: w.probability.unwrap_or(1.0),
+                        speaker: speaker.clone(),
+                    })
+                    .collect();
+                WhisperXSegment { start: seg.start, end: seg.end, text: seg.text.clone(), speaker, words }
+            })
+            .collect();
+        let word_segments = segments.iter().flat_map(|s| s.words.iter().cloned()).collect();
+        WhisperXJson { segments, word_segments, language: self.language.clone() }
+    }
+}
@@ -6,13 +6,13 @@ use eyre::Result;
 use serde::{Deserialize, Serialize};
 use serde_json;
 use std::path::PathBuf;
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
 use std::time::Instant;
 use tauri::{AppHandle, Emitter, Manager, Runtime, command};
 use transcription_engine::{
-    Callbacks, ContentFormatting, Engine, EngineConfig, LabeledProgressFn, PostProcessConfig, ProgressType, SegmentStage,
-    Segment as WDSegment, TextCase, TextDensity, TranscribeOptions, process_segments,
+    Callbacks, ContentFormatting, Engine, EngineConfig, ExportOptions, FrameRate, LabeledProgressFn, PostProcessConfig, ProgressType,
+    SegmentStage, Segment as WDSegment, SpeakerColors, SubtitleFormat, TextCase, TextDensity, TranscribeOptions, process_segments,
 };
 
 // Frontend-compatible progress data type
@@ -87,7 +87,7 @@ fn round_to_places(val: f64, places: u32) -> f64 {
 }
 
 // --- Frontend Options Struct ---
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct FrontendTranscribeOptions {
     pub audio_path: String,
@@ -118,7 +118,7 @@ pub struct FrontendTranscribeOptions {
 }
 
 /// Parse a frontend text_case string ("none"|"lowercase"|"uppercase"|"titlecase") into TextCase.
-fn parse_text_case(s: Option<&str>) -> TextCase {
+pub fn parse_text_case(s: Option<&str>) -> TextCase {
     match s.map(|v| v.to_lowercase()) {
         Some(ref v) if v == "lowercase" => TextCase::Lowercase,
         Some(ref v) if v == "uppercase" => TextCase::Uppercase,
@@ -273,6 +273,21 @@ pub async fn ensure_models<R: Runtime>(
 pub async fn transcribe_audio<R: Runtime>(
     app: AppHandle<R>,
     options: FrontendTranscribeOptions,
+) -> Result<Transcript, String> {
+    transcribe_audio_with(app, options, &mut None, None).await
+}
+
+/// [`transcribe_audio`] with a caller-owned engine. The engine is created on
+/// first use and kept in `engine`, so a batch of files (the CLI) loads its
+/// models once rather than per file.
+///
+/// `cancel`, when given, is the only thing that cancels this run; otherwise
+/// the process-wide [`SHOULD_CANCEL`] flag (set by [`cancel_transcription`]) does.
+pub async fn transcribe_audio_with<R: Runtime>(
+    app: AppHandle<R>,
+    options: FrontendTranscribeOptions,
+    engine: &mut Option<Engine>,
+    cancel: Option<Arc<AtomicBool>>,
 ) -> Result<Transcript, String> {
     let start_time = Instant::now();
     let options_log = TranscribeOptionsLogView::from(&options);
@@ -289,9 +304,13 @@ pub async fn transcribe_audio<R: Runtime>(
     if let Ok(mut queue) = PROGRESS_QUEUE.lock() {
         queue.clear();
     }
-    if let Ok(mut should_cancel) = SHOULD_CANCEL.lock() {
+    if let Some(mut should_cancel) = SHOULD_CANCEL.lock().ok().filter(|_| cancel.is_none()) {
         *should_cancel = false;
     }
+    let is_cancelled: Arc<dyn Fn() -> bool + Send + Sync> = match cancel {
+        Some(token) => Arc::new(move || token.load(Ordering::Relaxed)),
+        None => Arc::new(|| SHOULD_CANCEL.lock().map(|c| *c).unwrap_or(false)),
+    };
 
     let emit_app = app.clone();
     let (stop_tx, mut stop_rx) = tokio::sync::oneshot::channel::<()>();
@@ -381,7 +400,7 @@ pub async fn transcribe_audio<R: Runtime>(
             asr_model_path: options.asr_model_path.clone(),
         };
 
-        let mut engine = Engine::new(engine_config);
+        let engine = engine.get_or_insert_with(|| Engine::new(engine_config));
 
         // Map frontend options to crate options
         let mut transcribe_options = TranscribeOptions::default();
@@ -464,14 +483,6 @@ pub async fn transcribe_audio<R: Runtime>(
         });
 
 
-        let is_cancelled: Arc<dyn Fn() -> bool + Send + Sync> = Arc::new(|| {
-            if let Ok(should_cancel) = SHOULD_CANCEL.lock() {
-                *should_cancel
-            } else {
-                false
-            }
-        });
-
         let speakers_app = app.clone();
         let speakers_identified: Arc<dyn Fn(usize) + Send + Sync> = Arc::new(move |count: usize| {
             tracing::info!("diarization identified {count} speaker(s)");
@@ -482,14 +493,12 @@ pub async fn transcribe_audio<R: Runtime>(
             progress: Some(progress_callback),
             new_segment_callback: Some(segment_callback),
             speakers_identified: Some(speakers_identified),
-            is_cancelled: Some(is_cancelled),
+            is_cancelled: Some(is_cancelled.clone()),
         };
 
         // Check for cancellation before starting transcription
-        if let Ok(should_cancel) = SHOULD_CANCEL.lock() {
-            if *should_cancel {
-                return Err("Transcription cancelled".to_string());
-            }
+        if is_cancelled() {
+            return Err("Transcription cancelled".to_string());
         }
 
         // Build content formatting options from frontend settings.
@@ -516,14 +525,9 @@ pub async fn transcribe_audio<R: Runtime>(
             .await
             .map_err(|e| {
                 // Check if this was a cancellation error
-                if let Ok(should_cancel) = SHOULD_CANCEL.lock() {
-                    if *should_cancel {
-                        tracing::info!("transcription cancelled by user");
-                        "Transcription cancelled".to_string()
-                    } else {
-                        tracing::error!("transcription failed: {:#}", e);
-                        format!("Transcription failed: {:#}", e)
-                    }
+                if is_cancelled() {
+                    tracing::info!("transcription cancelled by user");
+                    "Transcription cancelled".to_string()
                 } else {
                     tracing::error!("transcription failed: {:#}", e);
                     format!("Transcription failed: {:#}", e)
@@ -531,10 +535,8 @@ pub async fn transcribe_audio<R: Runtime>(
             })?;
 
         // Check for cancellation after transcription completes
-        if let Ok(should_cancel) = SHOULD_CANCEL.lock() {
-            if *should_cancel {
-                return Err("Transcription cancelled".to_string());
-            }
+        if is_cancelled() {
+            return Err("Transcription cancelled".to_string());
         }
 
         // Convert whisper-diarize-rs segments to app's Segment format
@@ -689,6 +691,30 @@ fn wd_to_app_segment(seg: &WDSegment) -> Segment {
         end: seg.end,
         text: seg.text.clone(),
         words,
+        stats: seg.stats.clone(),
+    }
+}
+
+/// Convert an app `Segment` back to the `transcription_engine` segment type.
+fn app_to_wd_segment(seg: &Segment) -> WDSegment {
+    let words = seg.words.as_ref().map(|words| {
+        words
+            .iter()
+            .map(|w| transcription_engine::WordTimestamp {
+                text: w.word.clone(),
+                start: w.start,
+                end: w.end,
+                probability: w.probability,
+            })
+            .collect()
+    });
+    WDSegment {
+        start: seg.start,
+        end: seg.end,
+        text: seg.text.clone(),
+        words,
+        speaker_id: seg.speaker_id.clone(),
+        stats: seg.stats.clone(),
     }
 }
 
@@ -778,31 +804,14 @@ pub async fn reformat_subtitles(
     segments: Vec<Segment>,
     options: FrontendFormattingOptions,
 ) -> Result<Vec<Segment>, String> {
-    // Convert app segments to engine segments (WDSegment)
-    let engine_segments: Vec<WDSegment> = segments
-        .iter()
-        .map(|seg| {
-            let words = seg.words.as_ref().map(|words| {
-                words
-                    .iter()
-                    .map(|w| transcription_engine::WordTimestamp {
-                        text: w.word.clone(),
-                        start: w.start,
-                        end: w.end,
-                        probability: w.probability,
-                    })
-                    .collect()
-            });
+    Ok(reformat_segments(&segments, &options))
+}
 
-            WDSegment {
-                start: seg.start,
-                end: seg.end,
-                text: seg.text.clone(),
-                words,
-                speaker_id: seg.speaker_id.clone(),
-            }
-        })
-        .collect();
+/// Rebuild cues from raw segments (a transcript's `original_segments`) with
+/// `options`. Shared by `reformat_subtitles` and `autosubs reformat`.
+pub fn reformat_segments(segments: &[Segment], options: &FrontendFormattingOptions) -> Vec<Segment> {
+    // Convert app segments to engine segments (WDSegment)
+    let engine_segments: Vec<WDSegment> = segments.iter().map(app_to_wd_segment).collect();
 
     // Build config from language profile, then apply density and max_lines.
     // Some engines do not report a language, so keep reformatting consistent
@@ -845,34 +854,203 @@ pub async fn reformat_subtitles(
     config.remove_punctuation = options.remove_punctuation.unwrap_or(false);
     config.censored_words = options.censored_words.clone().unwrap_or_default();
 
-    // Run the formatting engine
-    let formatted = process_segments(&engine_segments, &config);
-
-    // Convert back to app segments
-    let result: Vec<Segment> = formatted
-        .iter()
-        .map(|seg| {
-            let words = seg.words.as_ref().map(|words| {
-                words
-                    .iter()
-                    .map(|w| WordTimestamp {
-                        word: w.text.clone(),
-                        start: w.start,
-                        end: w.end,
-                        probability: w.probability,
-                    })
-                    .collect()
-            });
+    format_segments(&engine_segments, &config)
+}
 
-            Segment {
-                start: seg.start,
-                end: seg.end,
-                text: seg.text.clone(),
-                words,
-                speaker_id: seg.speaker_id.clone(),
+/// Run the formatting engine over engine segments and convert the result to
+/// app segments.
+fn format_segments(segments: &[WDSegment], config: &PostProcessConfig) -> Vec<Segment> {
+    process_segments(segments, config).iter().map(wd_to_app_segment).collect()
+}
+
+/// Re-wrap a transcript's raw segments with the line layout `format` needs,
+/// keeping the content formatting the transcript was produced with:
+///
+/// - SCC: the CEA-608 caption grid (`PostProcessConfig::cea608`)
+/// - LRC: one sung line per cue (`PostProcessConfig::apply_lyrics_layout`)
+/// - EBU STL: two teletext rows (`PostProcessConfig::apply_teletext_layout`)
+///
+/// Returns `None` for formats that use the transcript's own layout.
+pub fn format_for_export(
+    original_segments: &[Segment],
+    format: SubtitleFormat,
+    language: &str,
+    content: &ContentFormatting,
+) -> Option<Vec<Segment>> {
+    let engine_segments: Vec<WDSegment> = original_segments.iter().map(app_to_wd_segment).collect();
+    let for_language = || {
+        if language.eq_ignore_ascii_case("auto") {
+            let text = engine_segments.iter().map(|s| s.text.as_str()).collect::<Vec<_>>().join(" ");
+            PostProcessConfig::for_text(&text)
+        } else {
+            PostProcessConfig::for_language(language)
+        }
+    };
+    let mut config = match format {
+        SubtitleFormat::Scc => PostProcessConfig::cea608(),
+        SubtitleFormat::Lrc => {
+            let mut config = for_language();
+            config.apply_lyrics_layout();
+            config
+        }
+        SubtitleFormat::Stl => {
+            let mut config = for_language();
+            config.apply_teletext_layout();
+            config
+        }
+        _ => return None,
+    };
+    config.text_case = content.text_case;
+    config.remove_punctuation = content.remove_punctuation;
+    config.censored_words = content.censored_words.clone();
+    Some(format_segments(&engine_segments, &config))
+}
+
+/// Map each segment `speaker_id` to its `Speaker`.
+///
+/// `speakers` is ordered by first appearance (see
+/// `aggregate_speakers_from_segments`), so the n-th distinct id maps to the n-th
+/// speaker.
+fn speakers_by_id<'a>(segments: &[Segment], speakers: &'a [Speaker]) -> Vec<(String, &'a Speaker)> {
+    let mut by_id: Vec<(String, &Speaker)> = Vec::new();
+    let mut order: Vec<String> = Vec::new();
+    for speaker_id in segments.iter().filter_map(|s| s.speaker_id.as_deref()) {
+        let trimmed = speaker_id.trim();
+        if trimmed.is_empty() || trimmed == "?" || by_id.iter().any(|(id, _)| id == speaker_id) {
+            continue;
+        }
+        let raw_id = trimmed.strip_prefix("Speaker ").unwrap_or(trimmed).trim().to_string();
+        let index = match order.iter().position(|id| *id == raw_id) {
+            Some(index) => index,
+            None => {
+                order.push(raw_id);
+                order.len() - 1
             }
-        })
-        .collect();
+        };
+        if let Some(speaker) = speakers.get(index) {
+            by_id.push((speaker_id.to_string(), speaker));
+        }
+    }
+    by_id
+}
 
-    Ok(result)
+/// Colours the user enabled for a speaker, in the engine's export form.
+fn speaker_colors(speaker: &Speaker) -> SpeakerColors {
+    let color = |modifier: &ColorModifier| {
+        (modifier.enabled && !modifier.color.trim().is_empty()).then(|| modifier.color.clone())
+    };
+    SpeakerColors {
+        fill: color(&speaker.fill),
+        outline: color(&speaker.outline),
+        border: color(&speaker.border),
+    }
+}
+
+/// Export options for a transcript: speaker names and colours come from
+/// `speakers`, matched to segments by `speaker_id`.
+pub fn export_options(segments: &[Segment], speakers: &[Speaker], language: Option<&str>) -> ExportOptions {
+    let by_id = speakers_by_id(segments, speakers);
+    ExportOptions {
+        speaker_names: by_id.iter().map(|(id, s)| (id.clone(), s.name.clone())).collect(),
+        speaker_colors: by_id.iter().map(|(id, s)| (id.clone(), speaker_colors(s))).collect(),
+        language: language.map(str::to_string),
+        ..Default::default()
+    }
+}
+
+/// Render app segments with the engine's shared exporters. Used by the
+/// export commands and the CLI so their output is byte-identical.
+pub fn render_export(segments: &[Segment], format: SubtitleFormat, opts: &ExportOptions) -> Vec<u8> {
+    let engine_segments: Vec<WDSegment> = segments.iter().map(app_to_wd_segment).collect();
+    transcription_engine::formats::render_bytes(&engine_segments, format, opts)
+}
+
+/// Render subtitles to a file format (any name `SubtitleFormat::parse`
+/// accepts, e.g. `srt`, `vtt`, `ass`, `html`, `scc`, `fcpxml`, `otio`, `stl` or `csv`) and
+/// return the file contents as raw bytes, since some formats (EBU STL) are
+/// binary. The frontend writes the result to the path the user picked.
+/// `speaker_names` and `speaker_colors` are keyed by `speaker_id`; unmapped
+/// speakers are labelled `Speaker {id}` with default styling. `frame_rate`
+/// (e.g. `25`, `29.97`, `30000/1001`) selects frame-based timing for formats
+/// that use it; `roll_up` (2–4) writes SCC as roll-up instead of pop-on
+/// captions; `start_timecode` (e.g. `01:00:00:00`) is where FCPXML, xmeml and
+/// OTIO timelines and EBU STL programmes start. `word_rows` writes CSV/TSV
+/// with one row per word; pass the unformatted segments for those.
+/// `media_url` is the recording that Markdown and HTML timestamps link to, and
+/// `paragraph_timecodes` stamps every paragraph of those documents.
+#[command]
+pub async fn export_subtitles(
+    segments: Vec<Segment>,
+    format: String,
+    speaker_names: Option<std::collections::HashMap<String, String>>,
+    speaker_colors: Option<std::collections::HashMap<String, SpeakerColors>>,
+    language: Option<String>,
+    karaoke: Option<bool>,
+    frame_rate: Option<String>,
+    roll_up: Option<u8>,
+    start_timecode: Option<String>,
+    word_rows: Option<bool>,
+    media_url: Option<String>,
+    paragraph_timecodes: Option<bool>,
+) -> Result<tauri::ipc::Response, String> {
+    let format = SubtitleFormat::parse(&format)
+        .ok_or_else(|| format!("Unsupported export format '{format}'"))?;
+    let frame_rate = match frame_rate {
+        Some(rate) => Some(FrameRate::parse(&rate).ok_or_else(|| format!("Invalid frame rate '{rate}'"))?),
+        None => None,
+    };
+    let mut opts = ExportOptions {
+        speaker_names: speaker_names.unwrap_or_default(),
+        speaker_colors: speaker_colors.unwrap_or_default(),
+        language,
+        karaoke: karaoke.unwrap_or(false),
+        frame_rate,
+        roll_up,
+        start_frame: 0,
+        word_rows: word_rows.unwrap_or(false),
+        media_url,
+        paragraph_timecodes: paragraph_timecodes.unwrap_or(false),
+    };
+    if let Some(tc) = start_timecode {
+        opts.start_frame = opts
+            .sequence_rate()
+            .parse_timecode(&tc)
+            .ok_or_else(|| format!("Invalid start timecode '{tc}'"))?;
+    }
+    Ok(tauri::ipc::Response::new(render_export(&segments, format, &opts)))
+}
+
+/// Build a transcript from subtitle file contents. Speaker names from voice
+/// tags or ASS styles replace the generic `Speaker N` labels.
+pub fn import_transcript(content: &str, format: SubtitleFormat) -> Result<Transcript> {
+    let parsed = transcription_engine::formats::parse(content, format)?;
+    let segments: Vec<Segment> = parsed.segments.iter().map(wd_to_app_segment).collect();
+    let (mut speakers, segments) = aggregate_speakers_from_segments(&segments);
+    for speaker in &mut speakers {
+        let raw_id = speaker.name.strip_prefix("Speaker ").unwrap_or(&speaker.name);
+        if let Some(name) = parsed.speaker_names.get(raw_id) {
+            speaker.name = name.clone();
+        }
+    }
+    Ok(Transcript {
+        processing_time_sec: 0,
+        language: "auto".to_string(),
+        original_segments: segments.clone(),
+        segments,
+        speakers,
+    })
+}
+
+/// Import an existing SRT, WebVTT or ASS file as a transcript. The format is
+/// taken from the file extension, falling back to sniffing the contents.
+#[command]
+pub async fn import_subtitles(path: String) -> Result<Transcript, String> {
+    let path = expand_tilde(PathBuf::from(path));
+    let content = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+    let format = SubtitleFormat::from_path(&path)
+        .filter(|f| f.importable())
+        .or_else(|| transcription_engine::formats::detect(&content))
+        .ok_or_else(|| format!("Unrecognised subtitle format: {}", path.display()))?;
+    import_transcript(&content, format).map_err(|e| e.to_string())
 }
@@ -113,3 +113,85 @@ export function getDefaultFormattingOptions(settings: {
     
     return options;
 }
+
+/**
+ * Colours for one speaker in styled export formats (ASS), as hex strings.
+ */
+export interface ExportSpeakerColors {
+    fill?: string;
+    outline?: string;
+    border?: string;
+}
+
+/**
+ * Options for `exportSubtitles` and `exportSubtitlesBinary`.
+ */
+export type ExportSubtitleOptions = {
+    speakerNames?: Record<string, string>;
+    speakerColors?: Record<string, ExportSpeakerColors>;
+    language?: string;
+    karaoke?: boolean;
+    frameRate?: string;
+    rollUp?: number;
+    startTimecode?: string;
+    wordRows?: boolean;
+    mediaUrl?: string;
+    paragraphTimecodes?: boolean;
+};
+
+/**
+ * Render subtitles to a file format using the Rust export engine.
+ * The output is byte-identical to what the `autosubs` CLI writes.
+ *
+ * @param subtitles - The subtitles to export
+ * @param format - Export format ("text", "markdown", "html", "srt", "vtt", "ass", "imsc",
+ *                 "ebu-tt-d", "scc", "fcpxml", "xmeml", "otio", "lrc", "csv" or "tsv"; use
+ *                 `exportSubtitlesBinary` for "stl")
+ * @param options - Speaker names/colours keyed by speaker_id, the transcript
+ *                  language, whether to include per-word karaoke timing,
+ *                  the video frame rate (e.g. "25", "29.97") for frame-based timing,
+ *                  the roll-up depth (2-4) for roll-up SCC captions, the
+ *                  sequence start timecode (e.g. "01:00:00:00") for FCPXML/xmeml/OTIO/STL,
+ *                  whether CSV/TSV get one row per word (pass the unformatted
+ *                  original segments for those), the recording Markdown/HTML
+ *                  timestamps link to, and whether every document paragraph is timestamped
+ * @returns The file contents
+ */
+export async function exportSubtitles(
+    subtitles: Subtitle[],
+    format: string,
+    options: ExportSubtitleOptions = {}
+): Promise<string> {
+    const bytes = await exportSubtitlesBinary(subtitles, format, options);
+    return new TextDecoder().decode(bytes);
+}
+
+/**
+ * Render subtitles to a file format as raw bytes. Required for binary formats
+ * such as EBU STL ("stl"); takes the same arguments as `exportSubtitles`.
+ */
+export async function exportSubtitlesBinary(
+    subtitles: Subtitle[],
+    format: string,
+    options: ExportSubtitleOptions = {}
+): Promise<Uint8Array> {
+    const segments: BackendSegment[] = subtitles.map(subtitleToBackendSegment);
+    const buffer = await invoke<ArrayBuffer>("export_subtitles", {
+        segments,
+        format,
+        ...options,
+    });
+    return new Uint8Array(buffer);
+}
+
+/**
+ * Import an existing SRT, WebVTT or ASS file as a transcript.
+ * The result has the same shape as `transcribe_audio`'s, so it can go through
+ * the same processing path (`processTranscriptionResults`).
+ *
+ * @param path - Path to the subtitle file
+ * @returns The imported transcript (segments, originalSegments, speakers)
+ */
+export async function importSubtitles(path: string): Promise<any> {
+    return invoke("import_subtitles", { path });
+}